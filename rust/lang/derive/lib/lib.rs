@@ -0,0 +1,308 @@
+extern crate proc_macro;
+extern crate quote;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{parse_quote, Attribute, Data, DeriveInput, Error, Fields, Generics, Lit, Meta, NestedMeta, Path, Result};
+
+/// implement `lang_lib::object::convert::ToObject`
+/// structs with named fields become hashes, enums become variant values of the enum type
+/// given by `lang_lib::object::convert::EnumType`, which is implemented as well
+#[proc_macro_derive(IntoObject, attributes(object))]
+pub fn into_object_derive(input: TokenStream) -> TokenStream {
+    let ast = syn::parse_macro_input!(input as DeriveInput);
+    expand(impl_into_object(&ast))
+}
+
+/// implement `lang_lib::object::convert::FromObject`
+/// structs are read from hashes or struct instances, enums from variant values of an enum with the same name
+#[proc_macro_derive(FromObject, attributes(object))]
+pub fn from_object_derive(input: TokenStream) -> TokenStream {
+    let ast = syn::parse_macro_input!(input as DeriveInput);
+    expand(impl_from_object(&ast))
+}
+
+fn expand(result: Result<TokenStream2>) -> TokenStream {
+    result.unwrap_or_else(Error::into_compile_error).into()
+}
+
+/// options of an `#[object(...)]` attribute
+/// (`#[lang(...)]` would clash with the built-in attribute for lang items)
+#[derive(Default)]
+struct Options {
+    // `rename = "..."`
+    rename: Option<String>,
+    // `skip`
+    skip: bool,
+}
+
+/// where an attribute is used, not every option is supported everywhere
+#[derive(PartialEq)]
+enum Position {
+    Container,
+    Variant,
+    Field,
+}
+
+fn parse_options(attrs: &[Attribute], position: Position) -> Result<Options> {
+    let mut options = Options::default();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("object")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(Error::new(meta.span(), "expected `#[object(...)]`")),
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.path.is_ident("rename") => match nv.lit {
+                    Lit::Str(ref name) => options.rename = Some(name.value()),
+                    ref lit => return Err(Error::new(lit.span(), "expected a string, e.g. `rename = \"name\"`")),
+                },
+                NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("skip") => {
+                    if position != Position::Field {
+                        return Err(Error::new(path.span(), "`skip` is only supported on fields"));
+                    }
+                    options.skip = true;
+                }
+                nested => {
+                    return Err(Error::new(nested.span(), "unknown object attribute, expected `rename = \"...\"` or `skip`"));
+                }
+            }
+        }
+    }
+    Ok(options)
+}
+
+/// a field of a struct or an enum variant
+struct Field {
+    // the field in rust, an identifier or an index
+    member: syn::Member,
+    // binding used when destructuring a variant
+    binding: syn::Ident,
+    // name of the field in the language
+    name: String,
+    skip: bool,
+}
+
+fn parse_fields(fields: &Fields) -> Result<Vec<Field>> {
+    fields.iter().enumerate().map(|(i, field)| {
+        let options = parse_options(&field.attrs, Position::Field)?;
+        let (member, name, binding) = match field.ident {
+            Some(ref ident) => (syn::Member::Named(ident.clone()), ident.to_string(), ident.clone()),
+            None => (syn::Member::Unnamed(i.into()), i.to_string(), format_ident!("field{}", i)),
+        };
+        Ok(Field {
+            member,
+            binding,
+            name: options.rename.unwrap_or(name),
+            skip: options.skip,
+        })
+    }).collect()
+}
+
+/// a variant of an enum
+struct Variant {
+    ident: syn::Ident,
+    name: String,
+    fields: Fields,
+    parsed: Vec<Field>,
+}
+
+fn parse_variants(data: &syn::DataEnum) -> Result<Vec<Variant>> {
+    data.variants.iter().map(|variant| {
+        let options = parse_options(&variant.attrs, Position::Variant)?;
+        Ok(Variant {
+            ident: variant.ident.clone(),
+            name: options.rename.unwrap_or_else(|| variant.ident.to_string()),
+            fields: variant.fields.clone(),
+            parsed: parse_fields(&variant.fields)?,
+        })
+    }).collect()
+}
+
+/// named fields of a struct, other structs are not supported
+fn struct_fields(ast: &DeriveInput, data: &syn::DataStruct) -> Result<Vec<Field>> {
+    match data.fields {
+        Fields::Named(_) => parse_fields(&data.fields),
+        _ => Err(Error::new(ast.ident.span(), "only structs with named fields and enums can be converted to objects")),
+    }
+}
+
+/// add `trait_path` bounds to the type parameters
+fn add_bounds(generics: &Generics, trait_path: &Path) -> Generics {
+    let mut generics = generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(#trait_path));
+    }
+    generics
+}
+
+fn type_name(ast: &DeriveInput) -> Result<String> {
+    let options = parse_options(&ast.attrs, Position::Container)?;
+    Ok(options.rename.unwrap_or_else(|| ast.ident.to_string()))
+}
+
+fn impl_into_object(ast: &DeriveInput) -> Result<TokenStream2> {
+    let name = &ast.ident;
+    let type_name = type_name(ast)?;
+    let trait_path: Path = parse_quote!(::lang_lib::object::convert::ToObject);
+    let generics = add_bounds(&ast.generics, &trait_path);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    match ast.data {
+        Data::Struct(ref data) => {
+            let entries = struct_fields(ast, data)?.into_iter().filter(|field| !field.skip).map(|field| {
+                let member = field.member;
+                let key = field.name;
+                quote! {
+                    (
+                        ::lang_lib::object::objects::Object::String(#key.to_string()),
+                        #trait_path::to_object(&self.#member),
+                    )
+                }
+            });
+            Ok(quote! {
+                impl #impl_generics #trait_path for #name #ty_generics #where_clause {
+                    fn to_object(&self) -> ::lang_lib::object::objects::Object {
+                        ::lang_lib::object::objects::Object::Hash(vec![#(#entries),*].into_iter().collect())
+                    }
+                }
+            })
+        }
+        Data::Enum(ref data) => {
+            let variants = parse_variants(data)?;
+            let definitions = variants.iter().map(|variant| {
+                let variant_name = &variant.name;
+                let fields = variant.parsed.iter().filter(|field| !field.skip).map(|field| &field.name);
+                quote! { (#variant_name.to_string(), vec![#(#fields.to_string()),*]) }
+            });
+            let arms = variants.iter().enumerate().map(|(tag, variant)| {
+                let ident = &variant.ident;
+                let bindings = variant.parsed.iter().map(|field| &field.binding);
+                let pattern = match variant.fields {
+                    // named fields are bound to their own names
+                    Fields::Named(_) => quote! { #name::#ident { #(#bindings),* } },
+                    Fields::Unnamed(_) => quote! { #name::#ident(#(#bindings),*) },
+                    Fields::Unit => quote! { #name::#ident },
+                };
+                let values = variant.parsed.iter().filter(|field| !field.skip).map(|field| {
+                    let binding = &field.binding;
+                    quote! { #trait_path::to_object(#binding) }
+                });
+                quote! {
+                    #[allow(unused_variables)]
+                    #pattern => ::lang_lib::object::convert::enum_value(def, #tag, vec![#(#values),*]),
+                }
+            });
+            Ok(quote! {
+                impl #impl_generics ::lang_lib::object::convert::EnumType for #name #ty_generics #where_clause {
+                    fn enum_def() -> ::std::rc::Rc<::lang_lib::object::objects::EnumDef> {
+                        ::std::thread_local! {
+                            static DEF: ::std::rc::Rc<::lang_lib::object::objects::EnumDef> =
+                                ::std::rc::Rc::new(::lang_lib::object::objects::EnumDef {
+                                    name: #type_name.to_string(),
+                                    variants: vec![#(#definitions),*],
+                                });
+                        }
+                        DEF.with(|def| def.clone())
+                    }
+                }
+
+                impl #impl_generics #trait_path for #name #ty_generics #where_clause {
+                    fn to_object(&self) -> ::lang_lib::object::objects::Object {
+                        let def = <Self as ::lang_lib::object::convert::EnumType>::enum_def();
+                        match self {
+                            #(#arms)*
+                        }
+                    }
+                }
+            })
+        }
+        Data::Union(ref data) => Err(Error::new(data.union_token.span, "unions can not be converted to objects")),
+    }
+}
+
+/// expression reading the non skipped fields in order from the iterator `values`,
+/// which holds the payload of a variant
+fn variant_constructor(name: &syn::Ident, variant: &Variant, type_name: &str) -> TokenStream2 {
+    let ident = &variant.ident;
+    let field_values = variant.parsed.iter().map(|field| {
+        if field.skip {
+            return quote! { ::std::default::Default::default() };
+        }
+        let path = format!("{}.{}", type_name, field.name);
+        quote! {
+            ::lang_lib::object::convert::FromObject::from_object(values.next().unwrap())
+                .map_err(|err| ::lang_lib::object::convert::field_error(#path, err))?
+        }
+    });
+    match variant.fields {
+        Fields::Named(_) => {
+            let members = variant.parsed.iter().map(|field| &field.member);
+            quote! { #name::#ident { #(#members: #field_values),* } }
+        }
+        Fields::Unnamed(_) => quote! { #name::#ident(#(#field_values),*) },
+        Fields::Unit => quote! { #name::#ident },
+    }
+}
+
+fn impl_from_object(ast: &DeriveInput) -> Result<TokenStream2> {
+    let name = &ast.ident;
+    let type_name = type_name(ast)?;
+    let trait_path: Path = parse_quote!(::lang_lib::object::convert::FromObject);
+    let generics = add_bounds(&ast.generics, &trait_path);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let body = match ast.data {
+        Data::Struct(ref data) => {
+            let fields = struct_fields(ast, data)?.into_iter().map(|field| {
+                let member = field.member;
+                if field.skip {
+                    return quote! { #member: ::std::default::Default::default() };
+                }
+                let key = field.name;
+                let path = format!("{}.{}", type_name, key);
+                quote! {
+                    #member: #trait_path::from_object(
+                        ::lang_lib::object::convert::struct_field(&object, #type_name, #key)?
+                    ).map_err(|err| ::lang_lib::object::convert::field_error(#path, err))?
+                }
+            });
+            quote! {
+                Ok(#name { #(#fields),* })
+            }
+        }
+        Data::Enum(ref data) => {
+            let variants = parse_variants(data)?;
+            let arms = variants.iter().map(|variant| {
+                let variant_name = &variant.name;
+                let payload = variant.parsed.iter().filter(|field| !field.skip).count();
+                let constructor = variant_constructor(name, variant, &type_name);
+                quote! {
+                    #variant_name => {
+                        #[allow(unused_mut, unused_variables)]
+                        let mut values = ::lang_lib::object::convert::payload(#type_name, #variant_name, values, #payload)?
+                            .into_iter();
+                        Ok(#constructor)
+                    }
+                }
+            });
+            quote! {
+                let (variant, values) = ::lang_lib::object::convert::variant_parts(object, #type_name)?;
+                match variant.as_str() {
+                    #(#arms)*
+                    variant => Err(::lang_lib::object::convert::unknown_variant(#type_name, variant)),
+                }
+            }
+        }
+        Data::Union(ref data) => return Err(Error::new(data.union_token.span, "unions can not be converted from objects")),
+    };
+    Ok(quote! {
+        impl #impl_generics #trait_path for #name #ty_generics #where_clause {
+            fn from_object(
+                object: ::lang_lib::object::objects::Object,
+            ) -> ::std::result::Result<Self, ::lang_lib::evaluator::error::RuntimeError> {
+                #body
+            }
+        }
+    })
+}
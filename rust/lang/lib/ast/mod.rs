@@ -1,4 +1,6 @@
-use crate::token::Token;
+use std::fmt::{self, Display, Formatter};
+
+use crate::token::{Float, Span, Token};
 
 /// Program is ast root Node
 pub type Program = Vec<Stmt>;
@@ -15,6 +17,41 @@ pub enum Stmt {
     ReturnStmt(Expr),
     // 表达式语句
     ExprStmt(Expr),
+    // throw 语句
+    ThrowStmt(Expr),
+    // 结构体声明语句
+    StructStmt {
+        // 结构体名称
+        name: Identifier,
+        // 字段列表
+        fields: Vec<Identifier>,
+        // 方法列表，方法体为 FnExpr
+        methods: Vec<(Identifier, Expr)>,
+    },
+    // 枚举声明语句
+    EnumStmt {
+        // 枚举名称
+        name: Identifier,
+        // 变体名称及其负载字段
+        variants: Vec<(Identifier, Vec<Identifier>)>,
+    },
+    // 字段赋值语句，形如 `expr.field = expr`
+    AssignStmt(Expr, Expr),
+    // 导入语句，形如 `import "path" as ident`
+    ImportStmt(String, Identifier),
+    // 导出声明，包装 let，struct 或者 enum 语句
+    ExportStmt(Box<Stmt>),
+    // for 循环语句，形如 `for (ident in expr) { ... }`
+    ForStmt {
+        // 循环变量
+        ident: Identifier,
+        // 被迭代的表达式
+        iterable: Expr,
+        // 循环体
+        body: BlockStatement,
+    },
+    // yield 语句，只能出现在生成器函数体中
+    YieldStmt(Expr),
 }
 
 /// 表达式
@@ -35,6 +72,22 @@ pub enum Expr {
         // condition为false的语句块
         alternative: Option<BlockStatement>,
     },
+    // try catch finally 表达式
+    TryExpr {
+        // try 语句块
+        body: BlockStatement,
+        // catch 绑定的标识符及语句块
+        catch: Option<(Identifier, BlockStatement)>,
+        // finally 语句块
+        finally: Option<BlockStatement>,
+    },
+    // match 表达式
+    MatchExpr {
+        // 被匹配的值
+        subject: Box<Expr>,
+        // 匹配分支
+        arms: Vec<(Pattern, BlockStatement)>,
+    },
     // 函数表达式
     FnExpr {
         // 函数参数
@@ -42,10 +95,24 @@ pub enum Expr {
         // 函数体
         body: BlockStatement,
     },
+    // 生成器函数表达式，形如 `fn*(a, b) { yield a; }`，调用时返回一个迭代器
+    GeneratorExpr {
+        // 函数参数
+        parameters: Vec<Identifier>,
+        // 函数体，包含 yield 语句
+        body: BlockStatement,
+    },
+    // 宏表达式，形如 `macro(a, b) { quote(...) }`，只在展开阶段调用
+    MacroExpr {
+        // 宏参数，展开时绑定为参数的 quote
+        parameters: Vec<Identifier>,
+        // 宏体，需要返回一个 quote
+        body: BlockStatement,
+    },
     // Array Literal
     ArrayExpr(Vec<Expr>),
     // HashMap Literal
-    HashExpr(Vec<(Literal, Expr)>),
+    HashExpr(Vec<(Expr, Expr)>),
     // 中缀表达式
     InfixExpr(Infix, Box<Expr>, Box<Expr>),
     // 调用表达式
@@ -54,6 +121,8 @@ pub enum Expr {
         function: Box<Expr>,
         // 函数调用入参
         arguments: Vec<Expr>,
+        // 调用位置
+        span: Span,
     },
     // 索引表达式
     IndexExpr {
@@ -63,6 +132,28 @@ pub enum Expr {
         // 索引值
         index: Box<Expr>,
     },
+    // 字段访问表达式
+    FieldExpr {
+        // 被访问体
+        object: Box<Expr>,
+        // 字段名
+        field: Identifier,
+    },
+}
+
+/// match 分支的模式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    // `_`，匹配任意值
+    Wildcard,
+    // 匹配任意值并绑定到标识符
+    Binding(Identifier),
+    // 匹配相等的字面量
+    Literal(Literal),
+    // 匹配结构体实例，形如 `Point { x, y: 0 }`
+    Struct(Identifier, Vec<(Identifier, Pattern)>),
+    // 匹配枚举变体，形如 `Circle(r)` 或者 `Shape.Circle(r)`，无负载的变体可省略括号
+    Variant(Option<Identifier>, Identifier, Vec<Pattern>),
 }
 
 /// 前缀类型
@@ -105,6 +196,7 @@ pub enum Infix {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Literal {
     IntLiteral(i64),
+    FloatLiteral(Float),
     BoolLiteral(bool),
     StringLiteral(String),
 }
@@ -113,6 +205,234 @@ pub enum Literal {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Identifier(pub String);
 
+/// 以逗号分隔输出
+fn write_list<T: Display>(f: &mut Formatter<'_>, items: &[T]) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", item)?;
+    }
+    Ok(())
+}
+
+/// 输出语句块，形如 `{ stmt stmt }`
+fn write_block(f: &mut Formatter<'_>, block: &[Stmt]) -> fmt::Result {
+    write!(f, "{{")?;
+    for stmt in block {
+        write!(f, " {}", stmt)?;
+    }
+    write!(f, " }}")
+}
+
+/// 输出为可以重新解析的源码，表达式语句以 `;` 结尾
+impl Display for Stmt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            Stmt::LetStmt(ref ident, ref expr) => write!(f, "let {} = {};", ident, expr),
+            Stmt::ReturnStmt(ref expr) => write!(f, "return {};", expr),
+            Stmt::ExprStmt(ref expr) => write!(f, "{};", expr),
+            Stmt::ThrowStmt(ref expr) => write!(f, "throw {};", expr),
+            Stmt::StructStmt { ref name, ref fields, ref methods } => {
+                write!(f, "struct {} {{ ", name)?;
+                write_list(f, fields)?;
+                for (method, function) in methods {
+                    match *function {
+                        Expr::FnExpr { ref parameters, ref body } => {
+                            write!(f, "; fn {}(", method)?;
+                            write_list(f, parameters)?;
+                            write!(f, ") ")?;
+                            write_block(f, body)?;
+                        }
+                        ref expr => write!(f, "; {}", expr)?,
+                    }
+                }
+                write!(f, " }}")
+            }
+            Stmt::EnumStmt { ref name, ref variants } => {
+                write!(f, "enum {} {{ ", name)?;
+                for (i, (variant, fields)) in variants.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", variant)?;
+                    if !fields.is_empty() {
+                        write!(f, "(")?;
+                        write_list(f, fields)?;
+                        write!(f, ")")?;
+                    }
+                }
+                write!(f, " }}")
+            }
+            Stmt::AssignStmt(ref target, ref value) => write!(f, "{} = {};", target, value),
+            Stmt::ImportStmt(ref path, ref alias) => {
+                write!(f, "import {} as {};", Literal::StringLiteral(path.clone()), alias)
+            }
+            Stmt::ExportStmt(ref stmt) => write!(f, "export {}", stmt),
+            Stmt::ForStmt { ref ident, ref iterable, ref body } => {
+                write!(f, "for ({} in {}) ", ident, iterable)?;
+                write_block(f, body)
+            }
+            Stmt::YieldStmt(ref expr) => write!(f, "yield {};", expr),
+        }
+    }
+}
+
+/// 输出为可以重新解析的源码，前缀、中缀以及索引表达式带有括号
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            Expr::IdentExpr(ref ident) => write!(f, "{}", ident),
+            Expr::LiteralExpr(ref literal) => write!(f, "{}", literal),
+            Expr::PrefixExpr(ref prefix, ref right) => write!(f, "({}{})", prefix, right),
+            Expr::InfixExpr(ref infix, ref left, ref right) => write!(f, "({} {} {})", left, infix, right),
+            Expr::IfExpr { ref cond, ref consequence, ref alternative } => {
+                write!(f, "if ({}) ", cond)?;
+                write_block(f, consequence)?;
+                if let Some(ref alternative) = *alternative {
+                    write!(f, " else ")?;
+                    write_block(f, alternative)?;
+                }
+                Ok(())
+            }
+            Expr::TryExpr { ref body, ref catch, ref finally } => {
+                write!(f, "try ")?;
+                write_block(f, body)?;
+                if let Some((ref ident, ref block)) = *catch {
+                    write!(f, " catch ({}) ", ident)?;
+                    write_block(f, block)?;
+                }
+                if let Some(ref block) = *finally {
+                    write!(f, " finally ")?;
+                    write_block(f, block)?;
+                }
+                Ok(())
+            }
+            Expr::MatchExpr { ref subject, ref arms } => {
+                write!(f, "match ({}) {{", subject)?;
+                for (pattern, body) in arms {
+                    write!(f, " {} => ", pattern)?;
+                    write_block(f, body)?;
+                }
+                write!(f, " }}")
+            }
+            Expr::FnExpr { ref parameters, ref body } => {
+                write!(f, "fn(")?;
+                write_list(f, parameters)?;
+                write!(f, ") ")?;
+                write_block(f, body)
+            }
+            Expr::GeneratorExpr { ref parameters, ref body } => {
+                write!(f, "fn*(")?;
+                write_list(f, parameters)?;
+                write!(f, ") ")?;
+                write_block(f, body)
+            }
+            Expr::MacroExpr { ref parameters, ref body } => {
+                write!(f, "macro(")?;
+                write_list(f, parameters)?;
+                write!(f, ") ")?;
+                write_block(f, body)
+            }
+            Expr::ArrayExpr(ref items) => {
+                write!(f, "[")?;
+                write_list(f, items)?;
+                write!(f, "]")
+            }
+            Expr::HashExpr(ref pairs) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in pairs.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", key, value)?;
+                }
+                write!(f, "}}")
+            }
+            Expr::CallExpr { ref function, ref arguments, .. } => {
+                write!(f, "{}(", function)?;
+                write_list(f, arguments)?;
+                write!(f, ")")
+            }
+            Expr::IndexExpr { ref left, ref index } => write!(f, "({}[{}])", left, index),
+            Expr::FieldExpr { ref object, ref field } => write!(f, "{}.{}", object, field),
+        }
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            Pattern::Wildcard => write!(f, "_"),
+            Pattern::Binding(ref ident) => write!(f, "{}", ident),
+            Pattern::Literal(ref literal) => write!(f, "{}", literal),
+            Pattern::Struct(ref name, ref fields) => {
+                write!(f, "{} {{ ", name)?;
+                for (i, (field, pattern)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", field, pattern)?;
+                }
+                write!(f, " }}")
+            }
+            Pattern::Variant(ref enum_name, ref variant, ref patterns) => {
+                if let Some(ref enum_name) = *enum_name {
+                    write!(f, "{}.", enum_name)?;
+                }
+                write!(f, "{}(", variant)?;
+                write_list(f, patterns)?;
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl Display for Prefix {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            Prefix::Plus => write!(f, "+"),
+            Prefix::Minus => write!(f, "-"),
+            Prefix::Not => write!(f, "!"),
+        }
+    }
+}
+
+impl Display for Infix {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            Infix::Plus => write!(f, "+"),
+            Infix::Minus => write!(f, "-"),
+            Infix::Divide => write!(f, "/"),
+            Infix::Multiply => write!(f, "*"),
+            Infix::Equal => write!(f, "=="),
+            Infix::NotEqual => write!(f, "!="),
+            Infix::GreaterThanEqual => write!(f, ">="),
+            Infix::LessThanEqual => write!(f, "<="),
+            Infix::GreaterThan => write!(f, ">"),
+            Infix::LessThan => write!(f, "<"),
+        }
+    }
+}
+
+/// 字符串中的 `\` 与 `"` 需要转义
+impl Display for Literal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            Literal::IntLiteral(i) => write!(f, "{}", i),
+            Literal::FloatLiteral(Float(x)) => write!(f, "{:?}", x),
+            Literal::BoolLiteral(b) => write!(f, "{}", b),
+            Literal::StringLiteral(ref s) => write!(f, "\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"")),
+        }
+    }
+}
+
+impl Display for Identifier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 /// 优先级定义
 #[derive(PartialEq, PartialOrd, Debug, Clone)]
 pub enum Precedence {
@@ -131,6 +451,8 @@ pub enum Precedence {
     PCall,
     // a[Index]
     PIndex,
+    // a.field
+    PField,
 }
 
 /// 优先级解析
@@ -149,6 +471,7 @@ pub fn precedences(t: &Token) -> (Precedence, Option<Infix>) {
 
         Token::LParen => (Precedence::PCall, None),
         Token::LBracket => (Precedence::PIndex, None),
+        Token::Dot => (Precedence::PField, None),
         _ => (Precedence::PLowest, None),
     }
 }
\ No newline at end of file
@@ -0,0 +1,111 @@
+use std::env;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// access of the I/O builtins, everything is denied by default
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// directories whose files and subdirectories can be read
+    pub read_roots: Vec<PathBuf>,
+    /// directories whose files and subdirectories can be written
+    pub write_roots: Vec<PathBuf>,
+    /// whether `read_stdin` may read the standard input
+    pub stdin: bool,
+    /// whether `env` may read environment variables
+    pub env: bool,
+}
+
+impl Capabilities {
+    /// the resolved path if it is inside one of the read roots, a path which can not be resolved
+    /// is checked lexically first, so that the error does not tell whether a path outside of the roots exists
+    pub(crate) fn check_read(&self, path: &str) -> Result<PathBuf, String> {
+        match resolve(path) {
+            Ok(resolved) => check(&self.read_roots, path, resolved, "reading"),
+            Err(err) => {
+                check(&self.read_roots, path, resolve_path(Path::new(path)), "reading")?;
+                Err(format!("{}: {}", path, err))
+            }
+        }
+    }
+
+    /// whether a path inside the read roots exists, a missing path is checked lexically
+    /// so that scripts can not probe paths outside of the roots
+    pub(crate) fn check_exists(&self, path: &str) -> Result<bool, String> {
+        match resolve(path) {
+            Ok(resolved) => check(&self.read_roots, path, resolved, "reading").map(|_| true),
+            Err(_) => check(&self.read_roots, path, resolve_path(Path::new(path)), "reading").map(|_| false),
+        }
+    }
+
+    /// the resolved path if it is inside one of the write roots,
+    /// the file itself does not need to exist
+    pub(crate) fn check_write(&self, path: &str) -> Result<PathBuf, String> {
+        match resolve_parent(path) {
+            Ok(resolved) => check(&self.write_roots, path, resolved, "writing"),
+            Err(err) => {
+                check(&self.write_roots, path, resolve_path(Path::new(path)), "writing")?;
+                Err(format!("{}: {}", path, err))
+            }
+        }
+    }
+}
+
+fn check(roots: &[PathBuf], path: &str, resolved: PathBuf, access: &str) -> Result<PathBuf, String> {
+    if roots.is_empty() {
+        return Err(format!("{} files is not allowed", access));
+    }
+    // the roots are resolved as well, a root which does not exist allows nothing
+    let allowed = roots.iter()
+        .filter_map(|root| root.canonicalize().ok())
+        .any(|root| resolved.starts_with(root));
+    if allowed {
+        Ok(resolved)
+    } else {
+        Err(format!("{} {} is not allowed", access, path))
+    }
+}
+
+/// absolute path without symbolic links, `..` and `.`, so that it can be compared with the roots
+fn resolve(path: &str) -> io::Result<PathBuf> {
+    Path::new(path).canonicalize()
+}
+
+/// like `resolve`, but only the parent directory has to exist
+fn resolve_parent(path: &str) -> io::Result<PathBuf> {
+    let path = Path::new(path);
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => {
+            let parent = if parent.as_os_str().is_empty() { Path::new(".") } else { parent };
+            let resolved = parent.canonicalize()?.join(name);
+            // an existing file may be a link out of the roots
+            match resolved.canonicalize() {
+                Ok(target) => Ok(target),
+                Err(_) => Ok(resolved),
+            }
+        }
+        _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "not a file path")),
+    }
+}
+
+/// like `resolve`, but the path does not need to exist: the longest existing ancestor is resolved
+/// and the rest of the path is appended with `..` and `.` removed lexically
+pub(crate) fn resolve_path(path: &Path) -> PathBuf {
+    let absolute = match env::current_dir() {
+        Ok(dir) if path.is_relative() => dir.join(path),
+        _ => path.to_path_buf(),
+    };
+    let Some((mut resolved, ancestor)) = absolute.ancestors()
+        .find_map(|ancestor| Some((ancestor.canonicalize().ok()?, ancestor))) else {
+        return absolute;
+    };
+    for component in absolute.strip_prefix(ancestor).unwrap_or(Path::new("")).components() {
+        match component {
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::Normal(name) => resolved.push(name),
+            _ => {}
+        }
+    }
+    resolved
+}
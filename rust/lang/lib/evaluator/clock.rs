@@ -0,0 +1,92 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// time source of the time builtins
+pub trait Clock {
+    /// milliseconds since the unix epoch
+    fn now(&self) -> i64;
+    /// milliseconds since a fixed point in time, never decreases
+    fn monotonic(&self) -> f64;
+    /// block for `ms` milliseconds
+    fn sleep(&self, ms: u64);
+}
+
+/// the clock of the operating system
+#[derive(Clone, Debug)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        SystemClock { start: Instant::now() }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> i64 {
+        match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(since) => i64::try_from(since.as_millis()).unwrap_or(i64::MAX),
+            Err(before) => -i64::try_from(before.duration().as_millis()).unwrap_or(i64::MAX),
+        }
+    }
+
+    fn monotonic(&self) -> f64 {
+        self.start.elapsed().as_secs_f64() * 1000.0
+    }
+
+    fn sleep(&self, ms: u64) {
+        thread::sleep(Duration::from_millis(ms));
+    }
+}
+
+/// clock which only moves when it is told to, `sleep` advances it instead of blocking,
+/// clones share the same time so a test can keep one and give the other to the evaluator
+#[derive(Clone, Debug, Default)]
+pub struct ManualClock {
+    now: Rc<Cell<i64>>,
+    elapsed: Rc<Cell<u64>>,
+}
+
+impl ManualClock {
+    /// clock starting at `now` milliseconds since the unix epoch
+    pub fn new(now: i64) -> Self {
+        ManualClock {
+            now: Rc::new(Cell::new(now)),
+            elapsed: Rc::new(Cell::new(0)),
+        }
+    }
+
+    /// set the wall-clock time, the monotonic time is not changed
+    pub fn set(&self, now: i64) {
+        self.now.set(now);
+    }
+
+    /// move both the wall-clock and the monotonic time forward
+    pub fn advance(&self, ms: u64) {
+        self.now.set(self.now.get().saturating_add_unsigned(ms));
+        self.elapsed.set(self.elapsed.get().saturating_add(ms));
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> i64 {
+        self.now.get()
+    }
+
+    fn monotonic(&self) -> f64 {
+        self.elapsed.get() as f64
+    }
+
+    fn sleep(&self, ms: u64) {
+        self.advance(ms);
+    }
+}
@@ -0,0 +1,190 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use crate::evaluator::limits::LimitExceeded;
+use crate::evaluator::EvalResult;
+use crate::object::objects::Object;
+use crate::token::Span;
+
+/// runtime failures of an evaluation
+#[derive(Clone, Debug, PartialEq)]
+pub enum RuntimeError {
+    // operand of a wrong type, e.g. `!5` or `5()`
+    TypeMismatch(String),
+    // identifier which is not bound in the environment
+    UnknownIdentifier(String),
+    // wrong number of arguments for a function call
+    Arity { expected: usize, given: usize },
+    // invalid index or index target
+    Index(String),
+    // value which can not be used as a hash key
+    HashKey(String),
+    // unknown field or method, or field access on a value without fields
+    Field(String),
+    // error message returned by a builtin function
+    Builtin(String),
+    // integer overflow or division by zero
+    Arithmetic(String),
+    // source given to `eval` which can not be parsed
+    Syntax(String),
+    // module which can not be found or read, cyclic imports and misplaced exports
+    Import(String),
+    // value thrown by the script
+    Thrown(Object),
+    // nested function calls exceeded `max_depth`
+    RecursionDepth,
+    // execution budget of `EvalLimits` exceeded
+    Limit(LimitExceeded),
+    // `exit(code)` called by the script
+    Exit(i32),
+    // `yield` which is not in the body of a generator function
+    Yield,
+    // `yield` in a generator body which is nested in an expression instead of being a statement
+    NestedYield,
+}
+
+impl RuntimeError {
+    /// name of the error kind
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            RuntimeError::TypeMismatch(_) => "TypeMismatch",
+            RuntimeError::UnknownIdentifier(_) => "UnknownIdentifier",
+            RuntimeError::Arity { .. } => "Arity",
+            RuntimeError::Index(_) => "Index",
+            RuntimeError::HashKey(_) => "HashKey",
+            RuntimeError::Field(_) => "Field",
+            RuntimeError::Builtin(_) => "Builtin",
+            RuntimeError::Arithmetic(_) => "Arithmetic",
+            RuntimeError::Syntax(_) => "Syntax",
+            RuntimeError::Import(_) => "Import",
+            RuntimeError::Thrown(_) => "Thrown",
+            RuntimeError::RecursionDepth => "RecursionDepth",
+            RuntimeError::Limit(_) => "Limit",
+            RuntimeError::Exit(_) => "Exit",
+            RuntimeError::Yield => "Yield",
+            RuntimeError::NestedYield => "NestedYield",
+        }
+    }
+
+    /// whether the error can be handled by `try catch`, exhausted execution budgets, exits
+    /// and misplaced `yield`s can not
+    pub fn is_catchable(&self) -> bool {
+        !matches!(*self, RuntimeError::Limit(_) | RuntimeError::Exit(_) | RuntimeError::Yield | RuntimeError::NestedYield)
+    }
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            RuntimeError::TypeMismatch(ref s)
+            | RuntimeError::Index(ref s)
+            | RuntimeError::HashKey(ref s)
+            | RuntimeError::Field(ref s)
+            | RuntimeError::Builtin(ref s)
+            | RuntimeError::Arithmetic(ref s)
+            | RuntimeError::Syntax(ref s)
+            | RuntimeError::Import(ref s) => write!(f, "{}", s),
+            RuntimeError::UnknownIdentifier(ref name) => write!(f, "identifier not found: {}", name),
+            RuntimeError::Arity { expected, given } => write!(
+                f,
+                "wrong number of arguments: {} expected but {} given",
+                expected, given
+            ),
+            RuntimeError::Thrown(ref o) => write!(f, "{}", o),
+            RuntimeError::RecursionDepth => write!(f, "maximum recursion depth exceeded"),
+            RuntimeError::Limit(ref limit) => write!(f, "{}", limit),
+            RuntimeError::Exit(code) => write!(f, "exit with status {}", code),
+            RuntimeError::Yield => write!(f, "yield outside of a generator"),
+            RuntimeError::NestedYield => write!(f, "yield must be a statement inside a generator"),
+        }
+    }
+}
+
+impl Error for RuntimeError {}
+
+/// result of an integer operation which does not fit into an integer
+pub fn overflow() -> RuntimeError {
+    RuntimeError::Arithmetic("integer overflow".to_string())
+}
+
+/// process exit status for the result of a program: the code given to `exit(code)`,
+/// an integer result, `1` for other errors and `0` otherwise
+pub fn exit_status(result: &EvalResult) -> i32 {
+    match *result {
+        Ok(Object::Integer(code)) => i32::try_from(code).unwrap_or(1),
+        Ok(_) => 0,
+        Err(EvalError { error: RuntimeError::Exit(code), .. }) => code,
+        Err(_) => 1,
+    }
+}
+
+/// a function call on the call stack
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    // name of the called function, `<anonymous>` for function literals
+    pub function: String,
+    // position of the call
+    pub span: Span,
+}
+
+/// a runtime error with the call stack between the failure and the top level
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvalError {
+    pub error: RuntimeError,
+    // innermost frame first
+    pub stack: Vec<Frame>,
+}
+
+impl EvalError {
+    /// add a frame while the error unwinds out of a function call
+    pub fn push_frame(mut self, frame: Frame) -> Self {
+        self.stack.push(frame);
+        self
+    }
+
+    /// the error as seen by a `catch` block: a hash with `message`, `kind` and `stack` fields,
+    /// the stack is an array of `{function, line, column}` hashes, innermost frame first
+    pub fn to_object(&self) -> Object {
+        let stack = self.stack.iter().map(|frame| {
+            Object::Hash(vec![
+                (Object::String("function".to_string()), Object::String(frame.function.clone())),
+                (Object::String("line".to_string()), Object::Integer(frame.span.line as i64)),
+                (Object::String("column".to_string()), Object::Integer(frame.span.column as i64)),
+            ].into_iter().collect())
+        }).collect();
+        Object::Hash(vec![
+            (Object::String("message".to_string()), Object::String(self.error.to_string())),
+            (Object::String("kind".to_string()), Object::String(self.error.kind().to_string())),
+            (Object::String("stack".to_string()), Object::Array(stack)),
+        ].into_iter().collect())
+    }
+}
+
+impl From<RuntimeError> for EvalError {
+    fn from(error: RuntimeError) -> Self {
+        EvalError {
+            error,
+            stack: vec![],
+        }
+    }
+}
+
+impl From<LimitExceeded> for EvalError {
+    fn from(limit: LimitExceeded) -> Self {
+        RuntimeError::Limit(limit).into()
+    }
+}
+
+impl Display for EvalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if !self.stack.is_empty() {
+            writeln!(f, "Traceback (most recent call last):")?;
+            for frame in self.stack.iter().rev() {
+                writeln!(f, "  {} called at {}", frame.function, frame.span)?;
+            }
+        }
+        write!(f, "{}: {}", self.error.kind(), self.error)
+    }
+}
+
+impl Error for EvalError {}
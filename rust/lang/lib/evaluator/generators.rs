@@ -0,0 +1,382 @@
+use std::cell::RefCell;
+use std::mem;
+use std::ops::ControlFlow;
+use std::rc::Rc;
+use std::vec;
+
+use crate::ast::{BlockStatement, Expr, Identifier, Stmt};
+use crate::evaluator::error::{EvalError, Frame, RuntimeError};
+use crate::evaluator::{EvalResult, Evaluator, STACK_RED_ZONE, STACK_SEGMENT_SIZE};
+use crate::object::environment::Environment;
+use crate::object::iterators::{iterator_object, Iter};
+use crate::object::objects::Object;
+use crate::object::objects::Object::Return;
+
+/// a call of a generator function, the body runs until the next `yield` each time an item is pulled
+#[derive(Debug)]
+pub struct GeneratorState {
+    // frame of the call, added to the errors raised by the body
+    frame: Frame,
+    // env where the body is suspended, the env of the call holding the parameters
+    // or the env of a loop iteration or match arm
+    env: Rc<RefCell<Environment>>,
+    // blocks being run, the function body first and the innermost block last
+    blocks: Vec<Block>,
+}
+
+/// a block of a suspended generator body
+#[derive(Debug)]
+enum Block {
+    // the remaining statements of a block
+    Statements(vec::IntoIter<Stmt>),
+    // a `for` loop, the body is pushed again for every item
+    Loop {
+        ident: String,
+        items: Rc<RefCell<Iter>>,
+        body: Rc<BlockStatement>,
+    },
+    // a `try` whose body or catch block is above it, the catch block is taken once it is entered
+    Try {
+        catch: Option<(String, BlockStatement)>,
+        finally: Option<BlockStatement>,
+    },
+    // an error raised again once the `finally` block above it has run
+    Raise(EvalError),
+    // a `return` which ends the generator once the `finally` block above it has run
+    Return,
+    // the env of a loop iteration or match arm is used by the blocks above it,
+    // the given env is the current env again once it is left
+    Scope(Rc<RefCell<Environment>>),
+}
+
+impl Evaluator {
+    /// call of a generator function, the body does not run until the first item is pulled
+    pub(crate) fn call_generator(&mut self, frame: Frame, params: Vec<Identifier>, body: Rc<BlockStatement>, env: Rc<RefCell<Environment>>, args: Vec<Object>) -> EvalResult {
+        if args.len() != params.len() {
+            return Err(RuntimeError::Arity {
+                expected: params.len(),
+                given: args.len(),
+            }.into());
+        }
+        let mut generator_env = Environment::new_with_outer(env);
+        for (Identifier(ident), obj) in params.into_iter().zip(args) {
+            generator_env.set(&ident, obj);
+        }
+        let state = GeneratorState {
+            frame,
+            env: Rc::new(RefCell::new(generator_env)),
+            blocks: vec![Block::Statements(BlockStatement::clone(&body).into_iter())],
+        };
+        self.allocate(iterator_object(Iter::Generator(state)))
+    }
+
+    /// the iterator of the value of `for ... in`: the items of an array, the chars of a string,
+    /// the keys of a hash, an iterator itself or an instance with a `next()` method
+    pub(crate) fn iterator(&mut self, object: Object) -> Result<Rc<RefCell<Iter>>, EvalError> {
+        let iter = match object {
+            Object::Iterator(iter) => return Ok(iter),
+            Object::Array(items) => Iter::Items(items.into_iter()),
+            Object::String(s) => Iter::Items(s.chars().map(|c| Object::String(c.to_string())).collect::<Vec<_>>().into_iter()),
+            Object::Hash(hash) => Iter::Items(hash.into_keys().collect::<Vec<_>>().into_iter()),
+            Object::Instance(ref instance) if instance.borrow().def.methods.contains_key("next") => Iter::Protocol(object),
+            o => return Err(RuntimeError::TypeMismatch(format!("{} is not iterable", o)).into()),
+        };
+        Ok(Rc::new(RefCell::new(iter)))
+    }
+
+    /// the next item of an iterator, `None` once it is exhausted
+    pub(crate) fn next_item(&mut self, iter: &Rc<RefCell<Iter>>) -> Result<Option<Object>, EvalError> {
+        self.budget.tick()?;
+        // the state is taken out while the item is computed, so the body of a generator
+        // or a callback may use the iterator without a borrow conflict
+        let mut state = mem::replace(&mut *iter.borrow_mut(), Iter::Running);
+        let result = self.step(&mut state);
+        *iter.borrow_mut() = state;
+        result
+    }
+
+    fn step(&mut self, state: &mut Iter) -> Result<Option<Object>, EvalError> {
+        match *state {
+            Iter::Items(ref mut items) => Ok(items.next()),
+            Iter::Generator(ref mut generator) => self.resume(generator),
+            Iter::Protocol(ref object) => self.next_of(object.clone()),
+            Iter::Map(ref source, ref f) => match self.next_item(source)? {
+                Some(item) => Ok(Some(self.call(f.clone(), vec![item])?)),
+                None => Ok(None),
+            },
+            Iter::Filter(ref source, ref f) => {
+                while let Some(item) = self.next_item(source)? {
+                    let keep = self.call(f.clone(), vec![item.clone()])?;
+                    if self.otb(keep)? {
+                        return Ok(Some(item));
+                    }
+                }
+                Ok(None)
+            }
+            Iter::Take(ref source, ref mut count) => {
+                if *count == 0 {
+                    return Ok(None);
+                }
+                *count -= 1;
+                self.next_item(source)
+            }
+            Iter::Skip(ref source, ref mut count) => {
+                while *count > 0 {
+                    *count -= 1;
+                    if self.next_item(source)?.is_none() {
+                        return Ok(None);
+                    }
+                }
+                self.next_item(source)
+            }
+            Iter::Zip(ref left, ref right) => {
+                let Some(left) = self.next_item(left)? else {
+                    return Ok(None);
+                };
+                let Some(right) = self.next_item(right)? else {
+                    return Ok(None);
+                };
+                Ok(Some(self.allocate(Object::Array(vec![left, right]))?))
+            }
+            Iter::Enumerate(ref source, ref mut index) => {
+                let Some(item) = self.next_item(source)? else {
+                    return Ok(None);
+                };
+                let pair = self.allocate(Object::Array(vec![Object::Integer(*index), item]))?;
+                *index += 1;
+                Ok(Some(pair))
+            }
+            Iter::Running => Err(RuntimeError::TypeMismatch(String::from("iterator is already running")).into()),
+        }
+    }
+
+    /// the next item of an object with a `next()` method, `next()` returns a hash with
+    /// a `value` and a boolean `done`, a missing `done` is `false`
+    fn next_of(&mut self, object: Object) -> Result<Option<Object>, EvalError> {
+        let next = self.field(object, "next")?;
+        match self.call(next, vec![])? {
+            Object::Hash(mut hash) => {
+                let done = hash.swap_remove(&Object::String("done".to_string())).unwrap_or(Object::Boolean(false));
+                if self.otb(done)? {
+                    return Ok(None);
+                }
+                Ok(Some(hash.swap_remove(&Object::String("value".to_string())).unwrap_or(Object::NULL)))
+            }
+            o => Err(RuntimeError::TypeMismatch(format!("next() returned {} instead of a hash with value and done", o)).into()),
+        }
+    }
+
+    /// run a generator body up to its next `yield`, `None` once the body is finished,
+    /// a finished or failed generator stays exhausted
+    fn resume(&mut self, generator: &mut GeneratorState) -> Result<Option<Object>, EvalError> {
+        if generator.blocks.is_empty() {
+            return Ok(None);
+        }
+        if self.depth >= self.max_depth {
+            return Err(RuntimeError::RecursionDepth.into());
+        }
+        self.depth += 1;
+        let current_env = mem::replace(&mut self.env, generator.env.clone());
+        let blocks = &mut generator.blocks;
+        let result = stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT_SIZE, || self.run_blocks(blocks));
+        generator.env = mem::replace(&mut self.env, current_env);
+        self.depth -= 1;
+        match result {
+            Ok(Some(item)) => Ok(Some(item)),
+            Ok(None) => {
+                generator.blocks.clear();
+                Ok(None)
+            }
+            Err(err) => {
+                generator.blocks.clear();
+                Err(err.push_frame(generator.frame.clone()))
+            }
+        }
+    }
+
+    /// run the statements of a generator body, `if`, `match`, `for` and `try` statements are entered
+    /// block by block so a `yield` inside them suspends the body, `return` ends the generator
+    fn run_blocks(&mut self, blocks: &mut Vec<Block>) -> Result<Option<Object>, EvalError> {
+        loop {
+            match self.run_step(blocks) {
+                Ok(ControlFlow::Continue(())) => {}
+                Ok(ControlFlow::Break(item)) => return Ok(item),
+                Err(err) => self.unwind(blocks, nested_yield(err))?,
+            }
+        }
+    }
+
+    /// run the next statement of the innermost block, breaks with the yielded item
+    /// or with `None` once the generator is finished
+    fn run_step(&mut self, blocks: &mut Vec<Block>) -> Result<ControlFlow<Option<Object>>, EvalError> {
+        let stmt = match blocks.last_mut() {
+            None => return Ok(ControlFlow::Break(None)),
+            Some(Block::Statements(statements)) => statements.next(),
+            Some(Block::Loop { ident, items, body }) => {
+                match self.next_item(items)? {
+                    Some(item) => {
+                        let statements = BlockStatement::clone(body).into_iter();
+                        let iteration_env = self.iteration_env(ident, item);
+                        self.enter_scope(blocks, iteration_env, statements);
+                    }
+                    None => {
+                        blocks.pop();
+                    }
+                }
+                return Ok(ControlFlow::Continue(()));
+            }
+            // the body or the catch block is finished
+            Some(Block::Try { finally, .. }) => {
+                let finally = finally.take();
+                blocks.pop();
+                if let Some(block) = finally {
+                    blocks.push(Block::Statements(block.into_iter()));
+                }
+                return Ok(ControlFlow::Continue(()));
+            }
+            // the finally block is finished
+            Some(Block::Raise(_)) => match blocks.pop() {
+                Some(Block::Raise(err)) => return Err(err),
+                _ => unreachable!(),
+            },
+            Some(Block::Return) => {
+                blocks.pop();
+                return Ok(self.finish(blocks));
+            }
+            Some(Block::Scope(_)) => {
+                self.pop_block(blocks);
+                return Ok(ControlFlow::Continue(()));
+            }
+        };
+        let Some(stmt) = stmt else {
+            blocks.pop();
+            return Ok(ControlFlow::Continue(()));
+        };
+        match stmt {
+            Stmt::YieldStmt(expr) => {
+                self.budget.tick()?;
+                return Ok(ControlFlow::Break(Some(self.eval_expr(expr)?)));
+            }
+            Stmt::ForStmt { ident: Identifier(ident), iterable, body } => {
+                self.budget.tick()?;
+                let iterable = self.eval_expr(iterable)?;
+                let items = self.iterator(iterable)?;
+                blocks.push(Block::Loop { ident, items, body: Rc::new(body) });
+            }
+            Stmt::ExprStmt(Expr::IfExpr { cond, consequence, alternative }) => {
+                self.budget.tick()?;
+                let cond_obj = self.eval_expr(*cond)?;
+                let block = if self.otb(cond_obj)? { Some(consequence) } else { alternative };
+                if let Some(block) = block {
+                    blocks.push(Block::Statements(block.into_iter()));
+                }
+            }
+            Stmt::ExprStmt(Expr::MatchExpr { subject, arms }) => {
+                self.budget.tick()?;
+                if let Some((arm_env, body)) = self.match_arm(*subject, arms)? {
+                    self.enter_scope(blocks, arm_env, body.into_iter());
+                }
+            }
+            Stmt::ExprStmt(Expr::TryExpr { body, catch, finally }) => {
+                self.budget.tick()?;
+                let catch = catch.map(|(Identifier(ident), block)| (ident, block));
+                blocks.push(Block::Try { catch, finally });
+                blocks.push(Block::Statements(body.into_iter()));
+            }
+            stmt => {
+                if let Return(_) = self.eval_stmt(stmt)? {
+                    return Ok(self.finish(blocks));
+                }
+            }
+        }
+        Ok(ControlFlow::Continue(()))
+    }
+
+    /// handle an error raised in the generator body like `try` does: the blocks are left up to
+    /// the innermost `try`, whose catch block or finally block runs next, the error is returned
+    /// if no `try` is left
+    fn unwind(&mut self, blocks: &mut Vec<Block>, err: EvalError) -> Result<(), EvalError> {
+        // a pending error or return of a finally block which fails is dropped with the block
+        while let Some(block) = self.pop_block(blocks) {
+            let Block::Try { catch, finally } = block else {
+                continue;
+            };
+            match catch {
+                Some((ident, block)) if err.error.is_catchable() => {
+                    let err_obj = self.allocate(err.to_object())?;
+                    self.env.borrow_mut().set(&ident, err_obj);
+                    blocks.push(Block::Try { catch: None, finally });
+                    blocks.push(Block::Statements(block.into_iter()));
+                    return Ok(());
+                }
+                _ => {
+                    if let Some(block) = finally {
+                        blocks.push(Block::Raise(err));
+                        blocks.push(Block::Statements(block.into_iter()));
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        Err(err)
+    }
+
+    /// end the generator after a `return`, the finally blocks of the enclosing `try`s run first
+    fn finish(&mut self, blocks: &mut Vec<Block>) -> ControlFlow<Option<Object>> {
+        while let Some(block) = self.pop_block(blocks) {
+            if let Block::Try { finally: Some(block), .. } = block {
+                blocks.push(Block::Return);
+                blocks.push(Block::Statements(block.into_iter()));
+                return ControlFlow::Continue(());
+            }
+        }
+        ControlFlow::Break(None)
+    }
+
+    /// run statements in the env of a loop iteration or match arm
+    fn enter_scope(&mut self, blocks: &mut Vec<Block>, env: Rc<RefCell<Environment>>, statements: vec::IntoIter<Stmt>) {
+        let current_env = mem::replace(&mut self.env, env);
+        blocks.push(Block::Scope(current_env));
+        blocks.push(Block::Statements(statements));
+    }
+
+    /// remove the innermost block, the env in use before a scope is restored when the scope is left
+    fn pop_block(&mut self, blocks: &mut Vec<Block>) -> Option<Block> {
+        let block = blocks.pop();
+        if let Some(Block::Scope(ref env)) = block {
+            self.env = env.clone();
+        }
+        block
+    }
+
+    /// evaluation for `for (ident in iterable) { body }`, each item is bound to `ident` in a new env
+    /// for its iteration, a `return` in the body returns from the enclosing function
+    pub(crate) fn eval_for(&mut self, ident: Identifier, iterable: Expr, body: BlockStatement) -> EvalResult {
+        let Identifier(ident) = ident;
+        let iterable = self.eval_expr(iterable)?;
+        let items = self.iterator(iterable)?;
+        while let Some(item) = self.next_item(&items)? {
+            let iteration_env = self.iteration_env(&ident, item);
+            if let ret @ Return(_) = self.in_env(iteration_env, |evaluator| evaluator.eval_statements(body.clone()))? {
+                return Ok(ret);
+            }
+        }
+        Ok(Object::NULL)
+    }
+
+    /// env of a loop iteration, holding the item and enclosed by the current env
+    fn iteration_env(&self, ident: &str, item: Object) -> Rc<RefCell<Environment>> {
+        let mut iteration_env = Environment::new_with_outer(self.env.clone());
+        iteration_env.set(ident, item);
+        Rc::new(RefCell::new(iteration_env))
+    }
+}
+
+/// a `yield` which fails without a call between it and the generator body is in the body itself,
+/// nested in an expression where the body can not be suspended
+fn nested_yield(err: EvalError) -> EvalError {
+    match err.error {
+        RuntimeError::Yield if err.stack.is_empty() => RuntimeError::NestedYield.into(),
+        _ => err,
+    }
+}
@@ -0,0 +1,144 @@
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// execution budgets of an evaluator, `None` means unlimited
+/// the budgets are counted per `Evaluator::evaluation` call, the object and byte budgets count
+/// every allocation of the evaluation and nothing is given back when an object is dropped,
+/// so they bound the total allocation of the evaluation rather than the memory it holds
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EvalLimits {
+    /// maximum number of evaluated statements and expressions
+    pub max_steps: Option<u64>,
+    /// wall-clock deadline of the evaluation
+    pub deadline: Option<Instant>,
+    /// maximum number of objects allocated by the evaluation
+    pub max_objects: Option<usize>,
+    /// maximum number of bytes allocated by the evaluation (estimated)
+    pub max_bytes: Option<usize>,
+}
+
+/// the limit which stopped an evaluation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LimitExceeded {
+    Steps,
+    Deadline,
+    Objects,
+    Bytes,
+    Cancelled,
+}
+
+impl Display for LimitExceeded {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            LimitExceeded::Steps => write!(f, "step limit exceeded"),
+            LimitExceeded::Deadline => write!(f, "deadline exceeded"),
+            LimitExceeded::Objects => write!(f, "object limit exceeded"),
+            LimitExceeded::Bytes => write!(f, "memory limit exceeded"),
+            LimitExceeded::Cancelled => write!(f, "evaluation cancelled"),
+        }
+    }
+}
+
+/// handle to cancel a running evaluation, can be sent to another thread
+/// the flag stays set until `reset` is called
+#[derive(Clone, Debug, Default)]
+pub struct CancelHandle(Arc<AtomicBool>);
+
+impl CancelHandle {
+    pub fn new() -> Self {
+        CancelHandle(Arc::new(AtomicBool::new(false)))
+    }
+
+    /// request the evaluation to stop
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// clear a previous cancellation
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
+
+/// limits together with the resources used by the current evaluation
+#[derive(Debug, Default)]
+pub(crate) struct Budget {
+    pub(crate) limits: EvalLimits,
+    pub(crate) cancel: CancelHandle,
+    steps: u64,
+    objects: usize,
+    bytes: usize,
+    // once a limit is hit every following step fails with it
+    exceeded: Option<LimitExceeded>,
+}
+
+impl Budget {
+    /// reset the used resources for a new evaluation
+    pub(crate) fn reset(&mut self) {
+        self.steps = 0;
+        self.objects = 0;
+        self.bytes = 0;
+        self.exceeded = None;
+    }
+
+    pub(crate) fn exceeded(&self) -> Option<LimitExceeded> {
+        self.exceeded
+    }
+
+    /// account one evaluation step
+    pub(crate) fn tick(&mut self) -> Result<(), LimitExceeded> {
+        if let Some(limit) = self.exceeded {
+            return Err(limit);
+        }
+        self.steps += 1;
+        if self.cancel.is_cancelled() {
+            return self.exceed(LimitExceeded::Cancelled);
+        }
+        if matches!(self.limits.max_steps, Some(max) if self.steps > max) {
+            return self.exceed(LimitExceeded::Steps);
+        }
+        if matches!(self.limits.deadline, Some(deadline) if Instant::now() >= deadline) {
+            return self.exceed(LimitExceeded::Deadline);
+        }
+        Ok(())
+    }
+
+    /// account allocated objects and bytes
+    pub(crate) fn allocate(&mut self, objects: usize, bytes: usize) -> Result<(), LimitExceeded> {
+        if let Some(limit) = self.exceeded {
+            return Err(limit);
+        }
+        self.objects += objects;
+        self.bytes += bytes;
+        if matches!(self.limits.max_objects, Some(max) if self.objects > max) {
+            return self.exceed(LimitExceeded::Objects);
+        }
+        if matches!(self.limits.max_bytes, Some(max) if self.bytes > max) {
+            return self.exceed(LimitExceeded::Bytes);
+        }
+        Ok(())
+    }
+
+    /// check that `bytes` more bytes fit into the limits before allocating them,
+    /// nothing is accounted
+    pub(crate) fn reserve(&mut self, bytes: usize) -> Result<(), LimitExceeded> {
+        if let Some(limit) = self.exceeded {
+            return Err(limit);
+        }
+        match self.limits.max_bytes {
+            Some(max) if self.bytes.saturating_add(bytes) > max => self.exceed(LimitExceeded::Bytes),
+            _ => Ok(()),
+        }
+    }
+
+    fn exceed(&mut self, limit: LimitExceeded) -> Result<(), LimitExceeded> {
+        self.exceeded = Some(limit);
+        Err(limit)
+    }
+}
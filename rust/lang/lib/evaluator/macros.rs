@@ -0,0 +1,184 @@
+use std::rc::Rc;
+
+use crate::ast::{Expr, Identifier, Literal, Program, Stmt};
+use crate::evaluator::error::{EvalError, Frame, RuntimeError};
+use crate::evaluator::{EvalResult, Evaluator};
+use crate::object::objects::Object;
+use crate::token::Float;
+
+impl Evaluator {
+    /// remove the top level `let name = macro(..) { .. };` statements of a program and keep their macros
+    /// for `expand_macros`, macros stay defined for the following programs
+    pub fn define_macros(&mut self, program: Program) -> Program {
+        program
+            .into_iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::LetStmt(Identifier(name), Expr::MacroExpr { parameters, body }) => {
+                    let macro_object = Object::Macro(parameters, Rc::new(body), self.globals.clone());
+                    self.macros.insert(name, macro_object);
+                    None
+                }
+                stmt => Some(stmt),
+            })
+            .collect()
+    }
+
+    /// replace the calls of the defined macros by the quotes they return,
+    /// the arguments are passed to the macro as quotes without being evaluated
+    pub fn expand_macros(&mut self, program: Program) -> Result<Program, EvalError> {
+        if self.macros.is_empty() {
+            return Ok(program);
+        }
+        modify_statements(program, &mut |expr| self.expand_macro_call(expr))
+    }
+
+    fn expand_macro_call(&mut self, expr: Expr) -> Result<Expr, EvalError> {
+        let macro_object = match expr {
+            Expr::CallExpr { ref function, .. } => match **function {
+                Expr::IdentExpr(Identifier(ref name)) => self.macros.get(name).cloned(),
+                _ => None,
+            },
+            _ => None,
+        };
+        match (macro_object, expr) {
+            (Some(Object::Macro(params, body, env)), Expr::CallExpr { function, arguments, span }) => {
+                if arguments.len() != params.len() {
+                    return Err(RuntimeError::Arity {
+                        expected: params.len(),
+                        given: arguments.len(),
+                    }.into());
+                }
+                let frame = Frame { function: function.to_string(), span };
+                let args = arguments.into_iter().map(Object::Quote).collect();
+                match self.apply_fn(frame, params, body, env, args)? {
+                    Object::Quote(expr) => Ok(expr),
+                    o => Err(RuntimeError::TypeMismatch(format!("macro {} returned {} instead of a quote", function, o)).into()),
+                }
+            }
+            (_, expr) => Ok(expr),
+        }
+    }
+
+    /// evaluation for `quote(expr)`, the expression is kept as it is except for the
+    /// `unquote(expr)` calls in it, which are replaced by the syntax tree of their values
+    pub(crate) fn eval_quote(&mut self, expr: Expr) -> EvalResult {
+        let expr = modify(expr, &mut |expr| match expr {
+            Expr::CallExpr { function, mut arguments, .. } if is_special_form(&function, &arguments, "unquote") => {
+                let value = self.eval_expr(arguments.remove(0))?;
+                unquoted(value)
+            }
+            expr => Ok(expr),
+        })?;
+        self.allocate(Object::Quote(expr))
+    }
+}
+
+/// whether a call is `name(argument)`, e.g. `quote(1 + 2)`
+pub(crate) fn is_special_form(function: &Expr, arguments: &[Expr], name: &str) -> bool {
+    matches!(*function, Expr::IdentExpr(Identifier(ref ident)) if ident == name) && arguments.len() == 1
+}
+
+/// the syntax tree of an unquoted value
+fn unquoted(object: Object) -> Result<Expr, EvalError> {
+    Ok(match object {
+        Object::Integer(i) => Expr::LiteralExpr(Literal::IntLiteral(i)),
+        Object::Float(x) => Expr::LiteralExpr(Literal::FloatLiteral(Float(x))),
+        Object::Boolean(b) => Expr::LiteralExpr(Literal::BoolLiteral(b)),
+        Object::String(s) => Expr::LiteralExpr(Literal::StringLiteral(s)),
+        Object::Quote(expr) => expr,
+        Object::Array(items) => Expr::ArrayExpr(items.into_iter().map(unquoted).collect::<Result<_, _>>()?),
+        Object::Hash(pairs) => Expr::HashExpr(pairs
+            .into_iter()
+            .map(|(k, v)| Ok((unquoted(k)?, unquoted(v)?)))
+            .collect::<Result<_, EvalError>>()?),
+        o => return Err(RuntimeError::TypeMismatch(format!("{} can not be unquoted", o)).into()),
+    })
+}
+
+/// apply `f` to every expression of the statements, inner expressions first
+fn modify_statements<F>(statements: Vec<Stmt>, f: &mut F) -> Result<Vec<Stmt>, EvalError>
+where
+    F: FnMut(Expr) -> Result<Expr, EvalError>,
+{
+    statements.into_iter().map(|stmt| modify_statement(stmt, f)).collect()
+}
+
+fn modify_statement<F>(stmt: Stmt, f: &mut F) -> Result<Stmt, EvalError>
+where
+    F: FnMut(Expr) -> Result<Expr, EvalError>,
+{
+    Ok(match stmt {
+        Stmt::LetStmt(ident, expr) => Stmt::LetStmt(ident, modify(expr, f)?),
+        Stmt::ReturnStmt(expr) => Stmt::ReturnStmt(modify(expr, f)?),
+        Stmt::ExprStmt(expr) => Stmt::ExprStmt(modify(expr, f)?),
+        Stmt::ThrowStmt(expr) => Stmt::ThrowStmt(modify(expr, f)?),
+        Stmt::StructStmt { name, fields, methods } => Stmt::StructStmt {
+            name,
+            fields,
+            methods: methods
+                .into_iter()
+                .map(|(method, function)| Ok((method, modify(function, f)?)))
+                .collect::<Result<_, EvalError>>()?,
+        },
+        stmt @ Stmt::EnumStmt { .. } => stmt,
+        Stmt::AssignStmt(target, value) => Stmt::AssignStmt(modify(target, f)?, modify(value, f)?),
+        stmt @ Stmt::ImportStmt(_, _) => stmt,
+        Stmt::ExportStmt(stmt) => Stmt::ExportStmt(Box::new(modify_statement(*stmt, f)?)),
+        Stmt::ForStmt { ident, iterable, body } => Stmt::ForStmt {
+            ident,
+            iterable: modify(iterable, f)?,
+            body: modify_statements(body, f)?,
+        },
+        Stmt::YieldStmt(expr) => Stmt::YieldStmt(modify(expr, f)?),
+    })
+}
+
+/// apply `f` to the expression and every expression in it, inner expressions first,
+/// the bodies of macro literals are left alone
+fn modify<F>(expr: Expr, f: &mut F) -> Result<Expr, EvalError>
+where
+    F: FnMut(Expr) -> Result<Expr, EvalError>,
+{
+    let expr = match expr {
+        expr @ (Expr::IdentExpr(_) | Expr::LiteralExpr(_) | Expr::MacroExpr { .. }) => expr,
+        Expr::PrefixExpr(prefix, right) => Expr::PrefixExpr(prefix, Box::new(modify(*right, f)?)),
+        Expr::InfixExpr(infix, left, right) => {
+            Expr::InfixExpr(infix, Box::new(modify(*left, f)?), Box::new(modify(*right, f)?))
+        }
+        Expr::IfExpr { cond, consequence, alternative } => Expr::IfExpr {
+            cond: Box::new(modify(*cond, f)?),
+            consequence: modify_statements(consequence, f)?,
+            alternative: alternative.map(|block| modify_statements(block, f)).transpose()?,
+        },
+        Expr::TryExpr { body, catch, finally } => Expr::TryExpr {
+            body: modify_statements(body, f)?,
+            catch: catch.map(|(ident, block)| modify_statements(block, f).map(|block| (ident, block))).transpose()?,
+            finally: finally.map(|block| modify_statements(block, f)).transpose()?,
+        },
+        Expr::MatchExpr { subject, arms } => Expr::MatchExpr {
+            subject: Box::new(modify(*subject, f)?),
+            arms: arms
+                .into_iter()
+                .map(|(pattern, body)| Ok((pattern, modify_statements(body, f)?)))
+                .collect::<Result<_, EvalError>>()?,
+        },
+        Expr::FnExpr { parameters, body } => Expr::FnExpr { parameters, body: modify_statements(body, f)? },
+        Expr::GeneratorExpr { parameters, body } => Expr::GeneratorExpr { parameters, body: modify_statements(body, f)? },
+        Expr::ArrayExpr(items) => Expr::ArrayExpr(items.into_iter().map(|item| modify(item, f)).collect::<Result<_, _>>()?),
+        Expr::HashExpr(pairs) => Expr::HashExpr(pairs
+            .into_iter()
+            .map(|(k, v)| Ok((modify(k, f)?, modify(v, f)?)))
+            .collect::<Result<_, EvalError>>()?),
+        Expr::CallExpr { function, arguments, span } => Expr::CallExpr {
+            function: Box::new(modify(*function, f)?),
+            arguments: arguments.into_iter().map(|arg| modify(arg, f)).collect::<Result<_, _>>()?,
+            span,
+        },
+        Expr::IndexExpr { left, index } => Expr::IndexExpr {
+            left: Box::new(modify(*left, f)?),
+            index: Box::new(modify(*index, f)?),
+        },
+        Expr::FieldExpr { object, field } => Expr::FieldExpr { object: Box::new(modify(*object, f)?), field },
+    };
+    f(expr)
+}
@@ -1,123 +1,453 @@
+pub mod capabilities;
+pub mod clock;
+pub mod error;
+pub mod generators;
+pub mod limits;
+pub mod macros;
+pub mod modules;
+pub mod prelude;
+pub mod random;
+pub mod regex_cache;
+
 use std::cell::{Cell, RefCell};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::mem::size_of;
 use std::process::id;
 use std::rc::Rc;
-use crate::ast::{BlockStatement, Expr, Identifier, Infix, Literal, Prefix, Program, Stmt};
+use std::time::Instant;
+use crate::ast::{BlockStatement, Expr, Identifier, Infix, Literal, Pattern, Prefix, Program, Stmt};
+use crate::object::builtins::MethodTable;
 use crate::object::environment::Environment;
-use crate::object::objects::{BuiltinFunction, Object};
+use crate::object::convert::{EnumType, ToObject};
+use crate::object::objects::{BuiltinFunction, EnumDef, EnumValue, Instance, NativeFn, NativeFunction, Object, StructDef};
 use crate::object::objects::Object::Return;
 use crate::object::objects::Object::NULL;
+use crate::evaluator::capabilities::Capabilities;
+use crate::evaluator::clock::{Clock, SystemClock};
+use crate::evaluator::error::{overflow, EvalError, Frame, RuntimeError};
+use crate::evaluator::limits::{Budget, CancelHandle, EvalLimits, LimitExceeded};
+use crate::evaluator::macros::is_special_form;
+use crate::evaluator::modules::ModuleLoader;
+use crate::evaluator::random::Rng;
+use crate::evaluator::regex_cache::RegexCache;
+use crate::parser::Parser;
+use crate::token::{Float, Span};
+
+/// default limit of nested (non-tail) function calls
+pub const DEFAULT_MAX_DEPTH: usize = 1000;
+
+// remaining stack below which a function call moves to a freshly allocated stack segment
+pub(crate) const STACK_RED_ZONE: usize = 128 * 1024;
+// size of each stack segment allocated for deep recursion
+pub(crate) const STACK_SEGMENT_SIZE: usize = 2 * 1024 * 1024;
+
+/// result of an evaluation step, errors abort the evaluation up to the top level
+pub type EvalResult = Result<Object, EvalError>;
+
+/// env holding the bindings of a matched pattern and the body of its arm
+type MatchArm = (Rc<RefCell<Environment>>, BlockStatement);
 
 pub struct Evaluator {
     env: Rc<RefCell<Environment>>,
+    // outermost env, `env` is a function env while a builtin is running
+    globals: Rc<RefCell<Environment>>,
+    // current depth of nested function calls
+    depth: usize,
+    // maximum depth of nested function calls
+    max_depth: usize,
+    // execution budgets and the resources used by the current evaluation
+    budget: Budget,
+    // methods of the object types
+    methods: MethodTable,
+    // position of the innermost builtin call, where functions called back by the builtin are called
+    builtin_span: Span,
+    // generator of the random builtins
+    rng: Rng,
+    // access of the I/O builtins
+    capabilities: Capabilities,
+    // command line arguments returned by `args()`
+    args: Vec<String>,
+    // time source of the time builtins
+    clock: Box<dyn Clock>,
+    // compiled patterns of the regex builtins
+    regexes: RegexCache,
+    // macros defined by the evaluated programs, expanded before a program is evaluated
+    macros: HashMap<String, Object>,
+    // search path and cache of the imported modules
+    modules: ModuleLoader,
+    // functions of the prelude, defined in the globals of the main program and of every module
+    prelude: HashMap<String, Object>,
+}
+
+/// result of evaluating a statement or expression in tail position
+enum TailEval {
+    // an ordinary value
+    Value(Object),
+    // a call in tail position, which is run by the caller's loop instead of a new stack frame
+    Call(Frame, Object, Vec<Object>),
 }
 
 
 impl Evaluator {
     /// 对象转bool类型
-    pub fn otb(&mut self, object: Object) -> Result<bool, Object> {
+    pub fn otb(&mut self, object: Object) -> Result<bool, RuntimeError> {
         match object {
             Object::Boolean(b) => Ok(b),
-            Object::Error(s) => Err(Object::Error(s)),
-            b => Err(Object::Error(format!("{} is not a bool", b))),
+            b => Err(RuntimeError::TypeMismatch(format!("{} is not a bool", b))),
         }
     }
 
-    pub fn oti(&mut self, object: Object) -> Result<i64, Object> {
+    pub fn oti(&mut self, object: Object) -> Result<i64, RuntimeError> {
         match object {
             Object::Integer(i) => Ok(i),
-            Object::Error(s) => Err(Object::Error(s)),
-            i => Err(Object::Error(format!("{} is not an integer", i))),
+            i => Err(RuntimeError::TypeMismatch(format!("{} is not an integer", i))),
+        }
+    }
+
+    /// order of two integers or two strings, strings are ordered by their chars
+    /// numbers are compared by value, `None` if one of them is `NaN`
+    pub fn oto(&mut self, left: Object, right: Object) -> Result<Option<Ordering>, RuntimeError> {
+        match (left, right) {
+            (Object::String(s1), Object::String(s2)) => Ok(Some(s1.cmp(&s2))),
+            (left @ Object::Float(_), right) | (left, right @ Object::Float(_)) => {
+                Ok(self.otf(left)?.partial_cmp(&self.otf(right)?))
+            }
+            (left, right) => Ok(Some(self.oti(left)?.cmp(&self.oti(right)?))),
         }
     }
 
-    pub fn otfn(&mut self, object: Object) -> Object {
+    /// a float or an integer as a float
+    pub fn otf(&mut self, object: Object) -> Result<f64, RuntimeError> {
         match object {
-            Object::Function(_, _, _) | Object::Builtin(_, _, _) => object,
-            Object::Error(s) => Object::Error(s),
-            f => Object::Error(format!("{} is not a valid function", f)),
+            Object::Float(x) => Ok(x),
+            Object::Integer(i) => Ok(i as f64),
+            o => Err(RuntimeError::TypeMismatch(format!("{} is not a number", o))),
         }
     }
 
-    pub fn othash(&mut self, object: Object) -> Object {
+    pub fn otfn(&mut self, object: Object) -> Result<Object, RuntimeError> {
         match object {
-            Object::Integer(i) => Object::Integer(i),
-            Object::Boolean(b) => Object::Boolean(b),
-            Object::String(s) => Object::String(s),
-            Object::Error(s) => Object::Error(s),
-            x => Object::Error(format!("{} is not hashable", x)),
+            Object::Function(_, _, _)
+            | Object::Generator(_, _, _)
+            | Object::Builtin(_, _, _)
+            | Object::Closure(_, _, _)
+            | Object::Method(_, _)
+            | Object::Struct(_)
+            | Object::Constructor(_, _) => Ok(object),
+            f => Err(RuntimeError::TypeMismatch(format!("{} is not a valid function", f))),
+        }
+    }
+
+    /// check that an object can be a hash key, see `Object::is_hashable`
+    pub fn othash(&mut self, object: Object) -> Result<Object, RuntimeError> {
+        if object.is_hashable() {
+            Ok(object)
+        } else {
+            Err(RuntimeError::HashKey(format!("{} is not hashable", object)))
         }
     }
 }
 
 
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Evaluator {
-    /// new a evaluator and init
+    /// new a evaluator and init, the functions of the prelude are defined
     pub fn new() -> Self {
+        let mut evaluator = Self::without_prelude();
+        evaluator.load_prelude().expect("the prelude evaluates");
+        evaluator
+    }
+
+    /// new a evaluator with only the builtins defined
+    pub fn without_prelude() -> Self {
+        let globals = Rc::new(RefCell::new(Environment::new()));
         Evaluator {
-            env: Rc::new(RefCell::new(Environment::new())),
+            env: globals.clone(),
+            globals,
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+            budget: Budget::default(),
+            methods: MethodTable::new(),
+            builtin_span: Span::default(),
+            rng: Rng::from_entropy(),
+            capabilities: Capabilities::default(),
+            args: vec![],
+            clock: Box::new(SystemClock::new()),
+            regexes: RegexCache::new(),
+            macros: HashMap::new(),
+            modules: ModuleLoader::new(),
+            prelude: HashMap::new(),
         }
     }
 
-    /// evaluation for Program
+    /// new a evaluator with execution budgets
+    pub fn with_limits(limits: EvalLimits) -> Self {
+        let mut evaluator = Self::new();
+        evaluator.set_limits(limits);
+        evaluator
+    }
+
+    /// seed the generator of the random builtins, evaluations with the same seed draw the same numbers
+    pub fn seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    /// generator of the random builtins
+    pub fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+
+    /// set the maximum depth of nested (non-tail) function calls
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    /// set the execution budgets used by the following evaluations
+    pub fn set_limits(&mut self, limits: EvalLimits) {
+        self.budget.limits = limits;
+    }
+
+    /// set the directories and streams the I/O builtins may access, nothing is allowed by default
+    pub fn set_capabilities(&mut self, capabilities: Capabilities) {
+        self.capabilities = capabilities;
+    }
+
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    /// set the command line arguments of the script, returned by `args()`
+    pub fn set_args(&mut self, args: Vec<String>) {
+        self.args = args;
+    }
+
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// replace the time source of the time builtins, e.g. by a `ManualClock` in tests
+    pub fn set_clock<C: Clock + 'static>(&mut self, clock: C) {
+        self.clock = Box::new(clock);
+    }
+
+    pub fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+
+    /// compiled patterns of the regex builtins
+    pub fn regexes(&mut self) -> &mut RegexCache {
+        &mut self.regexes
+    }
+
+    /// get a handle which can cancel the evaluation from another thread
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.budget.cancel.clone()
+    }
+
+    /// add or replace a method of the objects of a type, e.g. `"string"`,
+    /// the receiver is passed as the first argument and counts towards `num_params`
+    pub fn add_method(&mut self, type_name: &str, name: &str, num_params: usize, func: BuiltinFunction) {
+        self.methods.add(type_name, name, num_params, func);
+    }
+
+    /// add or replace a method of a type implemented by a host closure, e.g. for `Object::Native` values,
+    /// the receiver is passed as the first argument and counts towards `num_params`
+    pub fn register_method<F>(&mut self, type_name: &str, name: &str, num_params: usize, f: F)
+    where
+        F: Fn(&mut Evaluator, Vec<Object>) -> EvalResult + 'static,
+    {
+        self.methods.add_closure(type_name, name, num_params, NativeFunction::new(f));
+    }
+
+    /// bind a host closure as a global builtin function
+    pub fn register_fn<F>(&mut self, name: &str, num_params: usize, f: F)
+    where
+        F: Fn(&mut Evaluator, Vec<Object>) -> EvalResult + 'static,
+    {
+        self.globals.borrow_mut().register_fn(name, num_params, f);
+    }
+
+    /// bind a host value as a global variable
+    pub fn set_global<T: ToObject + ?Sized>(&mut self, name: &str, value: &T) {
+        self.globals.borrow_mut().set(name, value.to_object());
+    }
+
+    /// bind a host enum, which derives `IntoObject`, as a global enum type with its variants,
+    /// so scripts can construct and match its values
+    pub fn register_enum<T: EnumType>(&mut self) -> EvalResult {
+        let env = std::mem::replace(&mut self.env, self.globals.clone());
+        let result = self.bind_enum(T::enum_def());
+        self.env = env;
+        result
+    }
+
+    /// get a global variable
+    pub fn get_global(&self, name: &str) -> Option<Object> {
+        self.globals.borrow().get(name)
+    }
+
+    /// call a function, builtin or constructor with evaluated arguments,
+    /// used by builtins to call back functions given as arguments
+    pub fn call(&mut self, func: Object, args: Vec<Object>) -> EvalResult {
+        let function = match func {
+            Object::Builtin(ref name, _, _) | Object::Closure(ref name, _, _) => name.clone(),
+            _ => String::from("<anonymous>"),
+        };
+        let frame = Frame { function, span: self.builtin_span };
+        self.apply(frame, func, args)
+    }
+
+    /// the limit which stopped the last evaluation, if any
+    pub fn limit_exceeded(&self) -> Option<LimitExceeded> {
+        self.budget.exceeded()
+    }
+
+    /// evaluation for Program, runtime errors are returned as `Object::Error`
+    /// evaluation stops with an error once a limit of `EvalLimits` is hit,
+    /// the limit is reported by `limit_exceeded`
     pub fn evaluation(&mut self, program: Program) -> Object {
+        self.eval_program(program)
+            .unwrap_or_else(|err| Object::Error(err.error.to_string()))
+    }
+
+    /// evaluation for Program, runtime errors are returned with the call stack
+    pub fn eval_program(&mut self, program: Program) -> EvalResult {
+        self.budget.reset();
+        let program = self.define_macros(program);
+        let program = self.expand_macros(program)?;
         match self.eval_statements(program) {
-            Return(x) => *x,
-            o => o
+            Ok(Return(x)) => Ok(*x),
+            result => result,
+        }
+    }
+
+    /// account an allocated object against the allocation budgets
+    fn allocate(&mut self, object: Object) -> EvalResult {
+        let (objects, bytes) = match object {
+            Object::Array(ref items) => (1 + items.len(), size_of::<Object>() * (1 + items.len())),
+            Object::Hash(ref hash) => (1 + 2 * hash.len(), size_of::<Object>() * (1 + 2 * hash.len())),
+            Object::String(ref s) => (1, size_of::<Object>() + s.len()),
+            Object::Instance(ref instance) => {
+                let fields = instance.borrow().values.len();
+                (1 + fields, size_of::<Object>() * (1 + fields))
+            }
+            Object::Variant(ref value) => (1 + value.values.len(), size_of::<Object>() * (1 + value.values.len())),
+            _ => (1, size_of::<Object>()),
+        };
+        self.budget.allocate(objects, bytes)?;
+        Ok(object)
+    }
+
+    /// check that a string of `bytes` bytes fits into the allocation budgets before a builtin builds it,
+    /// the result is accounted once the builtin returns
+    pub(crate) fn reserve(&mut self, bytes: usize) -> Result<(), EvalError> {
+        Ok(self.budget.reserve(bytes)?)
+    }
+
+    /// parse and evaluate source code in the current env, a `return` ends the source,
+    /// the budgets of the running evaluation keep counting
+    pub(crate) fn eval_source(&mut self, source: &str) -> EvalResult {
+        let program = Parser::parse_source(source)
+            .map_err(|span| RuntimeError::Syntax(format!("syntax error at {}", span)))?;
+        let program = self.define_macros(program);
+        let program = self.expand_macros(program)?;
+        match self.eval_statements(program)? {
+            Return(x) => Ok(*x),
+            object => Ok(object),
         }
     }
 
+    /// sleep for `ms` milliseconds, but not past the deadline of the evaluation
+    pub(crate) fn sleep(&mut self, ms: u64) -> Result<(), EvalError> {
+        let ms = match self.budget.limits.deadline {
+            Some(deadline) => {
+                let left = deadline.saturating_duration_since(Instant::now()).as_nanos().div_ceil(1_000_000);
+                ms.min(u64::try_from(left).unwrap_or(u64::MAX))
+            }
+            None => ms,
+        };
+        self.clock.sleep(ms);
+        Ok(self.budget.tick()?)
+    }
+
     /// evaluation statements
-    fn eval_statements(&mut self, statements: Vec<Stmt>) -> Object {
+    fn eval_statements(&mut self, statements: Vec<Stmt>) -> EvalResult {
         let mut result = NULL;
         for stmt in statements {
-            let stmt_obj = self.eval_stmt(stmt);
-            result = match stmt_obj {
-                Return(_) => {
-                    result = stmt_obj;
-                    break;
-                }
-                obj => obj,
-            };
+            result = self.eval_stmt(stmt)?;
+            if let Return(_) = result {
+                break;
+            }
         };
-        result
+        Ok(result)
     }
 
     /// evaluation statement
-    fn eval_stmt(&mut self, stmt: Stmt) -> Object {
+    fn eval_stmt(&mut self, stmt: Stmt) -> EvalResult {
+        self.budget.tick()?;
         match stmt {
             Stmt::ExprStmt(expr) => self.eval_expr(expr),
-            Stmt::ReturnStmt(expr) => Return(Box::new(self.eval_expr(expr))),
+            Stmt::ReturnStmt(expr) => Ok(Return(Box::new(self.eval_expr(expr)?))),
+            Stmt::ThrowStmt(expr) => Err(RuntimeError::Thrown(self.eval_expr(expr)?).into()),
             Stmt::LetStmt(Identifier(ident), expr) => {
-                let object = self.eval_expr(expr);
+                let object = self.eval_expr(expr)?;
                 self.env.borrow_mut().set(&ident, object.clone());
-                object
+                Ok(object)
             }
+            Stmt::StructStmt { name, fields, methods } => self.eval_struct(name, fields, methods),
+            Stmt::EnumStmt { name, variants } => self.eval_enum(name, variants),
+            Stmt::AssignStmt(target, value) => self.eval_assign(target, value),
+            Stmt::ImportStmt(path, alias) => self.eval_import(path, alias),
+            Stmt::ExportStmt(stmt) => self.eval_export(*stmt),
+            Stmt::ForStmt { ident, iterable, body } => self.eval_for(ident, iterable, body),
+            Stmt::YieldStmt(_) => Err(RuntimeError::Yield.into()),
         }
     }
     /// evaluation expr
-    fn eval_expr(&mut self, expr: Expr) -> Object {
+    fn eval_expr(&mut self, expr: Expr) -> EvalResult {
+        self.budget.tick()?;
         match expr {
             Expr::IdentExpr(Identifier(ident)) => {
                 match self.env.borrow().get(&ident) {
-                    Some(o) => o,
-                    None => Object::Error(format!("identifier not found: {}", ident)),
+                    Some(o) => Ok(o),
+                    None => Err(RuntimeError::UnknownIdentifier(ident).into()),
                 }
             }
-            Expr::LiteralExpr(l) => self.eval_literal(l),
+            Expr::LiteralExpr(l) => Ok(self.eval_literal(l)),
             Expr::PrefixExpr(prefix, right) => self.eval_prefix(&prefix, *right),
             Expr::InfixExpr(infix, left, right) => self.eval_infix(&infix, *left, *right),
             Expr::IfExpr {
                 cond, consequence, alternative
-            } => self.eval_if(cond, consequence, alternative),
+            } => self.eval_if(*cond, consequence, alternative),
+            Expr::TryExpr {
+                body, catch, finally
+            } => self.eval_try(body, catch, finally),
+            Expr::MatchExpr { subject, arms } => self.eval_match(*subject, arms),
             Expr::FnExpr { parameters, body } => self.eval_fn(parameters, body),
+            Expr::GeneratorExpr { parameters, body } => {
+                self.allocate(Object::Generator(parameters, Rc::new(body), self.env.clone()))
+            }
+            Expr::MacroExpr { parameters, body } => Ok(Object::Macro(parameters, Rc::new(body), self.env.clone())),
+            Expr::CallExpr {
+                function, mut arguments, ..
+            } if is_special_form(&function, &arguments, "quote") => self.eval_quote(arguments.remove(0)),
             Expr::ArrayExpr(item_exprs) => self.eval_array(item_exprs),
             Expr::HashExpr(hash_pair_exprs) => self.eval_hash(hash_pair_exprs),
             Expr::CallExpr {
-                function, arguments
-            } => self.eval_call(function, arguments),
+                function, arguments, span
+            } => self.eval_call(*function, arguments, span),
             Expr::IndexExpr {
                 left, index
-            } => self.eval_index(left, index),
+            } => self.eval_index(*left, *index),
+            Expr::FieldExpr {
+                object, field
+            } => self.eval_field(*object, field),
         }
     }
 
@@ -126,193 +456,661 @@ impl Evaluator {
     fn eval_literal(&self, literal: Literal) -> Object {
         match literal {
             Literal::IntLiteral(i) => Object::Integer(i),
+            Literal::FloatLiteral(Float(x)) => Object::Float(x),
             Literal::BoolLiteral(b) => Object::Boolean(b),
             Literal::StringLiteral(s) => Object::String(s),
         }
     }
     /// evaluation for prefix `(!,-,+)`
-    fn eval_prefix(&mut self, prefix: &Prefix, right: Expr) -> Object {
-        let right = self.eval_expr(right);
+    fn eval_prefix(&mut self, prefix: &Prefix, right: Expr) -> EvalResult {
+        let right = self.eval_expr(right)?;
         match prefix {
-            Prefix::Plus => match self.oti(right) {
-                Ok(i) => Object::Integer(i),
-                Err(err) => err
-            }
-            Prefix::Minus => match self.oti(right) {
-                Ok(i) => Object::Integer(-i),
-                Err(err) => err
-            }
-            Prefix::Not => match self.otb(right) {
-                Ok(i) => Object::Boolean(!i),
-                Err(err) => err
-            }
+            Prefix::Plus => match right {
+                Object::Float(x) => Ok(Object::Float(x)),
+                right => Ok(Object::Integer(self.oti(right)?)),
+            },
+            Prefix::Minus => match right {
+                Object::Float(x) => Ok(Object::Float(-x)),
+                right => {
+                    let i = self.oti(right)?;
+                    i.checked_neg().map(Object::Integer).ok_or_else(|| overflow().into())
+                }
+            },
+            Prefix::Not => Ok(Object::Boolean(!self.otb(right)?)),
         }
     }
     /// evaluation for infix `(+,-,*,/,==,!=,>=,<=,>,<)`
-    fn eval_infix(&mut self, infix: &Infix, left: Expr, right: Expr) -> Object {
-        let left_obj = self.eval_expr(left);
-        let right_obj = self.eval_expr(right);
+    fn eval_infix(&mut self, infix: &Infix, left: Expr, right: Expr) -> EvalResult {
+        let left_obj = self.eval_expr(left)?;
+        let right_obj = self.eval_expr(right)?;
         match infix {
             Infix::Plus => {
                 match (left_obj, right_obj) {
-                    (Object::Integer(i1), Object::Integer(i2)) => Object::Integer(i1 + i2),
-                    (Object::String(s1), Object::String(s2)) => Object::String(s1 + &s2),
-                    (Object::Error(s), _) | (_, Object::Error(s)) => Object::Error(s),
-                    (x, y) => Object::Error(format!("{:?} and {:?} are not addable", x, y)),
+                    (Object::String(s1), Object::String(s2)) => self.allocate(Object::String(s1 + &s2)),
+                    (x @ (Object::Integer(_) | Object::Float(_)), y @ (Object::Integer(_) | Object::Float(_))) => {
+                        self.eval_arithmetic(infix, x, y)
+                    }
+                    (x, y) => Err(RuntimeError::TypeMismatch(format!("{:?} and {:?} are not addable", x, y)).into()),
                 }
             }
-            Infix::Minus => {
-                match (self.oti(left_obj), self.oti(right_obj)) {
-                    (Ok(i1), Ok(i2)) => Object::Integer(i1 - i2),
-                    (Err(err), _) | (_, Err(err)) => err,
-                }
+            Infix::Minus | Infix::Divide | Infix::Multiply => self.eval_arithmetic(infix, left_obj, right_obj),
+            Infix::Equal => Ok(Object::Boolean(left_obj == right_obj)),
+            Infix::NotEqual => Ok(Object::Boolean(left_obj != right_obj)),
+            Infix::GreaterThanEqual => Ok(Object::Boolean(self.oto(left_obj, right_obj)?.is_some_and(Ordering::is_ge))),
+            Infix::LessThanEqual => Ok(Object::Boolean(self.oto(left_obj, right_obj)?.is_some_and(Ordering::is_le))),
+            Infix::GreaterThan => Ok(Object::Boolean(self.oto(left_obj, right_obj)?.is_some_and(Ordering::is_gt))),
+            Infix::LessThan => Ok(Object::Boolean(self.oto(left_obj, right_obj)?.is_some_and(Ordering::is_lt))),
+        }
+    }
+    /// evaluation for `+ - * /` on numbers, integer arithmetic is checked for overflow and division by zero,
+    /// an integer and a float are computed as floats
+    fn eval_arithmetic(&mut self, infix: &Infix, left: Object, right: Object) -> EvalResult {
+        if let (Object::Float(_), _) | (_, Object::Float(_)) = (&left, &right) {
+            let (x, y) = (self.otf(left)?, self.otf(right)?);
+            return Ok(Object::Float(match infix {
+                Infix::Plus => x + y,
+                Infix::Minus => x - y,
+                Infix::Multiply => x * y,
+                _ => x / y,
+            }));
+        }
+        let (i1, i2) = (self.oti(left)?, self.oti(right)?);
+        let result = match infix {
+            Infix::Plus => i1.checked_add(i2),
+            Infix::Minus => i1.checked_sub(i2),
+            Infix::Multiply => i1.checked_mul(i2),
+            _ if i2 == 0 => return Err(RuntimeError::Arithmetic("division by zero".to_string()).into()),
+            _ => i1.checked_div(i2),
+        };
+        result.map(Object::Integer).ok_or_else(|| overflow().into())
+    }
+    /// evaluation for `if {block} else {block}`
+    fn eval_if(&mut self, cond: Expr, consequence: BlockStatement, alternative: Option<BlockStatement>) -> EvalResult {
+        let cond_obj = self.eval_expr(cond)?;
+        if self.otb(cond_obj)? {
+            self.eval_statements(consequence)
+        } else {
+            match alternative {
+                None => Ok(Object::NULL),
+                Some(block) => self.eval_statements(block),
+            }
+        }
+    }
+    /// evaluation for `try {block} catch (ident) {block} finally {block}`
+    /// the caught error is bound to `ident` in the current env,
+    /// a `return` or an error in the finally block replaces the result of the try block
+    fn eval_try(&mut self, body: BlockStatement, catch: Option<(Identifier, BlockStatement)>, finally: Option<BlockStatement>) -> EvalResult {
+        let result = match (self.eval_statements(body), catch) {
+            (Err(err), Some((Identifier(ident), block))) if err.error.is_catchable() => {
+                let err_obj = self.allocate(err.to_object())?;
+                self.env.borrow_mut().set(&ident, err_obj);
+                self.eval_statements(block)
+            }
+            (result, _) => result,
+        };
+        if let Some(block) = finally {
+            if let ret @ Return(_) = self.eval_statements(block)? {
+                return Ok(ret);
             }
-            Infix::Divide => {
-                match (self.oti(left_obj), self.oti(right_obj)) {
-                    (Ok(i1), Ok(i2)) => Object::Integer(i1 / i2),
-                    (Err(err), _) | (_, Err(err)) => err,
+        }
+        result
+    }
+    /// evaluation for `match (subject) { pattern => body, ... }`, `null` if no arm matches
+    fn eval_match(&mut self, subject: Expr, arms: Vec<(Pattern, BlockStatement)>) -> EvalResult {
+        match self.match_arm(subject, arms)? {
+            Some((arm_env, body)) => self.in_env(arm_env, |evaluator| evaluator.eval_statements(body)),
+            None => Ok(Object::NULL),
+        }
+    }
+    /// select the first arm whose pattern matches the subject, the identifiers bound
+    /// by the pattern are set in a new env for the arm body, enclosed by the current env
+    fn match_arm(&mut self, subject: Expr, arms: Vec<(Pattern, BlockStatement)>) -> Result<Option<MatchArm>, EvalError> {
+        let value = self.eval_expr(subject)?;
+        for (pattern, body) in arms {
+            let mut bindings = vec![];
+            if self.match_pattern(&pattern, &value, &mut bindings)? {
+                let mut arm_env = Environment::new_with_outer(self.env.clone());
+                for (ident, object) in bindings {
+                    arm_env.set(&ident, object);
                 }
+                return Ok(Some((Rc::new(RefCell::new(arm_env)), body)));
             }
-            Infix::Multiply => {
-                match (self.oti(left_obj), self.oti(right_obj)) {
-                    (Ok(i1), Ok(i2)) => Object::Integer(i1 * i2),
-                    (Err(err), _) | (_, Err(err)) => err,
+        }
+        Ok(None)
+    }
+    /// evaluate with `env` as the current env, the current env is restored afterwards
+    pub(crate) fn in_env<T>(&mut self, env: Rc<RefCell<Environment>>, f: impl FnOnce(&mut Self) -> T) -> T {
+        let current_env = std::mem::replace(&mut self.env, env);
+        let result = f(self);
+        self.env = current_env;
+        result
+    }
+    /// match a value against a pattern, collecting the bound identifiers
+    fn match_pattern(&mut self, pattern: &Pattern, value: &Object, bindings: &mut Vec<(String, Object)>) -> Result<bool, EvalError> {
+        match (pattern, value) {
+            (Pattern::Wildcard, _) => Ok(true),
+            (Pattern::Binding(Identifier(ident)), value) => {
+                // a variant without payload in scope is compared instead of bound
+                if let Some(Object::Variant(unit)) = self.env.borrow().get(ident) {
+                    if unit.variant() == ident {
+                        return Ok(Object::Variant(unit) == *value);
+                    }
                 }
+                bindings.push((ident.clone(), value.clone()));
+                Ok(true)
             }
-            Infix::Equal => Object::Boolean(left_obj == right_obj),
-            Infix::NotEqual => Object::Boolean(left_obj != right_obj),
-            Infix::GreaterThanEqual => {
-                match (self.oti(left_obj), self.oti(right_obj)) {
-                    (Ok(i1), Ok(i2)) => Object::Boolean(i1 >= i2),
-                    (Err(err), _) | (_, Err(err)) => err,
+            (Pattern::Variant(enum_name, variant, patterns), value) => {
+                let (def, tag) = self.resolve_variant(enum_name.as_ref(), variant)?;
+                if patterns.len() != def.variants[tag].1.len() {
+                    return Err(RuntimeError::Arity {
+                        expected: def.variants[tag].1.len(),
+                        given: patterns.len(),
+                    }.into());
+                }
+                let values = match value {
+                    Object::Variant(value) if Rc::ptr_eq(&value.def, &def) && value.tag == tag => value.values.clone(),
+                    _ => return Ok(false),
+                };
+                for (pattern, value) in patterns.iter().zip(values.iter()) {
+                    if !self.match_pattern(pattern, value, bindings)? {
+                        return Ok(false);
+                    }
                 }
+                Ok(true)
             }
-            Infix::LessThanEqual => {
-                match (self.oti(left_obj), self.oti(right_obj)) {
-                    (Ok(i1), Ok(i2)) => Object::Boolean(i1 <= i2),
-                    (Err(err), _) | (_, Err(err)) => err,
+            (Pattern::Literal(literal), value) => Ok(self.eval_literal(literal.clone()) == *value),
+            (Pattern::Struct(Identifier(name), fields), value) => {
+                // the struct name is resolved in the env, so only instances of that very type match
+                let def = match self.env.borrow().get(name) {
+                    Some(Object::Struct(def)) => def,
+                    Some(o) => return Err(RuntimeError::TypeMismatch(format!("{} is not a struct", o)).into()),
+                    None => return Err(RuntimeError::UnknownIdentifier(name.clone()).into()),
+                };
+                let instance = match value {
+                    Object::Instance(instance) if Rc::ptr_eq(&instance.borrow().def, &def) => instance.borrow().clone(),
+                    _ => return Ok(false),
+                };
+                for (Identifier(field), pattern) in fields {
+                    let field_value = instance.get(field).ok_or_else(|| {
+                        RuntimeError::Field(format!("{} has no field {}", def.name, field))
+                    })?;
+                    if !self.match_pattern(pattern, &field_value, bindings)? {
+                        return Ok(false);
+                    }
                 }
+                Ok(true)
             }
-            Infix::GreaterThan => {
-                match (self.oti(left_obj), self.oti(right_obj)) {
-                    (Ok(i1), Ok(i2)) => Object::Boolean(i1 > i2),
-                    (Err(err), _) | (_, Err(err)) => err,
+        }
+    }
+    /// evaluation for `struct name { fields; methods }`, binds the struct type to `name`
+    fn eval_struct(&mut self, name: Identifier, fields: Vec<Identifier>, methods: Vec<(Identifier, Expr)>) -> EvalResult {
+        let Identifier(name) = name;
+        let methods = methods.into_iter()
+            .map(|(Identifier(method), expr)| Ok((method, self.eval_expr(expr)?)))
+            .collect::<Result<_, EvalError>>()?;
+        let def = self.allocate(Object::Struct(Rc::new(StructDef {
+            name: name.clone(),
+            fields: fields.into_iter().map(|Identifier(field)| field).collect(),
+            methods,
+        })))?;
+        self.env.borrow_mut().set(&name, def.clone());
+        Ok(def)
+    }
+    /// resolve the enum and tag of a variant pattern, either `Enum.Variant` or a variant in scope
+    fn resolve_variant(&mut self, enum_name: Option<&Identifier>, variant: &Identifier) -> Result<(Rc<EnumDef>, usize), EvalError> {
+        let Identifier(variant) = variant;
+        match enum_name {
+            Some(Identifier(name)) => {
+                let def = match self.env.borrow().get(name) {
+                    Some(Object::Enum(def)) => def,
+                    Some(o) => return Err(RuntimeError::TypeMismatch(format!("{} is not an enum", o)).into()),
+                    None => return Err(RuntimeError::UnknownIdentifier(name.clone()).into()),
+                };
+                match def.tag(variant) {
+                    Some(tag) => Ok((def, tag)),
+                    None => Err(RuntimeError::Field(format!("{} has no variant {}", def.name, variant)).into()),
                 }
             }
-            Infix::LessThan => {
-                match (self.oti(left_obj), self.oti(right_obj)) {
-                    (Ok(i1), Ok(i2)) => Object::Boolean(i1 < i2),
-                    (Err(err), _) | (_, Err(err)) => err,
+            None => match self.env.borrow().get(variant) {
+                Some(Object::Constructor(def, tag)) => Ok((def, tag)),
+                Some(Object::Variant(value)) if value.values.is_empty() => Ok((value.def.clone(), value.tag)),
+                Some(o) => Err(RuntimeError::TypeMismatch(format!("{} is not an enum variant", o)).into()),
+                None => Err(RuntimeError::UnknownIdentifier(variant.clone()).into()),
+            },
+        }
+    }
+    /// evaluation for `enum name { variant(fields), ... }`
+    /// binds the enum type to `name` and each variant to its own name,
+    /// variants without payload are values, the others are constructors
+    fn eval_enum(&mut self, name: Identifier, variants: Vec<(Identifier, Vec<Identifier>)>) -> EvalResult {
+        let Identifier(name) = name;
+        let def = Rc::new(EnumDef {
+            name,
+            variants: variants.into_iter()
+                .map(|(Identifier(variant), fields)| (variant, fields.into_iter().map(|Identifier(field)| field).collect()))
+                .collect(),
+        });
+        self.bind_enum(def)
+    }
+    /// bind an enum type and its variants in the current env
+    fn bind_enum(&mut self, def: Rc<EnumDef>) -> EvalResult {
+        let enum_obj = self.allocate(Object::Enum(def.clone()))?;
+        self.env.borrow_mut().set(&def.name, enum_obj.clone());
+        for (tag, (variant, _)) in def.variants.iter().enumerate() {
+            let variant_obj = self.variant_object(def.clone(), tag)?;
+            self.env.borrow_mut().set(variant, variant_obj);
+        }
+        Ok(enum_obj)
+    }
+    /// the value of a variant without payload, or the constructor of a variant with payload
+    fn variant_object(&mut self, def: Rc<EnumDef>, tag: usize) -> EvalResult {
+        if def.variants[tag].1.is_empty() {
+            self.allocate(Object::Variant(Rc::new(EnumValue { def, tag, values: vec![] })))
+        } else {
+            Ok(Object::Constructor(def, tag))
+        }
+    }
+    /// construct an enum variant value, arguments are the payload in declaration order
+    fn construct_variant(&mut self, def: Rc<EnumDef>, tag: usize, args: Vec<Object>) -> EvalResult {
+        if args.len() != def.variants[tag].1.len() {
+            return Err(RuntimeError::Arity {
+                expected: def.variants[tag].1.len(),
+                given: args.len(),
+            }.into());
+        }
+        self.allocate(Object::Variant(Rc::new(EnumValue { def, tag, values: args })))
+    }
+    /// construct an instance of a struct type, arguments are the fields in declaration order
+    fn construct(&mut self, def: Rc<StructDef>, args: Vec<Object>) -> EvalResult {
+        if args.len() != def.fields.len() {
+            return Err(RuntimeError::Arity {
+                expected: def.fields.len(),
+                given: args.len(),
+            }.into());
+        }
+        self.allocate(Object::Instance(Rc::new(RefCell::new(Instance { def, values: args }))))
+    }
+    /// evaluation for field access `object.field`
+    /// fields and methods of user defined types are looked up before the method table of the type,
+    /// methods are bound to the object
+    fn eval_field(&mut self, object: Expr, field: Identifier) -> EvalResult {
+        let Identifier(field) = field;
+        let object = self.eval_expr(object)?;
+        self.field(object, &field)
+    }
+    /// field or method of an evaluated object, see `eval_field`
+    pub(crate) fn field(&mut self, object: Object, field: &str) -> EvalResult {
+        if let Some(member) = self.eval_member(&object, field) {
+            return member;
+        }
+        if let Some(method) = self.methods.get(object.type_name(), field) {
+            return self.allocate(Object::Method(Box::new(object), Box::new(method)));
+        }
+        let message = match object {
+            Object::Instance(ref instance) => format!("{} has no field {}", instance.borrow().def.name, field),
+            Object::Struct(ref def) => format!("{} has no method {}", def.name, field),
+            Object::Enum(ref def) => format!("{} has no variant {}", def.name, field),
+            Object::Variant(ref value) => format!("{} has no field {}", value.variant(), field),
+            Object::Module(ref module) => format!("{} has no member {}", module.name, field),
+            ref o => format!("{} has no method {}", o.type_name(), field),
+        };
+        Err(RuntimeError::Field(message).into())
+    }
+    /// field, method or variant of a user defined type
+    fn eval_member(&mut self, object: &Object, field: &str) -> Option<EvalResult> {
+        match *object {
+            Object::Instance(ref instance) => {
+                if let Some(value) = instance.borrow().get(field) {
+                    return Some(Ok(value));
                 }
+                let method = instance.borrow().def.methods.get(field).cloned()?;
+                Some(self.bind_method(method, object.clone()))
             }
+            Object::Struct(ref def) => def.methods.get(field).cloned().map(Ok),
+            Object::Enum(ref def) => def.tag(field).map(|tag| self.variant_object(def.clone(), tag)),
+            Object::Variant(ref value) => value.get(field).map(Ok),
+            Object::Module(ref module) => module.members.get(field).cloned().map(Ok),
+            _ => None,
         }
     }
-    /// evaluation for `if {block} else {block}`
-    fn eval_if(&mut self, cond: Box<Expr>, consequence: BlockStatement, alternative: Option<BlockStatement>) -> Object {
-        let cond_obj = self.eval_expr(*cond);
-        match self.otb(cond_obj) {
-            Ok(b) => {
-                if b {
-                    self.eval_statements(consequence)
+    /// bind `self` of a method to the receiver, methods without a `self` parameter are returned as is,
+    /// functions compare by identity and each access binds a new function, so `p.m == p.m` is false
+    fn bind_method(&mut self, method: Object, receiver: Object) -> EvalResult {
+        match method {
+            Object::Function(mut params, body, env) if params.first().map(|Identifier(p)| p == "self").unwrap_or(false) => {
+                params.remove(0);
+                let mut method_env = Environment::new_with_outer(env);
+                method_env.set("self", receiver);
+                self.allocate(Object::Function(params, body, Rc::new(RefCell::new(method_env))))
+            }
+            method => Ok(method),
+        }
+    }
+    /// evaluation for field assignment `object.field = value`
+    fn eval_assign(&mut self, target: Expr, value: Expr) -> EvalResult {
+        let (object, Identifier(field)) = match target {
+            Expr::FieldExpr { object, field } => (*object, field),
+            _ => return Err(RuntimeError::TypeMismatch(String::from("only fields can be assigned")).into()),
+        };
+        let object = self.eval_expr(object)?;
+        let value = self.eval_expr(value)?;
+        match object {
+            Object::Instance(instance) => {
+                if instance.borrow_mut().set(&field, value.clone()) {
+                    Ok(value)
                 } else {
-                    match alternative {
-                        None => Object::NULL,
-                        Some(block) => self.eval_statements(block),
-                    }
+                    let name = instance.borrow().def.name.clone();
+                    Err(RuntimeError::Field(format!("{} has no field {}", name, field)).into())
                 }
             }
-            Err(err) => err
+            o => Err(RuntimeError::Field(format!("{} has no fields", o)).into()),
         }
     }
     /// evaluation for `env:{ fn(parameters){body} }`
-    fn eval_fn(&self, parameters: Vec<Identifier>, body: BlockStatement) -> Object {
-        Object::Function(parameters, body, self.env.clone())
+    fn eval_fn(&mut self, parameters: Vec<Identifier>, body: BlockStatement) -> EvalResult {
+        self.allocate(Object::Function(parameters, Rc::new(body), self.env.clone()))
     }
 
     /// evaluation for array `[item,item,..]`
-    fn eval_array(&mut self, items: Vec<Expr>) -> Object {
-        Object::Array(items.into_iter().map(|item| self.eval_expr(item)).collect())
+    fn eval_array(&mut self, items: Vec<Expr>) -> EvalResult {
+        let array = Object::Array(self.eval_exprs(items)?);
+        self.allocate(array)
+    }
+    /// evaluation for hash `{expr:expr,...}`
+    fn eval_hash(&mut self, hash_pairs: Vec<(Expr, Expr)>) -> EvalResult {
+        let hash = Object::Hash(hash_pairs.into_iter()
+            .map(|(k, e)| {
+                let key = self.eval_expr(k)?;
+                Ok((self.othash(key)?, self.eval_expr(e)?))
+            }).collect::<Result<_, EvalError>>()?);
+        self.allocate(hash)
+    }
+
+    /// evaluation for a list of expressions, e.g. call arguments
+    fn eval_exprs(&mut self, exprs: Vec<Expr>) -> Result<Vec<Object>, EvalError> {
+        exprs.into_iter().map(|expr| self.eval_expr(expr)).collect()
     }
-    /// evaluation for hash `{literal:expr,...}`
-    fn eval_hash(&mut self, hash_pairs: Vec<(Literal, Expr)>) -> Object {
-        Object::Hash(hash_pairs.into_iter()
-            .map(|(l, e)|
-                (self.othash(self.eval_literal(l)), self.eval_expr(e))
-            ).collect())
+
+    /// evaluation for the callee of a call and its stack frame,
+    /// the frame is named after the builtin or the called identifier
+    fn eval_callee(&mut self, function: Expr, span: Span) -> Result<(Frame, Object), EvalError> {
+        let ident = match function {
+            Expr::IdentExpr(Identifier(ref name)) => Some(name.clone()),
+            Expr::FieldExpr { field: Identifier(ref name), .. } => Some(name.clone()),
+            _ => None,
+        };
+        let func = self.eval_expr(function)?;
+        let name = match (&func, ident) {
+            (Object::Builtin(name, _, _), _) | (Object::Closure(name, _, _), _) => name.clone(),
+            (_, Some(name)) => name,
+            _ => String::from("<anonymous>"),
+        };
+        Ok((Frame { function: name, span }, func))
     }
 
     /// evaluation for call `(func_expr)([arguments])`
-    fn eval_call(&mut self, function: Box<Expr>, arguments: Vec<Expr>) -> Object {
-        let func = self.eval_expr(*function);
-        match self.otfn(func) {
+    fn eval_call(&mut self, function: Expr, arguments: Vec<Expr>, span: Span) -> EvalResult {
+        let (frame, func) = self.eval_callee(function, span)?;
+        match self.otfn(func)? {
             Object::Function(params, body, f_env) => {
-                self.eval_fn_call(arguments, params, body, f_env)
+                self.eval_fn_call(frame, arguments, params, body, f_env)
             }
             Object::Builtin(_, num_params, b_fn) => {
-                self.eval_builtin_call(arguments, num_params, b_fn)
+                self.eval_builtin_call(frame, arguments, num_params, b_fn)
             }
-            o => o,
+            Object::Closure(_, num_params, NativeFunction(f)) => {
+                let args = self.eval_exprs(arguments)?;
+                self.apply_builtin(frame, args, num_params, &*f)
+            }
+            Object::Struct(def) => {
+                let args = self.eval_exprs(arguments)?;
+                self.construct(def, args)
+            }
+            Object::Constructor(def, tag) => {
+                let args = self.eval_exprs(arguments)?;
+                self.construct_variant(def, tag, args)
+            }
+            method @ Object::Method(_, _) => {
+                let args = self.eval_exprs(arguments)?;
+                self.apply(frame, method, args)
+            }
+            generator @ Object::Generator(_, _, _) => {
+                let args = self.eval_exprs(arguments)?;
+                self.apply(frame, generator, args)
+            }
+            o => Ok(o),
         }
     }
 
     /// evaluation for index `(left_expr)[index_expr]`
-    fn eval_index(&mut self, left: Box<Expr>, index: Box<Expr>) -> Object {
-        let index = self.eval_expr(*index);
-        match self.eval_expr(*left) {
-            Object::Array(arr) => match self.oti(index) {
-                Ok(index_number) => arr
-                    .into_iter()
-                    .nth(index_number as usize)
-                    .unwrap_or(Object::NULL).clone(),
-                Err(err) => err,
-            },
-            Object::Hash(hash) => {
-                let name = self.othash(index);
-                match name {
-                    Object::Error(_) => name,
-                    _ => hash.get(&name).unwrap_or(&Object::NULL).clone()
-                }
+    fn eval_index(&mut self, left: Expr, index: Expr) -> EvalResult {
+        let index = self.eval_expr(index)?;
+        if let Expr::IdentExpr(Identifier(ref name)) = left {
+            // a variable is indexed in place, so that `items[i]` does not copy the whole array
+            let env = self.env.clone();
+            let item = env.borrow().with(name, |target| self.index(target, index));
+            return item.unwrap_or_else(|| Err(RuntimeError::UnknownIdentifier(name.clone()).into()));
+        }
+        let target = self.eval_expr(left)?;
+        self.index(&target, index)
+    }
+    /// the item of an array or a hash at the index, `null` if there is none
+    fn index(&mut self, target: &Object, index: Object) -> EvalResult {
+        match *target {
+            Object::Array(ref arr) => {
+                let index_number = self.oti(index)?;
+                Ok(arr
+                    .get(index_number as usize)
+                    .cloned()
+                    .unwrap_or(Object::NULL))
+            }
+            Object::Hash(ref hash) => {
+                let name = self.othash(index)?;
+                Ok(hash.get(&name).unwrap_or(&Object::NULL).clone())
             }
-            o => Object::Error(format!("unexpected index target: {}", o)),
+            ref o => Err(RuntimeError::Index(format!("unexpected index target: {}", o)).into()),
         }
     }
-    fn eval_fn_call(&mut self, arguments: Vec<Expr>, params: Vec<Identifier>, body: BlockStatement, env: Rc<RefCell<Environment>>) -> Object {
+    fn eval_fn_call(&mut self, frame: Frame, arguments: Vec<Expr>, params: Vec<Identifier>, body: Rc<BlockStatement>, env: Rc<RefCell<Environment>>) -> EvalResult {
         if arguments.len() != params.len() {
-            Object::Error(format!(
-                "wrong number of arguments: {} expected but {} given",
-                params.len(),
-                arguments.len()
-            ))
+            Err(RuntimeError::Arity {
+                expected: params.len(),
+                given: arguments.len(),
+            }.into())
         } else {
-            let args = arguments.into_iter().map(|arg| self.eval_expr(arg)).collect::<Vec<_>>();
-            let current_env = self.env.clone();
+            let args = self.eval_exprs(arguments)?;
+            self.apply_fn(frame, params, body, env, args)
+        }
+    }
+
+    /// call a function or builtin with evaluated arguments
+    fn apply(&mut self, frame: Frame, func: Object, args: Vec<Object>) -> EvalResult {
+        match self.otfn(func)? {
+            Object::Function(params, body, env) => {
+                if args.len() != params.len() {
+                    return Err(RuntimeError::Arity {
+                        expected: params.len(),
+                        given: args.len(),
+                    }.into());
+                }
+                self.apply_fn(frame, params, body, env, args)
+            }
+            Object::Generator(params, body, env) => self.call_generator(frame, params, body, env, args),
+            Object::Builtin(_, num_params, b_fn) => self.apply_builtin(frame, args, num_params, &b_fn),
+            Object::Closure(_, num_params, NativeFunction(f)) => self.apply_builtin(frame, args, num_params, &*f),
+            Object::Struct(def) => self.construct(def, args),
+            Object::Constructor(def, tag) => self.construct_variant(def, tag, args),
+            Object::Method(receiver, method) => {
+                // the receiver is not counted in arity errors
+                if let Object::Builtin(_, num_params, _) | Object::Closure(_, num_params, _) = *method {
+                    if args.len() + 1 != num_params {
+                        return Err(RuntimeError::Arity {
+                            expected: num_params.saturating_sub(1),
+                            given: args.len(),
+                        }.into());
+                    }
+                }
+                let mut args = args;
+                args.insert(0, *receiver);
+                self.apply(frame, *method, args)
+            }
+            o => Ok(o),
+        }
+    }
 
+    /// run a function body with evaluated arguments
+    /// calls in tail position reuse this loop instead of growing the rust stack,
+    /// so only nested (non-tail) calls count towards `max_depth`
+    fn apply_fn(&mut self, mut frame: Frame, mut params: Vec<Identifier>, mut body: Rc<BlockStatement>, mut env: Rc<RefCell<Environment>>, mut args: Vec<Object>) -> EvalResult {
+        if self.depth >= self.max_depth {
+            return Err(RuntimeError::RecursionDepth.into());
+        }
+        self.depth += 1;
+        let current_env = self.env.clone();
+        let result = loop {
             // set function env from arguments and params
-            let mut function_env = Environment::new_with_outer(env.clone());
-            for (_, (Identifier(ident), obj)) in params.into_iter().zip(args).enumerate() {
+            let mut function_env = Environment::new_with_outer(env);
+            for (Identifier(ident), obj) in params.into_iter().zip(args) {
                 function_env.set(&ident, obj);
             }
             // evaluation body with function env
             self.env = Rc::new(RefCell::new(function_env));
-            let result = self.eval_statements(body);
-            // reset env
-            self.env = current_env;
-            match result {
-                Return(v) => *v,
-                o => o,
+            // nested calls grow onto new stack segments, so deep recursion is bounded by `max_depth`
+            // instead of the size of the thread stack
+            let statements = BlockStatement::clone(&body);
+            let tail = stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT_SIZE, || self.eval_tail_statements(statements, true));
+            match tail {
+                Err(err) => break Err(err.push_frame(frame)),
+                Ok(TailEval::Value(Return(v))) => break Ok(*v),
+                Ok(TailEval::Value(o)) => break Ok(o),
+                Ok(TailEval::Call(next, Object::Function(p, b, e), a)) => {
+                    if a.len() != p.len() {
+                        let err = EvalError::from(RuntimeError::Arity {
+                            expected: p.len(),
+                            given: a.len(),
+                        });
+                        break Err(err.push_frame(frame));
+                    }
+                    frame = next;
+                    params = p;
+                    body = b;
+                    env = e;
+                    args = a;
+                }
+                Ok(TailEval::Call(next, func, a)) => break self.apply(next, func, a).map_err(|err| err.push_frame(frame)),
+            }
+        };
+        // reset env
+        self.env = current_env;
+        self.depth -= 1;
+        result
+    }
+
+    /// evaluation statements of a function body, `tail` marks whether the value of the block is
+    /// the value of the function
+    fn eval_tail_statements(&mut self, statements: BlockStatement, tail: bool) -> Result<TailEval, EvalError> {
+        let mut result = NULL;
+        let last = statements.len().saturating_sub(1);
+        for (i, stmt) in statements.into_iter().enumerate() {
+            let stmt_obj = match stmt {
+                // `return f(..)` is always a tail call
+                Stmt::ReturnStmt(expr) => {
+                    self.budget.tick()?;
+                    return Ok(match self.eval_tail_expr(expr)? {
+                        TailEval::Value(o @ Return(_)) => TailEval::Value(o),
+                        TailEval::Value(o) => TailEval::Value(Return(Box::new(o))),
+                        call => call,
+                    });
+                }
+                Stmt::ExprStmt(expr) if tail && i == last => {
+                    self.budget.tick()?;
+                    return self.eval_tail_expr(expr);
+                }
+                // an `if` in the middle of the body may still contain `return f(..)`
+                Stmt::ExprStmt(Expr::IfExpr { cond, consequence, alternative }) => {
+                    self.budget.tick()?;
+                    match self.eval_tail_if(*cond, consequence, alternative, false)? {
+                        TailEval::Value(o) => o,
+                        call => return Ok(call),
+                    }
+                }
+                Stmt::ExprStmt(Expr::MatchExpr { subject, arms }) => {
+                    self.budget.tick()?;
+                    match self.eval_tail_match(*subject, arms, false)? {
+                        TailEval::Value(o) => o,
+                        call => return Ok(call),
+                    }
+                }
+                stmt => self.eval_stmt(stmt)?,
+            };
+            if let Return(_) = stmt_obj {
+                return Ok(TailEval::Value(stmt_obj));
+            }
+            result = stmt_obj;
+        }
+        Ok(TailEval::Value(result))
+    }
+
+    /// evaluation expr in tail position
+    fn eval_tail_expr(&mut self, expr: Expr) -> Result<TailEval, EvalError> {
+        match expr {
+            Expr::CallExpr { function, mut arguments, .. } if is_special_form(&function, &arguments, "quote") => {
+                Ok(TailEval::Value(self.eval_quote(arguments.remove(0))?))
+            }
+            Expr::CallExpr { function, arguments, span } => {
+                let (frame, func) = self.eval_callee(*function, span)?;
+                let func = self.otfn(func)?;
+                let args = self.eval_exprs(arguments)?;
+                Ok(TailEval::Call(frame, func, args))
+            }
+            Expr::IfExpr { cond, consequence, alternative } => self.eval_tail_if(*cond, consequence, alternative, true),
+            Expr::MatchExpr { subject, arms } => self.eval_tail_match(*subject, arms, true),
+            expr => Ok(TailEval::Value(self.eval_expr(expr)?)),
+        }
+    }
+
+    /// evaluation for `if {block} else {block}` inside a function body
+    fn eval_tail_if(&mut self, cond: Expr, consequence: BlockStatement, alternative: Option<BlockStatement>, tail: bool) -> Result<TailEval, EvalError> {
+        let cond_obj = self.eval_expr(cond)?;
+        if self.otb(cond_obj)? {
+            self.eval_tail_statements(consequence, tail)
+        } else {
+            match alternative {
+                None => Ok(TailEval::Value(Object::NULL)),
+                Some(block) => self.eval_tail_statements(block, tail),
             }
         }
     }
-    fn eval_builtin_call(&mut self, arguments: Vec<Expr>, num_params: usize, b_fn: BuiltinFunction) -> Object {
-        if arguments.len() != num_params {
-            Object::Error(format!(
-                "wrong number of arguments: {} expected but {} given",
-                num_params,
-                arguments.len()
-            ))
+
+    /// evaluation for `match (subject) { pattern => body, ... }` inside a function body
+    fn eval_tail_match(&mut self, subject: Expr, arms: Vec<(Pattern, BlockStatement)>, tail: bool) -> Result<TailEval, EvalError> {
+        match self.match_arm(subject, arms)? {
+            Some((arm_env, body)) => self.in_env(arm_env, |evaluator| evaluator.eval_tail_statements(body, tail)),
+            None => Ok(TailEval::Value(Object::NULL)),
+        }
+    }
+
+    fn eval_builtin_call(&mut self, frame: Frame, arguments: Vec<Expr>, num_params: usize, b_fn: BuiltinFunction) -> EvalResult {
+        let args = self.eval_exprs(arguments)?;
+        self.apply_builtin(frame, args, num_params, &b_fn)
+    }
+
+    /// call a builtin or a host closure
+    fn apply_builtin(&mut self, frame: Frame, args: Vec<Object>, num_params: usize, b_fn: &NativeFn) -> EvalResult {
+        if args.len() != num_params {
+            Err(RuntimeError::Arity {
+                expected: num_params,
+                given: args.len(),
+            }.into())
         } else {
-            let args = arguments.into_iter().map(|arg| self.eval_expr(arg)).collect::<Vec<_>>();
-            b_fn(args).unwrap_or_else(Object::Error)
+            let outer_span = std::mem::replace(&mut self.builtin_span, frame.span);
+            // builtins may call back into functions, so they grow the stack like function bodies
+            let result = stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT_SIZE, || b_fn(self, args));
+            self.builtin_span = outer_span;
+            match result {
+                Ok(o) => self.allocate(o),
+                Err(err) => Err(err.push_frame(frame)),
+            }
         }
     }
 }
@@ -320,11 +1118,35 @@ impl Evaluator {
 
 #[cfg(test)]
 mod tests {
+    use std::cell::Cell;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+    use std::thread;
+    use std::time::{Duration, Instant};
     use crate::evaluator::Evaluator;
+    use crate::evaluator::capabilities::Capabilities;
+    use crate::evaluator::clock::ManualClock;
+    use crate::evaluator::error::{exit_status, EvalError, Frame, RuntimeError};
+    use crate::evaluator::limits::{EvalLimits, LimitExceeded};
     use crate::lexer::Lexer;
-    use crate::object::objects::Object;
+    use crate::object::convert::{FromObject, ToObject};
+    use crate::object::objects::{NativeObject, Object};
     use crate::parser::Parser;
-    use crate::token::Tokens;
+    use crate::token::{Span, Tokens};
+
+    fn eval_with_limits(input: &[u8], limits: EvalLimits) -> (Object, Option<LimitExceeded>) {
+        let (_, r) = Lexer::lexing(input).unwrap();
+        let (_, program) = Parser::parsing(Tokens::new(&r)).unwrap();
+        let mut evaluator = Evaluator::with_limits(limits);
+        let eval = evaluator.evaluation(program);
+        (eval, evaluator.limit_exceeded())
+    }
+
+    fn eval_program(input: &[u8]) -> Result<Object, EvalError> {
+        let (_, (tokens, spans)) = Lexer::lexing_with_spans(input).unwrap();
+        let (_, program) = Parser::parsing(Tokens::with_spans(&tokens, &spans)).unwrap();
+        Evaluator::new().eval_program(program)
+    }
 
     fn compare(input: &[u8], object: Object) {
         let (_, r) = Lexer::lexing(input).unwrap();
@@ -694,8 +1516,8 @@ mod tests {
             Object::NULL,
         );
         compare(
-            (input_beg.clone() + "h[[]]").as_bytes(),
-            Object::Error("[] is not hashable".to_string()),
+            (input_beg.clone() + "struct P { x }; h[[P(1)]]").as_bytes(),
+            Object::Error("[P { x: 1 }] is not hashable".to_string()),
         );
         compare(
             (input_beg + "3[true];").as_bytes(),
@@ -796,4 +1618,1502 @@ mod tests {
             Object::Integer(15),
         );
     }
+
+    #[test]
+    fn test_tail_call() {
+        let count_input = "let count = fn(n) { if (n == 0) { 0 } else { count(n - 1) } };\
+             count(100000)\
+            "
+            .as_bytes();
+
+        let return_input = "let sum = fn(n, acc) {\
+                if (n == 0) { return acc; }\
+                return sum(n - 1, acc + n);\
+             };\
+             sum(100000, 0)\
+            "
+            .as_bytes();
+
+        let mutual_input = "let even = fn(n) { if (n == 0) { true } else { odd(n - 1) } };\
+             let odd = fn(n) { if (n == 0) { false } else { even(n - 1) } };\
+             even(10001)\
+            "
+            .as_bytes();
+
+        compare(count_input, Object::Integer(0));
+        compare(return_input, Object::Integer(5000050000));
+        compare(mutual_input, Object::Boolean(false));
+        compare(
+            "let f = fn(x) { return len(x); }; f([1, 2])".as_bytes(),
+            Object::Integer(2),
+        );
+        compare(
+            "let g = fn(x, y) { x + y }; let f = fn(x) { g(x) }; f(1)".as_bytes(),
+            Object::Error("wrong number of arguments: 2 expected but 1 given".to_string()),
+        );
+    }
+
+    #[test]
+    fn test_recursion_limit() {
+        let deep_input = "let f = fn(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } };\
+             f(100000)\
+            "
+            .as_bytes();
+        compare(
+            deep_input,
+            Object::Error("maximum recursion depth exceeded".to_string()),
+        );
+
+        let mut evaluator = Evaluator::new();
+        evaluator.set_max_depth(11);
+        let define = "let f = fn(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } };";
+        for (input, expected) in [
+            ("f(10)", Object::Integer(10)),
+            ("[f(10), f(11)]", Object::Error("maximum recursion depth exceeded".to_string())),
+        ] {
+            let (_, r) = Lexer::lexing((define.to_string() + input).as_bytes()).unwrap();
+            let (_, program) = Parser::parsing(Tokens::new(&r)).unwrap();
+            assert_eq!(evaluator.evaluation(program), expected);
+        }
+    }
+
+    #[test]
+    fn test_limits() {
+        let forever = "let f = fn() { f() }; f(); 1".as_bytes();
+
+        let (eval, limit) = eval_with_limits(forever, EvalLimits { max_steps: Some(1000), ..Default::default() });
+        assert_eq!(eval, Object::Error("step limit exceeded".to_string()));
+        assert_eq!(limit, Some(LimitExceeded::Steps));
+
+        let deadline = Some(Instant::now() + Duration::from_millis(50));
+        let (eval, limit) = eval_with_limits(forever, EvalLimits { deadline, ..Default::default() });
+        assert_eq!(eval, Object::Error("deadline exceeded".to_string()));
+        assert_eq!(limit, Some(LimitExceeded::Deadline));
+
+        let grow_array = "let f = fn(arr) { f(cons(1, arr)) }; f([])".as_bytes();
+        let (eval, limit) = eval_with_limits(grow_array, EvalLimits { max_objects: Some(10000), ..Default::default() });
+        assert_eq!(eval, Object::Error("object limit exceeded".to_string()));
+        assert_eq!(limit, Some(LimitExceeded::Objects));
+
+        let grow_string = "let f = fn(s) { f(s + s) }; f(\"ab\")".as_bytes();
+        let (eval, limit) = eval_with_limits(grow_string, EvalLimits { max_bytes: Some(1 << 20), ..Default::default() });
+        assert_eq!(eval, Object::Error("memory limit exceeded".to_string()));
+        assert_eq!(limit, Some(LimitExceeded::Bytes));
+
+        // the budgets count every allocation, also of objects which are dropped again
+        let churn = "let f = fn(n) { if (n > 0) { let a = [1, 2]; f(n - 1) } else { 0 } }; f(1000)".as_bytes();
+        let (eval, limit) = eval_with_limits(churn, EvalLimits { max_objects: Some(1000), ..Default::default() });
+        assert_eq!(eval, Object::Error("object limit exceeded".to_string()));
+        assert_eq!(limit, Some(LimitExceeded::Objects));
+
+        let (eval, limit) = eval_with_limits("let a = [1, 2, 3]; len(a)".as_bytes(), EvalLimits {
+            max_steps: Some(1000),
+            max_objects: Some(100),
+            max_bytes: Some(1 << 20),
+            deadline: Some(Instant::now() + Duration::from_secs(60)),
+        });
+        assert_eq!(eval, Object::Integer(3));
+        assert_eq!(limit, None);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let (_, r) = Lexer::lexing("let f = fn() { f() }; f()".as_bytes()).unwrap();
+        let (_, program) = Parser::parsing(Tokens::new(&r)).unwrap();
+        let mut evaluator = Evaluator::new();
+        let handle = evaluator.cancel_handle();
+        let canceller = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            handle.cancel();
+        });
+        assert_eq!(
+            evaluator.evaluation(program),
+            Object::Error("evaluation cancelled".to_string())
+        );
+        assert_eq!(evaluator.limit_exceeded(), Some(LimitExceeded::Cancelled));
+        canceller.join().unwrap();
+    }
+
+    #[test]
+    fn test_embedding() {
+        let run = |evaluator: &mut Evaluator, input: &str| {
+            let (_, r) = Lexer::lexing(input.as_bytes()).unwrap();
+            let (_, program) = Parser::parsing(Tokens::new(&r)).unwrap();
+            evaluator.evaluation(program)
+        };
+        let mut evaluator = Evaluator::new();
+
+        // closures with captured state
+        let calls = Rc::new(Cell::new(0));
+        let counter = calls.clone();
+        evaluator.register_fn("tick", 1, move |_, args| {
+            counter.set(counter.get() + 1);
+            let step = i64::from_object(args.into_iter().next().unwrap())?;
+            Ok((counter.get() * step).to_object())
+        });
+        assert_eq!(run(&mut evaluator, "tick(10); tick(10)"), Object::Integer(20));
+        assert_eq!(calls.get(), 2);
+        assert_eq!(run(&mut evaluator, "[1, 2].map(fn(x) { tick(x) })"), Object::Array(vec![
+            Object::Integer(3),
+            Object::Integer(8),
+        ]));
+        assert_eq!(
+            run(&mut evaluator, "tick(\"a\")"),
+            Object::Error("a is not an integer".to_string())
+        );
+        assert_eq!(
+            run(&mut evaluator, "tick()"),
+            Object::Error("wrong number of arguments: 1 expected but 0 given".to_string())
+        );
+
+        // host values with methods
+        let mut config = HashMap::new();
+        config.insert("name".to_string(), "lang".to_string());
+        evaluator.set_global("config", &NativeObject::new("Config", config));
+        evaluator.register_method("Config", "get", 2, |_, args| {
+            let mut args = args.into_iter();
+            let config = NativeObject::from_object(args.next().unwrap())?;
+            let key = String::from_object(args.next().unwrap())?;
+            let config = config.borrow::<HashMap<String, String>>().unwrap();
+            Ok(config.get(&key).to_object())
+        });
+        evaluator.register_method("Config", "set", 3, |_, args| {
+            let mut args = args.into_iter();
+            let config = NativeObject::from_object(args.next().unwrap())?;
+            let key = String::from_object(args.next().unwrap())?;
+            let value = String::from_object(args.next().unwrap())?;
+            config.borrow_mut::<HashMap<String, String>>().unwrap().insert(key, value);
+            Ok(Object::NULL)
+        });
+        assert_eq!(run(&mut evaluator, "config.get(\"name\")"), Object::String("lang".to_string()));
+        assert_eq!(run(&mut evaluator, "config.get(\"nope\")"), Object::NULL);
+        run(&mut evaluator, "let set = config.set; set(\"mode\", \"fast\")");
+        let Some(Object::Native(config)) = evaluator.get_global("config") else { panic!() };
+        assert_eq!(config.borrow::<HashMap<String, String>>().unwrap()["mode"], "fast");
+        assert_eq!(run(&mut evaluator, "config == config"), Object::Boolean(true));
+        assert_eq!(run(&mut evaluator, "config.nope()"), Object::Error("Config has no method nope".to_string()));
+        assert_eq!(run(&mut evaluator, "config").to_string(), "[native: Config]");
+
+        // globals
+        evaluator.set_global("limits", &vec![1i64, 2]);
+        assert_eq!(run(&mut evaluator, "let total = limits[0] + limits[1];"), Object::Integer(3));
+        assert_eq!(evaluator.get_global("total").map(i64::from_object), Some(Ok(3)));
+    }
+
+    #[test]
+    fn test_runtime_errors() {
+        assert_eq!(
+            eval_program("[1, foo]".as_bytes()),
+            Err(EvalError::from(RuntimeError::UnknownIdentifier("foo".to_string())))
+        );
+        assert_eq!(
+            eval_program("let f = fn(x) { x }; f(1, 2)".as_bytes()),
+            Err(EvalError::from(RuntimeError::Arity { expected: 1, given: 2 }))
+        );
+        assert_eq!(
+            eval_program("{\"a\": 1}[if (false) { 1 }]".as_bytes()),
+            Err(EvalError::from(RuntimeError::HashKey("null is not hashable".to_string())))
+        );
+        assert_eq!(
+            eval_program("5[0]".as_bytes()),
+            Err(EvalError::from(RuntimeError::Index("unexpected index target: 5".to_string())))
+        );
+        assert_eq!(
+            eval_program("1 + true".as_bytes()),
+            Err(EvalError::from(RuntimeError::TypeMismatch(
+                "Integer(1) and Boolean(true) are not addable".to_string()
+            )))
+        );
+    }
+
+    #[test]
+    fn test_traceback() {
+        let input = "let inner = fn(x) { head(x) + 1 };\n\
+             let outer = fn(x) { let y = inner(x); y };\n\
+             outer([]);\n\
+            "
+            .as_bytes();
+        let err = eval_program(input).unwrap_err();
+        assert_eq!(err.error, RuntimeError::Builtin("empty array".to_string()));
+        assert_eq!(
+            err.stack,
+            vec![
+                Frame { function: "head".to_string(), span: Span { line: 1, column: 25 } },
+                Frame { function: "inner".to_string(), span: Span { line: 2, column: 34 } },
+                Frame { function: "outer".to_string(), span: Span { line: 3, column: 6 } },
+            ]
+        );
+        assert_eq!(
+            err.to_string(),
+            "Traceback (most recent call last):\n  \
+             outer called at line 3, column 6\n  \
+             inner called at line 2, column 34\n  \
+             head called at line 1, column 25\n\
+             Builtin: empty array"
+        );
+
+        // frames replaced by tail calls are not kept
+        let input = "let check = fn(x) { if (x) { 1 } else { 0 } };\n\
+             let f = fn(x) { check(x) };\n\
+             f(5)\n\
+            "
+            .as_bytes();
+        let err = eval_program(input).unwrap_err();
+        assert_eq!(err.error, RuntimeError::TypeMismatch("5 is not a bool".to_string()));
+        assert_eq!(
+            err.stack,
+            vec![Frame { function: "check".to_string(), span: Span { line: 2, column: 22 } }]
+        );
+    }
+
+    #[test]
+    fn test_try_catch() {
+        compare(
+            "try { head([]) } catch (e) { e[\"message\"] }".as_bytes(),
+            Object::String("empty array".to_string()),
+        );
+        compare(
+            "try { throw \"boom\"; 1 } catch (e) { [e[\"kind\"], e[\"message\"]] }".as_bytes(),
+            Object::Array(vec![
+                Object::String("Thrown".to_string()),
+                Object::String("boom".to_string()),
+            ]),
+        );
+        compare(
+            "try { 1 } catch (e) { 2 }".as_bytes(),
+            Object::Integer(1),
+        );
+        compare(
+            "let r = try { foo } catch (e) { e[\"kind\"] } finally { let done = true; }; [r, done]".as_bytes(),
+            Object::Array(vec![
+                Object::String("UnknownIdentifier".to_string()),
+                Object::Boolean(true),
+            ]),
+        );
+        compare(
+            "try { throw 1 } finally { 2 }".as_bytes(),
+            Object::Error("1".to_string()),
+        );
+        compare(
+            "let f = fn() { try { return 1; } finally { return 2; } }; f()".as_bytes(),
+            Object::Integer(2),
+        );
+        compare(
+            "try { throw 1 } catch (e) { throw e[\"message\"] + \"!\" }".as_bytes(),
+            Object::Error("1!".to_string()),
+        );
+
+        let stack_input = "let inner = fn(x) { let y = x + 1; throw y };\n\
+             let outer = fn(x) { let r = inner(x); r };\n\
+             try { outer(1) } catch (e) { e[\"stack\"] }\n\
+            "
+            .as_bytes();
+        let stack = |function: &str, line: i64, column: i64| {
+            Object::Hash(vec![
+                (Object::String("function".to_string()), Object::String(function.to_string())),
+                (Object::String("line".to_string()), Object::Integer(line)),
+                (Object::String("column".to_string()), Object::Integer(column)),
+            ].into_iter().collect())
+        };
+        assert_eq!(
+            eval_program(stack_input),
+            Ok(Object::Array(vec![stack("inner", 2, 34), stack("outer", 3, 12)]))
+        );
+
+        // exhausted budgets can not be caught
+        let (eval, limit) = eval_with_limits(
+            "try { let f = fn() { f() }; f() } catch (e) { 1 }".as_bytes(),
+            EvalLimits { max_steps: Some(100), ..Default::default() },
+        );
+        assert_eq!(eval, Object::Error("step limit exceeded".to_string()));
+        assert_eq!(limit, Some(LimitExceeded::Steps));
+    }
+
+    #[test]
+    fn test_struct() {
+        let point = "struct Point {\
+                 x, y;\
+                 fn sum(self) { self.x + self.y }\
+                 fn move_by(self, dx) { self.x = self.x + dx; self }\
+                 fn origin() { Point(0, 0) }\
+             };\
+            ";
+        let with_point = |input: &str| format!("{}{}", point, input);
+
+        compare(with_point("let p = Point(1, 2); p.x").as_bytes(), Object::Integer(1));
+        compare(with_point("Point(1, 2).sum()").as_bytes(), Object::Integer(3));
+        compare(with_point("let p = Point(1, 2); p.y = 5; p.sum()").as_bytes(), Object::Integer(6));
+        compare(with_point("let p = Point(1, 2); p.move_by(10).move_by(1); p.x").as_bytes(), Object::Integer(12));
+        compare(with_point("let f = Point(1, 2).sum; f()").as_bytes(), Object::Integer(3));
+        compare(with_point("Point.origin().sum()").as_bytes(), Object::Integer(0));
+        compare(with_point("Point.sum(Point(3, 4))").as_bytes(), Object::Integer(7));
+        // every access binds a new method, so bound methods are only equal to themselves
+        compare(with_point("let p = Point(1, 2); let m = p.sum; [p.sum == p.sum, m == m]").as_bytes(), Object::Array(vec![
+            Object::Boolean(false),
+            Object::Boolean(true),
+        ]));
+        // structural equality
+        compare(with_point("Point(1, 2) == Point(1, 2)").as_bytes(), Object::Boolean(true));
+        compare(with_point("Point(1, 2) == Point(2, 1)").as_bytes(), Object::Boolean(false));
+        compare(
+            "struct A { x }; struct B { x }; A(1) == B(1)".as_bytes(),
+            Object::Boolean(false),
+        );
+        compare("struct Empty {}; Empty() == Empty()".as_bytes(), Object::Boolean(true));
+
+        let (_, r) = Lexer::lexing(with_point("Point(1, Point(2, 3))").as_bytes()).unwrap();
+        let (_, program) = Parser::parsing(Tokens::new(&r)).unwrap();
+        let instance = Evaluator::new().evaluation(program);
+        assert_eq!(instance.to_string(), "Point { x: 1, y: Point { x: 2, y: 3 } }");
+        let (_, r) = Lexer::lexing(with_point("Point").as_bytes()).unwrap();
+        let (_, program) = Parser::parsing(Tokens::new(&r)).unwrap();
+        assert_eq!(Evaluator::new().evaluation(program).to_string(), "[struct: Point]");
+
+        // a field pointing back at its own instance is compared and printed without recursing forever
+        let cyclic = "struct P { x }; let p = P(1); p.x = p; let q = P(1); q.x = q;";
+        let with_cyclic = |input: &str| format!("{}{}", cyclic, input);
+        compare(with_cyclic("[p == q, p == p, p == P(1), p == P(p)]").as_bytes(), Object::Array(vec![
+            Object::Boolean(true),
+            Object::Boolean(true),
+            Object::Boolean(false),
+            Object::Boolean(true),
+        ]));
+        let (_, r) = Lexer::lexing(with_cyclic("[p, P(q)]").as_bytes()).unwrap();
+        let (_, program) = Parser::parsing(Tokens::new(&r)).unwrap();
+        let instances = Evaluator::new().evaluation(program);
+        assert_eq!(instances.to_string(), "[P { x: ... }, P { x: P { x: ... } }]");
+        assert!(format!("{:?}", instances).contains("values: [Instance(RefCell { value: ... })]"));
+
+        // errors
+        compare(
+            with_point("Point(1)").as_bytes(),
+            Object::Error("wrong number of arguments: 2 expected but 1 given".to_string()),
+        );
+        compare(with_point("Point(1, 2).z").as_bytes(), Object::Error("Point has no field z".to_string()));
+        compare(with_point("let p = Point(1, 2); p.z = 1").as_bytes(), Object::Error("Point has no field z".to_string()));
+        compare("5.x".as_bytes(), Object::Error("integer has no method x".to_string()));
+        compare("let a = 5; a.x = 1".as_bytes(), Object::Error("5 has no fields".to_string()));
+        assert_eq!(
+            eval_program(with_point("Point(1, 2).z").as_bytes()).unwrap_err().error.kind(),
+            "Field"
+        );
+    }
+
+    #[test]
+    fn test_match() {
+        let point = "struct Point { x, y };";
+        let with_point = |input: &str| format!("{}{}", point, input);
+
+        compare("match (1) { 0 => 10, 1 => 11, _ => 12 }".as_bytes(), Object::Integer(11));
+        compare("match (5) { 0 => 10, n => n * 2 }".as_bytes(), Object::Integer(10));
+        compare("match (\"a\") { \"b\" => 1 }".as_bytes(), Object::NULL);
+        compare(
+            with_point("match (Point(0, 7)) { Point { x: 1 } => 1, Point { x: 0, y } => { let z = y + 1; z } }").as_bytes(),
+            Object::Integer(8),
+        );
+        compare(
+            with_point("match (Point(1, Point(2, 3))) { Point { y: Point { x, y } } => x * y }").as_bytes(),
+            Object::Integer(6),
+        );
+        compare(
+            with_point("struct Other { x, y }; match (Other(0, 0)) { Point { x } => 1, _ => 2 }").as_bytes(),
+            Object::Integer(2),
+        );
+        compare(
+            "match (1) { Nope { x } => 1 }".as_bytes(),
+            Object::Error("identifier not found: Nope".to_string()),
+        );
+        // the bindings of an arm are local to the arm body
+        compare("let n = 1; match (5) { n => n * 2 }; n".as_bytes(), Object::Integer(1));
+        compare(
+            with_point("match (Point(1, 2)) { Point { x, y } => { let z = x + y; z } }; z").as_bytes(),
+            Object::Error("identifier not found: z".to_string()),
+        );
+        compare("let f = fn(n) { match (n) { m => m + n } }; f(2)".as_bytes(), Object::Integer(4));
+
+        // match arms in tail position do not grow the call stack
+        compare(
+            "let count = fn(n) { match (n) { 0 => \"done\", _ => count(n - 1) } }; count(100000)".as_bytes(),
+            Object::String("done".to_string()),
+        );
+    }
+
+    #[test]
+    fn test_enum() {
+        let shape = "enum Shape { Circle(r), Rect(w, h), Empty };\
+             let area = fn(s) {\
+                 match (s) { Circle(r) => 3 * r * r, Shape.Rect(w, h) => w * h, Empty => 0 }\
+             };\
+            ";
+        let with_shape = |input: &str| format!("{}{}", shape, input);
+
+        compare(with_shape("area(Circle(2))").as_bytes(), Object::Integer(12));
+        compare(with_shape("area(Shape.Rect(2, 3))").as_bytes(), Object::Integer(6));
+        compare(with_shape("area(Empty)").as_bytes(), Object::Integer(0));
+        compare(with_shape("area(Shape.Empty)").as_bytes(), Object::Integer(0));
+        compare(with_shape("Rect(2, 3).h").as_bytes(), Object::Integer(3));
+        // constructors are first-class values
+        compare(with_shape("let make = Shape.Circle; area(make(1))").as_bytes(), Object::Integer(3));
+        compare(
+            with_shape("let apply = fn(f, x) { f(x) }; apply(Circle, 1) == Circle(1)").as_bytes(),
+            Object::Boolean(true),
+        );
+        // equality
+        compare(with_shape("Circle(1) == Circle(1)").as_bytes(), Object::Boolean(true));
+        compare(with_shape("Circle(1) == Circle(2)").as_bytes(), Object::Boolean(false));
+        compare(with_shape("Empty == Shape.Empty").as_bytes(), Object::Boolean(true));
+        compare(
+            with_shape("enum Other { Circle(r) }; Circle(1) == Shape.Circle(1)").as_bytes(),
+            Object::Boolean(false),
+        );
+        // a bound variable is not mistaken for a unit variant
+        compare(with_shape("match (Circle(1)) { Empty => 1, other => 2 }").as_bytes(), Object::Integer(2));
+        compare(
+            with_shape("enum Option { Some(x), None }; match (Some(Circle(4))) { Some(Circle(r)) => r, None => 0 }").as_bytes(),
+            Object::Integer(4),
+        );
+
+        let display = |input: &str| {
+            let (_, r) = Lexer::lexing(with_shape(input).as_bytes()).unwrap();
+            let (_, program) = Parser::parsing(Tokens::new(&r)).unwrap();
+            Evaluator::new().evaluation(program).to_string()
+        };
+        assert_eq!(display("Rect(1, Circle(2))"), "Shape.Rect(1, Shape.Circle(2))");
+        assert_eq!(display("Empty"), "Shape.Empty");
+        assert_eq!(display("Circle"), "[variant: Shape.Circle]");
+        assert_eq!(display("Shape"), "[enum: Shape]");
+
+        // errors
+        compare(
+            with_shape("Circle(1, 2)").as_bytes(),
+            Object::Error("wrong number of arguments: 1 expected but 2 given".to_string()),
+        );
+        compare(
+            with_shape("match (Circle(1)) { Circle(a, b) => 1 }").as_bytes(),
+            Object::Error("wrong number of arguments: 1 expected but 2 given".to_string()),
+        );
+        compare(with_shape("Shape.Square").as_bytes(), Object::Error("Shape has no variant Square".to_string()));
+        compare(with_shape("Circle(1).w").as_bytes(), Object::Error("Circle has no field w".to_string()));
+    }
+
+    #[test]
+    fn test_methods() {
+        compare("\"abc\".len()".as_bytes(), Object::Integer(3));
+        compare("[1, 2, 3].tail().head()".as_bytes(), Object::Integer(2));
+        compare("[1, 2].push(3)".as_bytes(), Object::Array(vec![
+            Object::Integer(1),
+            Object::Integer(2),
+            Object::Integer(3),
+        ]));
+        compare("{\"a\": 1}.keys()".as_bytes(), Object::Array(vec![Object::String("a".to_string())]));
+        compare("{\"a\": 1, \"b\": 2}.values().len()".as_bytes(), Object::Integer(2));
+        // methods are first-class values bound to their receiver
+        compare("let l = [1, 2].len; l()".as_bytes(), Object::Integer(2));
+        let (_, r) = Lexer::lexing(&b"[1].push"[..]).unwrap();
+        let (_, program) = Parser::parsing(Tokens::new(&r)).unwrap();
+        assert_eq!(Evaluator::new().evaluation(program).to_string(), "[built-in method: push]");
+        // fields of user defined types come first
+        compare("struct S { len }; S(7).len".as_bytes(), Object::Integer(7));
+
+        compare("1.len()".as_bytes(), Object::Error("integer has no method len".to_string()));
+        compare(
+            "\"abc\".len(1)".as_bytes(),
+            Object::Error("wrong number of arguments: 0 expected but 1 given".to_string()),
+        );
+
+        // the host can add methods to a type
+        let (_, r) = Lexer::lexing(&b"[5.double(), \"ab\".double()]"[..]).unwrap();
+        let (_, program) = Parser::parsing(Tokens::new(&r)).unwrap();
+        let mut evaluator = Evaluator::new();
+        evaluator.add_method("integer", "double", 1, |_, args| match args[0] {
+            Object::Integer(i) => Ok(Object::Integer(i * 2)),
+            _ => Err(RuntimeError::Builtin("not an integer".to_string()).into()),
+        });
+        evaluator.add_method("string", "double", 1, |_, args| Ok(Object::String(args[0].to_string().repeat(2))));
+        assert_eq!(
+            evaluator.evaluation(program),
+            Object::Array(vec![Object::Integer(10), Object::String("abab".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_higher_order_builtins() {
+        let ints = |v: Vec<i64>| Object::Array(v.into_iter().map(Object::Integer).collect());
+
+        compare("map([1, 2, 3], fn(x) { x * 2 })".as_bytes(), ints(vec![2, 4, 6]));
+        compare("[1, 2, 3, 4].filter(fn(x) { x > 2 })".as_bytes(), ints(vec![3, 4]));
+        compare("reduce([1, 2, 3], 10, fn(acc, x) { acc + x })".as_bytes(), Object::Integer(16));
+        compare("[3, 1, 2].sort_by(fn(a, b) { a - b })".as_bytes(), ints(vec![1, 2, 3]));
+        compare("[3, 1, 2].sort_by(fn(a, b) { b - a })".as_bytes(), ints(vec![3, 2, 1]));
+        // stable
+        compare(
+            "[[1, 1], [0, 2], [1, 3], [0, 4]].sort_by(fn(a, b) { a[0] - b[0] }).map(fn(p) { p[1] })".as_bytes(),
+            ints(vec![2, 4, 1, 3]),
+        );
+        compare("find([1, 2, 3], fn(x) { x > 1 })".as_bytes(), Object::Integer(2));
+        compare("find([1, 2, 3], fn(x) { x > 5 })".as_bytes(), Object::NULL);
+        compare("[1, 2].any(fn(x) { x == 2 })".as_bytes(), Object::Boolean(true));
+        compare("any([], fn(x) { true })".as_bytes(), Object::Boolean(false));
+        // builtins, constructors and closures are callable too
+        compare("map([[1], [1, 2]], len)".as_bytes(), ints(vec![1, 2]));
+        compare(
+            "struct W { v }; map([1], W) == [W(1)]".as_bytes(),
+            Object::Boolean(true),
+        );
+        compare(
+            "let n = 10; [1, 2].map(fn(x) { x + n })".as_bytes(),
+            ints(vec![11, 12]),
+        );
+
+        // errors of the callback keep the call stack and stay catchable
+        compare(
+            "[1, 2].filter(fn(x) { x })".as_bytes(),
+            Object::Error("1 is not a bool".to_string()),
+        );
+        compare(
+            "[filter, find, any].map(fn(f) { try { f([1], fn(x) { x }) } catch (e) { e[\"kind\"] } })".as_bytes(),
+            Object::Array(vec![Object::String("TypeMismatch".to_string()); 3]),
+        );
+        compare(
+            "try { map([1], fn(x) { throw x + 1 }) } catch (e) { e[\"message\"] }".as_bytes(),
+            Object::String("2".to_string()),
+        );
+        let err = eval_program("let f = fn(x) { x.nope };\nmap([1], f)".as_bytes()).unwrap_err();
+        assert_eq!(err.stack, vec![
+            Frame { function: "<anonymous>".to_string(), span: Span { line: 2, column: 4 } },
+            Frame { function: "map".to_string(), span: Span { line: 2, column: 4 } },
+        ]);
+        compare(
+            "map([1], fn(x, y) { x })".as_bytes(),
+            Object::Error("wrong number of arguments: 2 expected but 1 given".to_string()),
+        );
+
+        // callbacks count towards the execution budgets
+        let (eval, limit) = eval_with_limits(
+            "let f = fn(x) { map([x], f) }; f(1)".as_bytes(),
+            EvalLimits { max_steps: Some(1000), ..Default::default() },
+        );
+        assert_eq!(eval, Object::Error("step limit exceeded".to_string()));
+        assert_eq!(limit, Some(LimitExceeded::Steps));
+    }
+
+    #[test]
+    fn test_string_builtins() {
+        let string = |s: &str| Object::String(s.to_string());
+        let strings = |v: Vec<&str>| Object::Array(v.into_iter().map(string).collect());
+
+        compare("split(\"a,b,,c\", \",\")".as_bytes(), strings(vec!["a", "b", "", "c"]));
+        compare("\"abc\".split(\"\")".as_bytes(), strings(vec!["a", "b", "c"]));
+        compare("[1, \"a\", true].join(\"-\")".as_bytes(), string("1-a-true"));
+        compare("join([], \",\")".as_bytes(), string(""));
+        compare("\"  a b \n\".trim()".as_bytes(), string("a b"));
+        compare("upper(\"aBc\") + lower(\"aBc\")".as_bytes(), string("ABCabc"));
+        compare("\"a-b-c\".replace(\"-\", \"+\")".as_bytes(), string("a+b+c"));
+        compare(
+            "[contains(\"abc\", \"bc\"), \"abc\".starts_with(\"ab\"), \"abc\".ends_with(\"ab\")]".as_bytes(),
+            Object::Array(vec![Object::Boolean(true), Object::Boolean(true), Object::Boolean(false)]),
+        );
+        compare("\"ab\".repeat(3)".as_bytes(), string("ababab"));
+        compare("\"ab\".bytes()".as_bytes(), Object::Array(vec![Object::Integer(97), Object::Integer(98)]));
+        compare("parse_int(\" -42 \") + 1".as_bytes(), Object::Integer(-41));
+        compare("parse_int(\"4x\")".as_bytes(), Object::Error("\"4x\" is not an integer".to_string()));
+
+        // lengths and indices count chars, not bytes
+        compare("len(\"héllo\")".as_bytes(), Object::Integer(5));
+        compare("\"héllo\".chars()".as_bytes(), strings(vec!["h", "é", "l", "l", "o"]));
+        compare("\"héllo\".index_of(\"l\")".as_bytes(), Object::Integer(2));
+        compare("index_of(\"abc\", \"x\")".as_bytes(), Object::Integer(-1));
+
+        // formatting
+        compare("format(\"{} + {} = {}\", [1, 2, 1 + 2])".as_bytes(), string("1 + 2 = 3"));
+        compare("\"{{{}}}\".format([[1]])".as_bytes(), string("{[1]}"));
+        compare("format(\"{}\", [])".as_bytes(), Object::Error("too few arguments for format".to_string()));
+        compare("format(\"\", [1])".as_bytes(), Object::Error("too many arguments for format".to_string()));
+        compare("format(\"{\", [])".as_bytes(), Object::Error("unmatched { in format string".to_string()));
+        compare("sprintf(\"%s is %d%%\", [\"x\", 50])".as_bytes(), string("x is 50%"));
+        compare("sprintf(\"%d\", [\"x\"])".as_bytes(), Object::Error("x is not an integer".to_string()));
+
+        // comparison
+        compare(
+            "[\"a\" < \"b\", \"b\" <= \"a\", \"ab\" > \"a\", \"a\" >= \"a\"]".as_bytes(),
+            Object::Array(vec![Object::Boolean(true), Object::Boolean(false), Object::Boolean(true), Object::Boolean(true)]),
+        );
+        compare("[\"b\", \"c\", \"a\"].sort_by(fn(x, y) { if (x < y) { -1 } else { 1 } })".as_bytes(), strings(vec!["a", "b", "c"]));
+        compare("\"a\" < 1".as_bytes(), Object::Error("a is not an integer".to_string()));
+        compare("upper(1)".as_bytes(), Object::Error("invalid arguments for upper".to_string()));
+
+        // large strings are refused before they are built
+        let (eval, limit) = eval_with_limits(
+            "\"ab\".repeat(1000000000000)".as_bytes(),
+            EvalLimits { max_bytes: Some(1 << 20), ..Default::default() },
+        );
+        assert_eq!(eval, Object::Error("memory limit exceeded".to_string()));
+        assert_eq!(limit, Some(LimitExceeded::Bytes));
+    }
+
+    #[test]
+    fn test_hash_builtins() {
+        let string = |s: &str| Object::String(s.to_string());
+        let strings = |v: Vec<&str>| Object::Array(v.into_iter().map(string).collect());
+        let h = "let h = {\"z\": 1, \"a\": 2, \"m\": 3};";
+
+        // insertion order, also in the display
+        compare(format!("{} keys(h)", h).as_bytes(), strings(vec!["z", "a", "m"]));
+        compare(
+            format!("{} h.values()", h).as_bytes(),
+            Object::Array(vec![Object::Integer(1), Object::Integer(2), Object::Integer(3)]),
+        );
+        compare(format!("{} format(\"{{}}\", [h])", h).as_bytes(), string("{z : 1, a : 2, m : 3}"));
+        compare(format!("{} format(\"{{}}\", [h.entries()])", h).as_bytes(), string("[[z, 1], [a, 2], [m, 3]]"));
+        compare(format!("{} [has(h, \"a\"), h.has(\"b\"), h.has(1)]", h).as_bytes(), Object::Array(vec![
+            Object::Boolean(true),
+            Object::Boolean(false),
+            Object::Boolean(false),
+        ]));
+
+        // updates return a new hash, existing keys keep their position
+        compare(
+            format!("{} format(\"{{}} {{}}\", [h.set(\"a\", 0).set(\"b\", 4), h])", h).as_bytes(),
+            string("{z : 1, a : 0, m : 3, b : 4} {z : 1, a : 2, m : 3}"),
+        );
+        compare(format!("{} format(\"{{}}\", [delete(h, \"z\").delete(\"x\")])", h).as_bytes(), string("{a : 2, m : 3}"));
+        compare(
+            format!("{} format(\"{{}}\", [h.merge({{\"m\": 0, \"n\": 4}})])", h).as_bytes(),
+            string("{z : 1, a : 2, m : 0, n : 4}"),
+        );
+        compare(format!("{} len(h.delete(\"a\")) + h.len()", h).as_bytes(), Object::Integer(5));
+        compare("{1: 1}.set(1, 2) == {1: 2}".as_bytes(), Object::Boolean(true));
+        // equality does not depend on the order
+        compare("{1: 1, 2: 2} == {2: 2, 1: 1}".as_bytes(), Object::Boolean(true));
+
+        compare("struct P { x }; {}.set(P(1), 1)".as_bytes(), Object::Error("P { x: 1 } is not hashable".to_string()));
+        compare("merge({}, [])".as_bytes(), Object::Error("invalid arguments for merge".to_string()));
+        compare("keys([])".as_bytes(), Object::Error("invalid arguments for keys".to_string()));
+    }
+
+    #[test]
+    fn test_hash_keys_and_equality() {
+        let bools = |v: Vec<bool>| Object::Array(v.into_iter().map(Object::Boolean).collect());
+
+        // composite values are compared and hashed by their contents
+        compare("{[1, 2]: \"pair\"}[[1, 2]]".as_bytes(), Object::String("pair".to_string()));
+        compare("let k = [1, [2]]; {k: 1}[[1, [2]]]".as_bytes(), Object::Integer(1));
+        compare("{[1, 2]: 1}[[2, 1]]".as_bytes(), Object::NULL);
+        compare("{{\"a\": 1, \"b\": 2}: 1}[{\"b\": 2, \"a\": 1}]".as_bytes(), Object::Integer(1));
+        compare("{1 + 1: \"two\"}[2]".as_bytes(), Object::String("two".to_string()));
+        compare(
+            "enum C { Red, Rgb(r, g, b) }; let h = {C.Red: \"red\", Rgb(0, 0, 0): \"black\"}; [h[Red], h[C.Rgb(0, 0, 0)], h[Rgb(0, 0, 1)]]".as_bytes(),
+            Object::Array(vec![Object::String("red".to_string()), Object::String("black".to_string()), Object::NULL]),
+        );
+
+        // functions are only equal to themselves, also when their code is the same
+        compare(
+            "let f = fn(x) { x }; let g = fn(x) { x }; let h = f; [f == f, f == h, f == g, {f: 1}[h] == 1, {f: 1}[g] == 1]".as_bytes(),
+            bools(vec![true, true, false, true, false]),
+        );
+        // recursive functions are compared without walking their env
+        compare(
+            "let f = fn(n) { if (n == 0) { 0 } else { f(n - 1) } }; [f == f, [f] == [f]]".as_bytes(),
+            bools(vec![true, true]),
+        );
+        compare("let m = fn() { fn() { 1 } }; m() == m()".as_bytes(), Object::Boolean(false));
+        compare(
+            "[len == len, len == print, {len: 1}[len] == 1, [1].map == [1].map, [1].map == [2].map]".as_bytes(),
+            bools(vec![true, false, true, true, false]),
+        );
+        compare("struct P { x }; struct Q { x }; [{P: 1}[P] == 1, P == Q, P(1) == P(1)]".as_bytes(), bools(vec![true, false, true]));
+
+        // mutable values can not be keys, also inside composite values
+        compare("struct P { x }; {P(1): 1}".as_bytes(), Object::Error("P { x: 1 } is not hashable".to_string()));
+        compare(
+            "struct P { x }; {}.has([1, P(2)])".as_bytes(),
+            Object::Error("[1, P { x: 2 }] is not hashable".to_string()),
+        );
+        compare("{[1, if (false) { 1 }]: 1}".as_bytes(), Object::Error("[1, null] is not hashable".to_string()));
+    }
+
+    #[test]
+    fn test_math() {
+        let float = Object::Float;
+
+        // floats and mixed arithmetic
+        compare("1.5 + 2".as_bytes(), float(3.5));
+        compare("7 / 2 + 7.0 / 2".as_bytes(), float(6.5));
+        compare("-2.5 * 2".as_bytes(), float(-5.0));
+        compare("[1.0 == 1, 0.5 < 1, 2 >= 2.5, math.NAN == math.NAN, math.NAN < 1]".as_bytes(), Object::Array(
+            vec![true, true, false, false, false].into_iter().map(Object::Boolean).collect(),
+        ));
+        compare("format(\"{} {}\", [1.0, 0.1 + 0.2])".as_bytes(), Object::String("1.0 0.30000000000000004".to_string()));
+        compare("match (2.5) { 2.5 => 1, _ => 0 }".as_bytes(), Object::Integer(1));
+        compare("{1.5: 1}".as_bytes(), Object::Error("1.5 is not hashable".to_string()));
+
+        // integer arithmetic is checked
+        compare("9223372036854775807 + 1".as_bytes(), Object::Error("integer overflow".to_string()));
+        compare("-math.MIN_INT".as_bytes(), Object::Error("integer overflow".to_string()));
+        compare("1 / 0".as_bytes(), Object::Error("division by zero".to_string()));
+        compare("1.0 / 0".as_bytes(), float(f64::INFINITY));
+        compare(
+            "try { math.MIN_INT / -1 } catch (e) { e[\"kind\"] }".as_bytes(),
+            Object::String("Arithmetic".to_string()),
+        );
+
+        // the math module
+        compare("[math.abs(-3), math.min(2, 1.5), math.max(2, 1), math.clamp(15, 0, 10)]".as_bytes(), Object::Array(vec![
+            Object::Integer(3),
+            float(1.5),
+            Object::Integer(2),
+            Object::Integer(10),
+        ]));
+        compare("math.clamp(0.5, 1, 2)".as_bytes(), float(1.0));
+        // integers above 2^53 are compared exactly
+        compare("[math.min(9007199254740993, 9007199254740992), math.max(9007199254740992, 9007199254740993)]".as_bytes(), Object::Array(vec![
+            Object::Integer(9007199254740992),
+            Object::Integer(9007199254740993),
+        ]));
+        compare("math.clamp(1, 2, 1)".as_bytes(), Object::Error("clamp bounds are out of order".to_string()));
+        compare("[math.pow(2, 10), math.pow(2, -1), math.pow(4, 0.5)]".as_bytes(), Object::Array(vec![
+            Object::Integer(1024),
+            float(0.5),
+            float(2.0),
+        ]));
+        compare("math.pow(2, 63)".as_bytes(), Object::Error("integer overflow".to_string()));
+        compare("math.abs(math.MIN_INT)".as_bytes(), Object::Error("integer overflow".to_string()));
+        compare("[math.sum([1, 2, 3]), math.sum([1, 0.5]), math.sum([])]".as_bytes(), Object::Array(vec![
+            Object::Integer(6),
+            float(1.5),
+            Object::Integer(0),
+        ]));
+        compare("math.sum([math.MAX_INT, 1])".as_bytes(), Object::Error("integer overflow".to_string()));
+        compare("[math.gcd(12, -18), math.gcd(0, 0)]".as_bytes(), Object::Array(vec![Object::Integer(6), Object::Integer(0)]));
+        compare("math.gcd(math.MIN_INT, 0)".as_bytes(), Object::Error("integer overflow".to_string()));
+        compare(
+            "[math.floor(-1.5), math.ceil(1.2), math.round(2.5), math.trunc(-1.7), math.round(3)]".as_bytes(),
+            Object::Array(vec![-2, 2, 3, -1, 3].into_iter().map(Object::Integer).collect()),
+        );
+        compare("math.floor(math.INF)".as_bytes(), Object::Error("inf is out of range for an integer".to_string()));
+        compare("[math.sqrt(16), math.float(2)]".as_bytes(), Object::Array(vec![float(4.0), float(2.0)]));
+        compare("math.round(math.sin(math.PI / 2) * 1000)".as_bytes(), Object::Integer(1000));
+        compare("math.atan2(1, 1) * 4 == math.PI".as_bytes(), Object::Boolean(true));
+        compare("math.ln(math.E)".as_bytes(), float(1.0));
+        compare("math.sqrt(\"a\")".as_bytes(), Object::Error("a is not a number".to_string()));
+        compare("math.nope".as_bytes(), Object::Error("math has no member nope".to_string()));
+        compare("[1, 2.5].map(math.abs)".as_bytes(), Object::Array(vec![Object::Integer(1), float(2.5)]));
+    }
+
+    #[test]
+    fn test_random() {
+        let run = |evaluator: &mut Evaluator, input: &str| {
+            let (_, r) = Lexer::lexing(input.as_bytes()).unwrap();
+            let (_, program) = Parser::parsing(Tokens::new(&r)).unwrap();
+            evaluator.evaluation(program)
+        };
+        let draws = "[random(), random_int(1, 6), shuffle([1, 2, 3, 4, 5]), choice([\"a\", \"b\", \"c\"])]";
+
+        // seeded runs are reproducible, from the script or from the host
+        let seeded = |seed: i64| run(&mut Evaluator::new(), &format!("seed({}); {}", seed, draws));
+        assert_eq!(seeded(42), seeded(42));
+        assert_ne!(seeded(42), seeded(43));
+        let mut evaluator = Evaluator::new();
+        evaluator.seed(42);
+        assert_eq!(run(&mut evaluator, draws), seeded(42));
+
+        // evaluators have their own generators
+        let mut a = Evaluator::new();
+        let mut b = Evaluator::new();
+        run(&mut a, "seed(1)");
+        run(&mut b, "seed(1)");
+        let first = run(&mut a, "random()");
+        run(&mut a, "random()");
+        assert_eq!(run(&mut b, "random()"), first);
+        assert_ne!(run(&mut Evaluator::new(), "random()"), run(&mut Evaluator::new(), "random()"));
+
+        // ranges
+        let mut evaluator = Evaluator::new();
+        let mut seen = [false; 6];
+        for _ in 0..200 {
+            match run(&mut evaluator, "random_int(1, 6)") {
+                Object::Integer(i @ 1..=6) => seen[i as usize - 1] = true,
+                o => panic!("{} is out of range", o),
+            }
+            match run(&mut evaluator, "random()") {
+                Object::Float(x) => assert!((0.0..1.0).contains(&x)),
+                o => panic!("{} is not a float", o),
+            }
+        }
+        assert!(seen.iter().all(|&s| s));
+        compare("random_int(3, 3)".as_bytes(), Object::Integer(3));
+        compare("let x = random_int(math.MIN_INT, math.MAX_INT); x == x".as_bytes(), Object::Boolean(true));
+        compare(
+            "shuffle([3, 1, 2, 5, 4]).sort_by(fn(a, b) { a - b })".as_bytes(),
+            Object::Array((1..=5).map(Object::Integer).collect()),
+        );
+        compare("shuffle([])".as_bytes(), Object::Array(vec![]));
+        compare("choice([7])".as_bytes(), Object::Integer(7));
+
+        compare("random_int(2, 1)".as_bytes(), Object::Error("empty range for random_int: 2 > 1".to_string()));
+        compare("choice([])".as_bytes(), Object::Error("choice from an empty array".to_string()));
+        compare("seed(\"x\")".as_bytes(), Object::Error("invalid arguments for seed".to_string()));
+    }
+
+    #[test]
+    fn test_json() {
+        let string = |s: &str| Object::String(s.to_string());
+
+        compare(
+            "let v = json_parse(\"{\\\"b\\\": [1, 2.5, true, null], \\\"a\\\": {\\\"s\\\": \\\"x\\\"}}\"); [v[\"b\"], v[\"a\"][\"s\"], keys(v)]".as_bytes(),
+            Object::Array(vec![
+                Object::Array(vec![Object::Integer(1), Object::Float(2.5), Object::Boolean(true), Object::NULL]),
+                string("x"),
+                Object::Array(vec![string("b"), string("a")]),
+            ]),
+        );
+        compare("json_parse(\"18446744073709551615\")".as_bytes(), Object::Float(18446744073709551615.0));
+        compare(
+            "json_stringify({\"b\": [1, 2.5, if (false) { 1 }], \"a\": \"q\"}, 0)".as_bytes(),
+            string("{\"b\":[1,2.5,null],\"a\":\"q\"}"),
+        );
+        compare("json_stringify({\"a\": [1]}, 2)".as_bytes(), string("{\n  \"a\": [\n    1\n  ]\n}"));
+        compare("struct P { x, y }; json_stringify(P(1, \"2\"), 0)".as_bytes(), string("{\"x\":1,\"y\":\"2\"}"));
+        compare("json_stringify(\"é\\\"\", 0)".as_bytes(), string("\"é\\\"\""));
+        // round trip
+        compare(
+            "let s = \"{\\\"k\\\":[{\\\"n\\\":-1}],\\\"e\\\":{}}\"; json_stringify(json_parse(s), 0) == s".as_bytes(),
+            Object::Boolean(true),
+        );
+
+        compare("json_stringify([fn() {}], 0)".as_bytes(), Object::Error("[function] can not be converted to JSON".to_string()));
+        compare("json_stringify({1: 2}, 0)".as_bytes(), Object::Error("hash key 1 is not a string".to_string()));
+        compare("json_stringify(1, 16)".as_bytes(), string("1"));
+        compare("json_stringify(1, 9223372036854775807)".as_bytes(), Object::Error("indent 9223372036854775807 is larger than 16".to_string()));
+        compare("json_stringify(math.NAN, 0)".as_bytes(), Object::Error("NaN can not be converted to JSON".to_string()));
+        compare(
+            "struct N { next }; let n = N(0); n.next = n; json_stringify(n, 0)".as_bytes(),
+            Object::Error("instance is nested too deeply".to_string()),
+        );
+        compare(
+            "try { json_parse(\"[1,\") } catch (e) { e[\"message\"] }".as_bytes(),
+            string("invalid JSON: EOF while parsing a value at line 1 column 3"),
+        );
+
+        // conversion for embedders
+        let value = serde_json::json!({"a": [1, null], "b": {"c": 1.5}});
+        let object = value.to_object();
+        assert_eq!(object.to_string(), "{a : [1, null], b : {c : 1.5}}");
+        assert_eq!(serde_json::Value::from_object(object), Ok(value));
+        assert_eq!(
+            serde_json::Value::from_object(Object::Builtin("len".to_string(), 1, |_, _| Ok(Object::NULL))),
+            Err(RuntimeError::TypeMismatch("[built-in function: len] can not be converted to JSON".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_io() {
+        let run = |evaluator: &mut Evaluator, input: &str| {
+            let (_, r) = Lexer::lexing(input.as_bytes()).unwrap();
+            let (_, program) = Parser::parsing(Tokens::new(&r)).unwrap();
+            evaluator.evaluation(program)
+        };
+        let error = |message: String| Object::Error(message);
+
+        // nothing is allowed by default
+        compare("read_file(\"Cargo.toml\")".as_bytes(), error("reading files is not allowed".to_string()));
+        compare("write_file(\"x.txt\", \"\")".as_bytes(), error("writing files is not allowed".to_string()));
+        compare("exists(\"Cargo.toml\")".as_bytes(), error("reading files is not allowed".to_string()));
+        compare("read_stdin()".as_bytes(), error("reading stdin is not allowed".to_string()));
+
+        let dir = std::env::temp_dir().join(format!("lang-test-io-{}", std::process::id()));
+        let root = dir.join("root");
+        let outside = dir.join("outside");
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(outside.join("secret.txt"), "secret").unwrap();
+        let root_path = root.to_str().unwrap().to_string();
+
+        let mut evaluator = Evaluator::new();
+        evaluator.set_capabilities(Capabilities {
+            read_roots: vec![root.clone()],
+            write_roots: vec![root.clone()],
+            stdin: false,
+            env: false,
+        });
+        let mut eval = |input: &str| run(&mut evaluator, &input.replace("ROOT", &root_path));
+
+        assert_eq!(eval("write_file(\"ROOT/a.txt\", \"x\ny\")"), Object::NULL);
+        assert_eq!(eval("read_file(\"ROOT/a.txt\")"), Object::String("x\ny".to_string()));
+        assert_eq!(eval("read_lines(\"ROOT/sub/../a.txt\")"), Object::Array(vec![
+            Object::String("x".to_string()),
+            Object::String("y".to_string()),
+        ]));
+        assert_eq!(eval("[exists(\"ROOT/a.txt\"), exists(\"ROOT/b.txt\")]"), Object::Array(vec![
+            Object::Boolean(true),
+            Object::Boolean(false),
+        ]));
+        assert_eq!(eval("list_dir(\"ROOT\")"), Object::Array(vec![
+            Object::String("a.txt".to_string()),
+            Object::String("sub".to_string()),
+        ]));
+
+        // paths out of the roots
+        assert_eq!(
+            eval("read_file(\"ROOT/../outside/secret.txt\")"),
+            error(format!("reading {}/../outside/secret.txt is not allowed", root_path))
+        );
+        assert_eq!(
+            eval("exists(\"ROOT/../outside/missing.txt\")"),
+            error(format!("reading {}/../outside/missing.txt is not allowed", root_path))
+        );
+        assert_eq!(
+            eval("write_file(\"ROOT/../b.txt\", \"\")"),
+            error(format!("writing {}/../b.txt is not allowed", root_path))
+        );
+        // missing paths out of the roots give the same error as existing ones
+        assert_eq!(
+            eval("read_file(\"ROOT/../outside/missing.txt\")"),
+            error(format!("reading {}/../outside/missing.txt is not allowed", root_path))
+        );
+        assert_eq!(
+            eval("exists(\"ROOT/../missing/missing.txt\")"),
+            error(format!("reading {}/../missing/missing.txt is not allowed", root_path))
+        );
+        assert_eq!(
+            eval("write_file(\"ROOT/../missing/b.txt\", \"\")"),
+            error(format!("writing {}/../missing/b.txt is not allowed", root_path))
+        );
+        assert_eq!(eval("exists(\"ROOT/missing/../a.txt\")"), Object::Boolean(false));
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+            assert_eq!(
+                eval("read_file(\"ROOT/link/secret.txt\")"),
+                error(format!("reading {}/link/secret.txt is not allowed", root_path))
+            );
+            assert_eq!(
+                eval("write_file(\"ROOT/link/secret.txt\", \"\")"),
+                error(format!("writing {}/link/secret.txt is not allowed", root_path))
+            );
+        }
+        assert!(matches!(eval("read_file(\"ROOT/missing.txt\")"), Object::Error(_)));
+        assert_eq!(
+            eval("try { read_file(\"ROOT/missing.txt\") } catch (e) { e[\"kind\"] }"),
+            Object::String("Builtin".to_string())
+        );
+
+        // read only access
+        let mut evaluator = Evaluator::with_limits(EvalLimits { max_bytes: Some(1 << 20), ..Default::default() });
+        evaluator.set_capabilities(Capabilities { read_roots: vec![root.clone()], ..Default::default() });
+        assert_eq!(
+            run(&mut evaluator, &format!("write_file(\"{}/a.txt\", \"\")", root_path)),
+            error("writing files is not allowed".to_string())
+        );
+        // files are accounted before they are read
+        std::fs::write(root.join("big.txt"), vec![b'x'; 2 << 20]).unwrap();
+        assert_eq!(
+            run(&mut evaluator, &format!("read_file(\"{}/big.txt\")", root_path)),
+            error("memory limit exceeded".to_string())
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_process() {
+        let run = |evaluator: &mut Evaluator, input: &str| {
+            let (_, r) = Lexer::lexing(input.as_bytes()).unwrap();
+            let (_, program) = Parser::parsing(Tokens::new(&r)).unwrap();
+            evaluator.eval_program(program)
+        };
+
+        let mut evaluator = Evaluator::new();
+        assert_eq!(run(&mut evaluator, "args()"), Ok(Object::Array(vec![])));
+        evaluator.set_args(vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(run(&mut evaluator, "args()[1]"), Ok(Object::String("b".to_string())));
+        assert_eq!(
+            run(&mut evaluator, "env(\"PATH\")").map_err(|err| err.error),
+            Err(RuntimeError::Builtin("reading environment variables is not allowed".to_string()))
+        );
+        evaluator.set_capabilities(Capabilities { env: true, ..Default::default() });
+        std::env::set_var("LANG_TEST_PROCESS", "on");
+        assert_eq!(run(&mut evaluator, "env(\"LANG_TEST_PROCESS\")"), Ok(Object::String("on".to_string())));
+        assert_eq!(run(&mut evaluator, "env(\"LANG_TEST_PROCESS_UNSET\")"), Ok(Object::NULL));
+
+        // exits are not caught, but finally blocks run
+        let result = run(&mut evaluator, "let f = fn() { exit(3) }; try { f() } catch (e) { let caught = true; } finally { let done = true; };");
+        assert_eq!(result.clone().map_err(|err| err.error), Err(RuntimeError::Exit(3)));
+        assert_eq!(exit_status(&result), 3);
+        assert_eq!(run(&mut evaluator, "done"), Ok(Object::Boolean(true)));
+        assert!(evaluator.get_global("caught").is_none());
+        assert_eq!(
+            run(&mut evaluator, "exit(1099511627776)").map_err(|err| err.error),
+            Err(RuntimeError::Builtin("exit status 1099511627776 is out of range".to_string()))
+        );
+
+        // the result of the program is the exit status
+        assert_eq!(exit_status(&run(&mut evaluator, "let y = 4; y")), 4);
+        assert_eq!(exit_status(&run(&mut evaluator, "\"done\"")), 0);
+        assert_eq!(exit_status(&run(&mut evaluator, "1 / 0")), 1);
+    }
+
+    #[test]
+    fn test_time() {
+        let run = |evaluator: &mut Evaluator, input: &str| {
+            let (_, r) = Lexer::lexing(input.as_bytes()).unwrap();
+            let (_, program) = Parser::parsing(Tokens::new(&r)).unwrap();
+            evaluator.evaluation(program)
+        };
+        let string = |s: &str| Object::String(s.to_string());
+
+        // 2024-02-29 12:34:56.789 UTC
+        let clock = ManualClock::new(1_709_210_096_789);
+        let mut evaluator = Evaluator::new();
+        evaluator.set_clock(clock.clone());
+        assert_eq!(run(&mut evaluator, "now()"), Object::Integer(1_709_210_096_789));
+        assert_eq!(run(&mut evaluator, "let t = clock(); sleep(1500); [clock() - t, now()]"), Object::Array(vec![
+            Object::Float(1500.0),
+            Object::Integer(1_709_210_098_289),
+        ]));
+        clock.advance(250);
+        assert_eq!(run(&mut evaluator, "clock()"), Object::Float(1750.0));
+        clock.set(0);
+        assert_eq!(run(&mut evaluator, "now()"), Object::Integer(0));
+        assert_eq!(run(&mut evaluator, "sleep(-1)"), Object::Error("invalid arguments for sleep".to_string()));
+
+        let format = "\"%Y-%m-%d %H:%M:%S.%L\"";
+        let tests = vec![
+            (format!("format_time(1709210096789, {})", format), string("2024-02-29 12:34:56.789")),
+            (format!("format_time(0, {})", format), string("1970-01-01 00:00:00.000")),
+            (format!("format_time(-1, {})", format), string("1969-12-31 23:59:59.999")),
+            (format!("format_time(951782400000, {})", format), string("2000-02-29 00:00:00.000")),
+            ("format_time(0, \"100%% at %H\")".to_string(), string("100% at 00")),
+            ("format_time(0, \"%Q\")".to_string(), Object::Error("unknown format %Q".to_string())),
+            (format!("parse_time(\"2024-02-29 12:34:56.789\", {})", format), Object::Integer(1_709_210_096_789)),
+            (format!("parse_time(\"1969-12-31 23:59:59.999\", {})", format), Object::Integer(-1)),
+            ("parse_time(\"2024-03-01\", \"%Y-%m-%d\")".to_string(), Object::Integer(1_709_251_200_000)),
+            ("parse_time(\"12:00\", \"%H:%M\")".to_string(), Object::Integer(43_200_000)),
+            (
+                format!("let t = 4102444799999; parse_time(format_time(t, {}), {}) == t", format, format),
+                Object::Boolean(true),
+            ),
+            (
+                "parse_time(\"2023-02-29\", \"%Y-%m-%d\")".to_string(),
+                Object::Error("\"2023-02-29\" is not a valid time".to_string()),
+            ),
+            (
+                "parse_time(\"2024-1-01\", \"%Y-%m-%d\")".to_string(),
+                Object::Error("\"2024-1-01\" does not match the format \"%Y-%m-%d\"".to_string()),
+            ),
+            (
+                "parse_time(\"2024-01-01 \", \"%Y-%m-%d\")".to_string(),
+                Object::Error("\"2024-01-01 \" does not match the format \"%Y-%m-%d\"".to_string()),
+            ),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(run(&mut evaluator, &input), expected, "{}", input);
+        }
+
+        // sleeps stop at the deadline
+        let mut evaluator = Evaluator::with_limits(EvalLimits {
+            deadline: Some(std::time::Instant::now() + std::time::Duration::from_millis(50)),
+            ..Default::default()
+        });
+        let start = std::time::Instant::now();
+        assert_eq!(run(&mut evaluator, "sleep(60000)"), Object::Error("deadline exceeded".to_string()));
+        assert!(start.elapsed() < std::time::Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_regex() {
+        let string = |s: &str| Object::String(s.to_string());
+        let strings = |items: &[&str]| Object::Array(items.iter().map(|s| string(s)).collect());
+        let tests = vec![
+            ("regex_match(\"abc\", \"x\")", Object::NULL),
+            ("regex_match(\"v1.25\", \"[0-9]+\")", strings(&["1"])),
+            ("regex_match(\"v1.25\", \"([0-9]+)\\\\.([0-9]+)\")", strings(&["1.25", "1", "25"])),
+            ("regex_match(\"ab\", \"a(x)?(b)\")", Object::Array(vec![string("ab"), Object::NULL, string("b")])),
+            (
+                "regex_match(\"2024-03\", \"(?P<year>\\\\d+)-(?P<month>\\\\d+)\")",
+                Object::Hash(vec![
+                    (string("year"), string("2024")),
+                    (string("month"), string("03")),
+                ].into_iter().collect()),
+            ),
+            ("regex_find_all(\"a1b22c333\", \"[0-9]+\")", strings(&["1", "22", "333"])),
+            ("regex_find_all(\"abc\", \"[0-9]+\")", strings(&[])),
+            (
+                "regex_find_all(\"x=1, y=2\", \"(\\\\w)=(\\\\d)\")",
+                Object::Array(vec![strings(&["x=1", "x", "1"]), strings(&["y=2", "y", "2"])]),
+            ),
+            (
+                "map(regex_find_all(\"x=1, y=2\", \"(?P<k>\\\\w)=\\\\d\"), fn(m) { m[\"k\"] })",
+                strings(&["x", "y"]),
+            ),
+            ("regex_replace(\"a1b22\", \"[0-9]+\", \"#\")", string("a#b#")),
+            ("regex_replace(\"john smith\", \"(\\\\w+) (\\\\w+)\", \"$2 $1\")", string("smith john")),
+            ("regex_replace(\"a-b\", \"(?P<x>\\\\w)\", \"${x}$$\")", string("a$-b$")),
+            ("regex_split(\"a, b,c\", \",\\\\s*\")", strings(&["a", "b", "c"])),
+            ("regex_split(\"\", \",\")", strings(&[""])),
+            ("regex_match(\"a\", 1)", Object::Error("invalid arguments for regex_match".to_string())),
+            ("regex_replace(\"a\", \"a\", 1)", Object::Error("invalid arguments for regex_replace".to_string())),
+        ];
+        for (input, expected) in tests {
+            compare(input.as_bytes(), expected);
+        }
+
+        let mut evaluator = Evaluator::new();
+        let mut run = |input: &str| {
+            let (_, r) = Lexer::lexing(input.as_bytes()).unwrap();
+            let (_, program) = Parser::parsing(Tokens::new(&r)).unwrap();
+            evaluator.evaluation(program)
+        };
+        assert!(matches!(run("regex_match(\"a\", \"(\")"), Object::Error(ref e) if e.starts_with("invalid regex: ")));
+        run("let f = fn(s) { regex_match(s, \"[a-z]+\") }; map([\"a\", \"b\", \"c\"], f)");
+        // patterns are compiled once per evaluator
+        assert_eq!(evaluator.regexes().len(), 1);
+    }
+
+    #[test]
+    fn test_eval_and_parse() {
+        let string = |s: &str| Object::String(s.to_string());
+        let node = |fields: Vec<(&str, Object)>| {
+            Object::Hash(fields.into_iter().map(|(k, v)| (string(k), v)).collect())
+        };
+        let tests = vec![
+            ("eval(\"1 + 2\")", Object::Integer(3)),
+            ("let x = 10; eval(\"x * 2\")", Object::Integer(20)),
+            ("eval(\"let y = 5;\"); y", Object::Integer(5)),
+            ("let f = fn(a) { eval(\"a + 1\") }; f(1)", Object::Integer(2)),
+            ("let f = fn() { eval(\"return 1;\"); 2 }; f()", Object::Integer(2)),
+            ("eval(\"\")", Object::NULL),
+            ("eval(\"1 +\")", Object::Error("syntax error at line 1, column 1".to_string())),
+            ("eval(\"let a = 1;\nlet b = ;\")", Object::Error("syntax error at line 2, column 1".to_string())),
+            ("eval(\"foo\")", Object::Error("identifier not found: foo".to_string())),
+            (
+                "try { eval(\"let = 1\") } catch (e) { e[\"kind\"] }",
+                string("Syntax"),
+            ),
+            (
+                "parse(\"let x = -1 + y;\")",
+                Object::Array(vec![node(vec![
+                    ("type", string("let")),
+                    ("name", string("x")),
+                    ("value", node(vec![
+                        ("type", string("infix")),
+                        ("operator", string("+")),
+                        ("left", node(vec![
+                            ("type", string("prefix")),
+                            ("operator", string("-")),
+                            ("right", node(vec![("type", string("literal")), ("value", Object::Integer(1))])),
+                        ])),
+                        ("right", node(vec![("type", string("ident")), ("name", string("y"))])),
+                    ])),
+                ])]),
+            ),
+            (
+                "let call = parse(\"f(1, \\\"a\\\")\")[0][\"expr\"]; [call[\"type\"], len(call[\"arguments\"]), call[\"line\"]]",
+                Object::Array(vec![string("call"), Object::Integer(2), Object::Integer(1)]),
+            ),
+            (
+                "let m = parse(\"match (x) { Some(v) => { v } _ => { 0 } }\")[0][\"expr\"]; map(m[\"arms\"], fn(arm) { arm[\"pattern\"][\"type\"] })",
+                Object::Array(vec![string("variant"), string("wildcard")]),
+            ),
+            ("parse(\"if (a) { 1 }\")[0][\"expr\"][\"alternative\"]", Object::NULL),
+            ("parse(\"\")", Object::Array(vec![])),
+            (
+                "try { parse(\"fn(\") } catch (e) { [e[\"kind\"], e[\"message\"]] }",
+                Object::Array(vec![string("Syntax"), string("syntax error at line 1, column 1")]),
+            ),
+        ];
+        for (input, expected) in tests {
+            compare(input.as_bytes(), expected);
+        }
+    }
+
+    #[test]
+    fn test_macros() {
+        let unless = "let unless = macro(cond, then, otherwise) { quote(if (!(unquote(cond))) { unquote(then) } else { unquote(otherwise) }) };";
+        let tests = vec![
+            ("quote(foo)(1)", Object::Error("QUOTE(foo) is not a valid function".to_string())),
+            ("let x = 2; quote(unquote(x) * unquote(quote(x + 1))) == quote(2 * (x + 1))", Object::Boolean(true)),
+            ("quote(unquote(len))", Object::Error("[built-in function: len] can not be unquoted".to_string())),
+            ("let m = macro() { quote(1) }; m", Object::Error("identifier not found: m".to_string())),
+            ("let two = macro() { quote(1 + 1) }; two() * 3", Object::Integer(6)),
+            ("let f = fn() { let m = macro() { quote(1) }; m() }; f()", Object::Error("[macro] is not a valid function".to_string())),
+            ("let m = macro(a) { a }; m(1, 2)", Object::Error("wrong number of arguments: 1 expected but 2 given".to_string())),
+            ("let m = macro(a) { 1 }; m(x)", Object::Error("macro m returned 1 instead of a quote".to_string())),
+        ];
+        for (input, expected) in tests {
+            compare(input.as_bytes(), expected);
+        }
+        // the arguments of a macro are not evaluated
+        compare(
+            (unless.to_string() + "unless(10 > 5, \"no\", puts(\"never\"))").as_bytes(),
+            Object::Error("identifier not found: puts".to_string()),
+        );
+
+        let mut evaluator = Evaluator::new();
+        let mut run = |input: &str| {
+            let (_, r) = Lexer::lexing(input.as_bytes()).unwrap();
+            let (_, program) = Parser::parsing(Tokens::new(&r)).unwrap();
+            evaluator.evaluation(program)
+        };
+        assert_eq!(run("quote(1 + -2)").to_string(), "QUOTE((1 + (-2)))");
+        assert_eq!(run("let f = fn(x) { quote(x + unquote(x)) }; f(3)").to_string(), "QUOTE((x + 3))");
+        // macros stay defined for the following programs
+        run(unless);
+        assert_eq!(run("unless(1 > 2, \"yes\", \"no\")"), Object::String("yes".to_string()));
+        assert_eq!(run("eval(\"unless(true, 1, 2)\")"), Object::Integer(2));
+    }
+
+    #[test]
+    fn test_modules() {
+        let dir = std::env::temp_dir().join(format!("lang-test-modules-{}", std::process::id()));
+        let lib = dir.join("lib");
+        std::fs::create_dir_all(&lib).unwrap();
+        let dir = std::fs::canonicalize(dir).unwrap();
+        let files = [
+            ("main.lang", ""),
+            (
+                "util.lang",
+                "let hidden = 2; export let double = fn(x) { x * hidden }; \
+                 export let quad = fn(x) { double(double(x)) }; \
+                 export struct P { x } export enum Shape { Dot, Line(len) }",
+            ),
+            ("a.lang", "import \"b.lang\" as b; export let a = 1;"),
+            ("b.lang", "import \"a.lang\" as a; export let b = 2;"),
+            ("broken.lang", "let = 1;"),
+            ("numbers.lang", "export let r = range(0, 3); export let total = count(r, fn(x) { x > 0 });"),
+            ("lib/strings.lang", "import \"helpers.lang\" as helpers; export let shout = fn(s) { helpers.upper(s) + \"!\" };"),
+            ("lib/helpers.lang", "export let upper = upper;"),
+        ];
+        for (name, source) in files {
+            std::fs::write(dir.join(name), source).unwrap();
+        }
+        let outside = dir.with_file_name(format!("lang-test-modules-outside-{}", std::process::id()));
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(outside.join("secret.lang"), "export let secret = 42;").unwrap();
+        let outside_path = format!("../lang-test-modules-outside-{}/secret.lang", std::process::id());
+        let outside_import = format!("import {:?} as outside;", outside_path);
+
+        let mut evaluator = Evaluator::new();
+        let mut run = |input: &str| {
+            let (_, r) = Lexer::lexing(input.as_bytes()).unwrap();
+            let (_, program) = Parser::parsing(Tokens::new(&r)).unwrap();
+            evaluator.evaluation(program)
+        };
+        // without a main file and a module path nothing can be imported
+        assert_eq!(run("import \"util.lang\" as util;"), Object::Error("module \"util.lang\" not found".to_string()));
+
+        evaluator.set_main_file(&dir.join("main.lang"));
+        evaluator.set_module_path(vec![dir.join("lib")]);
+        let mut run = |input: &str| {
+            let (_, r) = Lexer::lexing(input.as_bytes()).unwrap();
+            let (_, program) = Parser::parsing(Tokens::new(&r)).unwrap();
+            evaluator.evaluation(program)
+        };
+        let tests = vec![
+            ("import \"util.lang\" as util; util.quad(3)", Object::Integer(12)),
+            ("util.P(1).x", Object::Integer(1)),
+            ("util.Shape.Line(3).len", Object::Integer(3)),
+            ("util.hidden", Object::Error("util has no member hidden".to_string())),
+            // modules see the prelude functions, not the globals of the importer
+            (
+                "let range = fn(a, b) { [] }; import \"numbers.lang\" as numbers; [numbers.r, numbers.total]",
+                Object::Array(vec![
+                    Object::Array(vec![Object::Integer(0), Object::Integer(1), Object::Integer(2)]),
+                    Object::Integer(2),
+                ]),
+            ),
+            // each file is evaluated once
+            ("import \"util.lang\" as again; again == util", Object::Boolean(true)),
+            ("import \"strings.lang\" as strings; strings.shout(\"hi\")", Object::String("HI!".to_string())),
+            ("import \"missing.lang\" as m;", Object::Error("module \"missing.lang\" not found".to_string())),
+            ("import \"/util.lang\" as m;", Object::Error("module path \"/util.lang\" is not relative".to_string())),
+            (
+                "import \"broken.lang\" as m;",
+                Object::Error(format!("syntax error at line 1, column 1 in {}", dir.join("broken.lang").display())),
+            ),
+            (
+                "import \"a.lang\" as a;",
+                Object::Error(format!(
+                    "cyclic import: {} -> {} -> {}",
+                    dir.join("a.lang").display(),
+                    dir.join("b.lang").display(),
+                    dir.join("a.lang").display(),
+                )),
+            ),
+            ("try { import \"missing.lang\" as m; } catch (e) { e[\"kind\"] }", Object::String("Import".to_string())),
+            ("let f = fn() { export let x = 1; }; f()", Object::Error("export is only allowed at the top level".to_string())),
+            // exports of the main program are ordinary declarations
+            ("export let y = 5; y", Object::Integer(5)),
+            // files out of the directory of the main file and the module path
+            (&outside_import, Object::Error(format!("importing {:?} is not allowed", outside_path))),
+            ("import \"../missing.lang\" as m;", Object::Error("importing \"../missing.lang\" is not allowed".to_string())),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(run(input), expected, "{}", input);
+        }
+        // the read roots can be imported from as well
+        evaluator.set_capabilities(Capabilities { read_roots: vec![outside.clone()], ..Default::default() });
+        let (_, r) = Lexer::lexing(format!("{} outside.secret", outside_import).as_bytes()).unwrap();
+        let (_, program) = Parser::parsing(Tokens::new(&r)).unwrap();
+        assert_eq!(evaluator.evaluation(program), Object::Integer(42));
+        std::fs::remove_dir_all(&dir).unwrap();
+        std::fs::remove_dir_all(&outside).unwrap();
+    }
+
+    #[test]
+    fn test_prelude() {
+        let ints = |items: &[i64]| Object::Array(items.iter().map(|i| Object::Integer(*i)).collect());
+        let string = |s: &str| Object::String(s.to_string());
+        let tests = vec![
+            ("range(2, 6)", ints(&[2, 3, 4, 5])),
+            ("range(3, 3)", ints(&[])),
+            ("len(range(0, 20000))", Object::Integer(20000)),
+            ("len(reverse(range(0, 20000)))", Object::Integer(20000)),
+            ("len(zip(range(0, 20000), enumerate(range(0, 20000))))", Object::Integer(20000)),
+            ("let xs = range(0, 20000); xs[19999]", Object::Integer(19999)),
+            ("range(3, 1)", ints(&[])),
+            ("reverse([1, 2, 3])", ints(&[3, 2, 1])),
+            ("reverse([])", ints(&[])),
+            (
+                "zip([1, 2, 3], [\"a\", \"b\"])",
+                Object::Array(vec![
+                    Object::Array(vec![Object::Integer(1), string("a")]),
+                    Object::Array(vec![Object::Integer(2), string("b")]),
+                ]),
+            ),
+            (
+                "enumerate([\"a\", \"b\"])",
+                Object::Array(vec![
+                    Object::Array(vec![Object::Integer(0), string("a")]),
+                    Object::Array(vec![Object::Integer(1), string("b")]),
+                ]),
+            ),
+            ("let even = fn(x) { x / 2 * 2 == x }; [all([2, 4], even), all([2, 3], even), all([], even)]", Object::Array(vec![
+                Object::Boolean(true),
+                Object::Boolean(false),
+                Object::Boolean(true),
+            ])),
+            ("count([1, 2, 3, 4], fn(x) { x > 1 })", Object::Integer(3)),
+            ("flat_map([1, 2], fn(x) { [x, x * 10] })", ints(&[1, 10, 2, 20])),
+            // prelude functions can be redefined without changing the others
+            ("let range = fn(a, b) { \"mine\" }; range(0, 1)", string("mine")),
+            ("let len = fn(x) { 0 }; let head = 1; reverse([1, 2])", ints(&[2, 1])),
+        ];
+        for (input, expected) in tests {
+            compare(input.as_bytes(), expected);
+        }
+
+        let (_, r) = Lexer::lexing("range(0, 2)".as_bytes()).unwrap();
+        let (_, program) = Parser::parsing(Tokens::new(&r)).unwrap();
+        assert_eq!(
+            Evaluator::without_prelude().evaluation(program),
+            Object::Error("identifier not found: range".to_string())
+        );
+    }
+
+    #[test]
+    fn test_generators() {
+        let ints = |items: &[i64]| Object::Array(items.iter().map(|i| Object::Integer(*i)).collect());
+        let string = |s: &str| Object::String(s.to_string());
+        let pair = |a: Object, b: Object| Object::Array(vec![a, b]);
+        let counter = "struct Counter { n, end; fn next(self) { \
+                 if (self.n < self.end) { self.n = self.n + 1; {\"value\": self.n, \"done\": false} } else { {\"done\": true} } \
+             } };";
+        let naturals = "let from = fn*(n) { yield n; for (x in from(n + 1)) { yield x; } }; ";
+        // the bindings of a loop body are local to its iteration, so results are kept in a field
+        let acc = "struct Acc { v }; let a = Acc(0); ";
+        let tests = vec![
+            // infinite generators are consumed lazily
+            (format!("{}from(1).map(fn(x) {{ x * x }}).filter(fn(x) {{ x / 2 * 2 == x }}).take(3).collect()", naturals), ints(&[4, 16, 36])),
+            (format!("{}from(0).skip(5).take(2).collect()", naturals), ints(&[5, 6])),
+            ("let g = fn*(a, b) { yield a; yield b; yield a + b; }; g(1, 2).collect()".to_string(), ints(&[1, 2, 3])),
+            ("let g = fn*() { yield 1; return 5; yield 2; }; g().collect()".to_string(), ints(&[1])),
+            ("let g = fn*() { 1 }; g().collect()".to_string(), ints(&[])),
+            // yield inside if, match and for
+            ("let evens = fn*(xs) { for (x in xs) { if (x / 2 * 2 == x) { yield x; } else { yield 0; } } }; evens([1, 2, 4]).collect()".to_string(), ints(&[0, 2, 4])),
+            ("let g = fn*(x) { match (x) { 1 => { yield 10; yield 11; }, n => { yield n; } } }; [g(1).collect(), g(3).collect()]".to_string(), Object::Array(vec![ints(&[10, 11]), ints(&[3])])),
+            (format!("{}let g = fn*(n) {{ for (x in [1, 2]) {{ a.v = a.v + x; yield a.v * n; }} }}; g(10).collect()", acc), ints(&[10, 30])),
+            // for ... in over arrays, strings, hashes, iterators and the next() protocol
+            (format!("{}for (x in [1, 2, 3]) {{ a.v = a.v + x; }}; a.v", acc), Object::Integer(6)),
+            (format!("{}a.v = \"\"; for (c in \"abc\") {{ a.v = c + a.v; }} a.v", acc), string("cba")),
+            (format!("{}a.v = []; for (k in {{\"a\": 1, \"b\": 2}}) {{ a.v = a.v.push(k); }}; a.v", acc), Object::Array(vec![string("a"), string("b")])),
+            (format!("{}{}for (x in from(1).take(4)) {{ a.v = a.v + x; }}; a.v", naturals, acc), Object::Integer(10)),
+            (format!("{}{}for (x in Counter(0, 3)) {{ a.v = a.v + x; }}; a.v", counter, acc), Object::Integer(6)),
+            // the loop variable and the bindings of the body do not leave the loop
+            ("let x = 0; for (x in [1, 2]) { let y = x; }; x".to_string(), Object::Integer(0)),
+            ("for (x in [1, 2]) { let y = x; }; y".to_string(), Object::Error("identifier not found: y".to_string())),
+            ("let g = fn*() { let x = 0; for (x in [1, 2]) { yield x; } yield x; }; g().collect()".to_string(), ints(&[1, 2, 0])),
+            ("let g = fn*(x) { match (x + 1) { x => { yield x; } } yield x; }; g(1).collect()".to_string(), ints(&[2, 1])),
+            (format!("{}iter(Counter(0, 4)).map(fn(x) {{ x * 10 }}).skip(1).collect()", counter), ints(&[20, 30, 40])),
+            ("let f = fn(xs) { for (x in xs) { if (x > 1) { return x; } }; 0 }; [f([1, 5, 7]), f([1])]".to_string(), ints(&[5, 0])),
+            ("for (x in []) { 1 }".to_string(), Object::NULL),
+            // next() returns a hash with value and done
+            ("let it = iter([7]); [it.next(), it.next()]".to_string(), Object::Array(vec![
+                Object::Hash(vec![(string("value"), Object::Integer(7)), (string("done"), Object::Boolean(false))].into_iter().collect()),
+                Object::Hash(vec![(string("value"), Object::NULL), (string("done"), Object::Boolean(true))].into_iter().collect()),
+            ])),
+            ("let it = iter([1, 2, 3]); it.next(); it.collect()".to_string(), ints(&[2, 3])),
+            ("iter(\"ab\").enumerate().collect()".to_string(), Object::Array(vec![
+                pair(Object::Integer(0), string("a")),
+                pair(Object::Integer(1), string("b")),
+            ])),
+            ("iter([1, 2, 3]).zip(\"xy\").collect()".to_string(), Object::Array(vec![
+                pair(Object::Integer(1), string("x")),
+                pair(Object::Integer(2), string("y")),
+            ])),
+            // adapters pull from their source only when they are consumed
+            ("let it = iter([1, 2, 3]); let m = it.map(fn(x) { x * 2 }); [it.next()[\"value\"], m.collect()]".to_string(), Object::Array(vec![
+                Object::Integer(1),
+                ints(&[4, 6]),
+            ])),
+            // a callback which does not return a bool fails like the array builtins
+            ("try { iter([1]).filter(fn(x) { x }).collect() } catch (e) { e[\"kind\"] }".to_string(), string("TypeMismatch")),
+            // a yield inside try suspends the body, errors after a yield are caught and finally blocks run
+            ("let g = fn*() { try { yield 1; } catch (e) { 0 } }; g().collect()".to_string(), ints(&[1])),
+            (
+                "let g = fn*() { try { yield 1; throw \"x\"; yield 2; } catch (e) { yield e[\"message\"]; } finally { yield 3; } yield 4; }; \
+                 g().collect()".to_string(),
+                Object::Array(vec![Object::Integer(1), string("x"), Object::Integer(3), Object::Integer(4)]),
+            ),
+            ("let g = fn*() { try { try { yield 1; return 0; } finally { yield 2; } } finally { yield 3; } yield 4; }; g().collect()".to_string(), ints(&[1, 2, 3])),
+            ("let g = fn*() { for (x in [1, 2]) { try { yield x; } finally { yield 0; } } }; g().collect()".to_string(), ints(&[1, 0, 2, 0])),
+            ("let g = fn*() { try { yield 1; 1 / 0; } finally { yield 2; } }; let it = g(); [it.next()[\"value\"], it.next()[\"value\"]]".to_string(), ints(&[1, 2])),
+            ("let g = fn*() { try { yield 1; 1 / 0; } finally { yield 2; } }; g().collect()".to_string(), Object::Error("division by zero".to_string())),
+            // a yield can not be caught where it can not suspend the body
+            ("let g = fn*() { let x = try { yield 1; } catch (e) { 0 }; yield x; }; g().collect()".to_string(), Object::Error("yield must be a statement inside a generator".to_string())),
+            ("let g = fn*(x) { yield x + if (x) { yield 1; 1 } else { 2 }; }; g(true).collect()".to_string(), Object::Error("yield must be a statement inside a generator".to_string())),
+            ("let g = fn*() { try { let x = [match (1) { _ => { yield 1; } }]; } catch (e) { yield 0; } }; g().collect()".to_string(), Object::Error("yield must be a statement inside a generator".to_string())),
+            ("let g = fn*() { let f = fn() { yield 1; }; f(); }; g().collect()".to_string(), Object::Error("yield outside of a generator".to_string())),
+            ("try { let f = fn() { yield 1; }; f() } catch (e) { 0 }".to_string(), Object::Error("yield outside of a generator".to_string())),
+            // errors
+            ("let g = fn*() { yield 1; }; g(1)".to_string(), Object::Error("wrong number of arguments: 0 expected but 1 given".to_string())),
+            ("yield 1;".to_string(), Object::Error("yield outside of a generator".to_string())),
+            ("let f = fn() { yield 1; }; f()".to_string(), Object::Error("yield outside of a generator".to_string())),
+            ("for (x in 5) { x }".to_string(), Object::Error("5 is not iterable".to_string())),
+            ("iter([1]).take(-1)".to_string(), Object::Error("negative count -1 for take".to_string())),
+            ("let g = fn*() { for (x in it) { yield x; } }; let it = g(); it.collect()".to_string(), Object::Error("iterator is already running".to_string())),
+            ("struct S { ; fn next(self) { 1 } }; for (x in S()) { x }".to_string(), Object::Error("next() returned 1 instead of a hash with value and done".to_string())),
+        ];
+        for (input, expected) in tests {
+            compare(input.as_bytes(), expected);
+        }
+
+        // an error ends the generator and is reported with the frame of the generator call
+        let input = "let g = fn*(x) { yield 1; yield head(x); yield 3; };\n\
+             let it = g([]);\n\
+             let first = it.next();\n\
+             let e = try { it.next() } catch (e) { e[\"message\"] };\n\
+             [first[\"value\"], e, it.next()[\"done\"]]\n\
+            "
+            .as_bytes();
+        assert_eq!(
+            eval_program(input),
+            Ok(Object::Array(vec![Object::Integer(1), string("empty array"), Object::Boolean(true)]))
+        );
+        let err = eval_program("let g = fn*() { yield head([]); };\ng().collect()".as_bytes()).unwrap_err();
+        assert_eq!(err.error, RuntimeError::Builtin("empty array".to_string()));
+        assert_eq!(
+            err.stack.iter().map(|frame| frame.function.as_str()).collect::<Vec<_>>(),
+            vec!["head", "g", "collect"]
+        );
+    }
 }
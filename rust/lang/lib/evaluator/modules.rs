@@ -0,0 +1,169 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::ast::{Identifier, Program, Stmt};
+use crate::evaluator::capabilities::resolve_path;
+use crate::evaluator::error::{EvalError, RuntimeError};
+use crate::evaluator::{EvalResult, Evaluator};
+use crate::object::environment::Environment;
+use crate::object::objects::{Module, Object};
+use crate::parser::Parser;
+
+/// modules imported by `import "path" as name`, each file is evaluated once per evaluator
+#[derive(Debug, Default)]
+pub struct ModuleLoader {
+    // directories searched after the directory of the importing file
+    search_path: Vec<PathBuf>,
+    // evaluated modules by canonical path
+    cache: HashMap<PathBuf, Rc<Module>>,
+    // files being evaluated, the main file first and the innermost import last
+    loading: Vec<PathBuf>,
+    // names exported by the module being evaluated, `None` for the main program
+    exports: Option<Vec<String>>,
+}
+
+impl ModuleLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Evaluator {
+    /// directories where imported modules are searched after the directory of the importing file
+    pub fn set_module_path(&mut self, search_path: Vec<PathBuf>) {
+        self.modules.search_path = search_path;
+    }
+
+    /// the file of the main program, its imports are resolved relative to its directory,
+    /// without a main file only the module path is searched
+    pub fn set_main_file(&mut self, path: &Path) {
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        self.modules.loading = vec![path];
+    }
+
+    /// evaluation for `import "path" as name`, binds the module to the name
+    pub(crate) fn eval_import(&mut self, path: String, alias: Identifier) -> EvalResult {
+        let Identifier(alias) = alias;
+        let module = Object::Module(self.import_module(&path)?);
+        self.env.borrow_mut().set(&alias, module.clone());
+        Ok(module)
+    }
+
+    /// evaluation for `export` declarations, the declared name becomes a member of the module
+    pub(crate) fn eval_export(&mut self, stmt: Stmt) -> EvalResult {
+        if !Rc::ptr_eq(&self.env, &self.globals) {
+            return Err(RuntimeError::Import("export is only allowed at the top level".to_string()).into());
+        }
+        let name = match stmt {
+            Stmt::LetStmt(Identifier(ref name), _)
+            | Stmt::StructStmt { name: Identifier(ref name), .. }
+            | Stmt::EnumStmt { name: Identifier(ref name), .. } => Some(name.clone()),
+            _ => None,
+        };
+        let object = self.eval_stmt(stmt)?;
+        if let (Some(exports), Some(name)) = (self.modules.exports.as_mut(), name) {
+            exports.push(name);
+        }
+        Ok(object)
+    }
+
+    /// the module of an import, evaluating the file on its first import
+    fn import_module(&mut self, path: &str) -> Result<Rc<Module>, EvalError> {
+        let file = self.resolve_module(path)?;
+        if let Some(module) = self.modules.cache.get(&file) {
+            return Ok(module.clone());
+        }
+        if let Some(start) = self.modules.loading.iter().position(|loading| *loading == file) {
+            let cycle = self.modules.loading[start..]
+                .iter()
+                .chain(Some(&file))
+                .map(|file| file.display().to_string())
+                .collect::<Vec<_>>();
+            return Err(RuntimeError::Import(format!("cyclic import: {}", cycle.join(" -> "))).into());
+        }
+        let source = fs::read_to_string(&file)
+            .map_err(|err| RuntimeError::Import(format!("can not read module {:?}: {}", path, err)))?;
+        let program = Parser::parse_source(&source)
+            .map_err(|span| RuntimeError::Syntax(format!("syntax error at {} in {}", span, file.display())))?;
+
+        self.modules.loading.push(file.clone());
+        let result = self.eval_module(program);
+        self.modules.loading.pop();
+        let module = Rc::new(Module {
+            name: file.file_stem().map_or_else(|| path.to_string(), |stem| stem.to_string_lossy().into_owned()),
+            members: result?,
+        });
+        self.modules.cache.insert(file, module.clone());
+        Ok(module)
+    }
+
+    /// evaluate a program in a new env, the result are the exported members,
+    /// a module sees the builtins and the prelude but not the globals of the importer
+    pub(crate) fn eval_module(&mut self, program: Program) -> Result<HashMap<String, Object>, EvalError> {
+        let mut module_env = Environment::new();
+        for (name, value) in &self.prelude {
+            module_env.set(name, value.clone());
+        }
+        let env = Rc::new(RefCell::new(module_env));
+        let env_before = mem::replace(&mut self.env, env.clone());
+        let globals_before = mem::replace(&mut self.globals, env.clone());
+        let exports_before = self.modules.exports.replace(vec![]);
+        let result = self.define_macros_and_eval(program);
+        let exports = mem::replace(&mut self.modules.exports, exports_before).unwrap_or_default();
+        self.env = env_before;
+        self.globals = globals_before;
+        result?;
+        Ok(exports
+            .into_iter()
+            .filter_map(|name| env.borrow().get(&name).map(|value| (name, value)))
+            .collect())
+    }
+
+    fn define_macros_and_eval(&mut self, program: Program) -> EvalResult {
+        let program = self.define_macros(program);
+        let program = self.expand_macros(program)?;
+        self.eval_statements(program)
+    }
+
+    /// the canonical path of the first existing file for the import path,
+    /// relative to the importing file and then to the directories of the module path,
+    /// files out of the module directories and the read roots can not be imported
+    fn resolve_module(&self, path: &str) -> Result<PathBuf, EvalError> {
+        if Path::new(path).is_absolute() {
+            return Err(RuntimeError::Import(format!("module path {:?} is not relative", path)).into());
+        }
+        let roots = self.module_roots();
+        let importer = self.modules.loading.last().and_then(|file| file.parent());
+        let mut denied = false;
+        for dir in importer.into_iter().chain(self.modules.search_path.iter().map(PathBuf::as_path)) {
+            // the candidate is checked before it is looked up, so that imports can not probe files out of the roots
+            let candidate = resolve_path(&dir.join(path));
+            if !roots.iter().any(|root| candidate.starts_with(root)) {
+                denied = true;
+            } else if candidate.is_file() {
+                return Ok(candidate);
+            }
+        }
+        if denied {
+            Err(RuntimeError::Import(format!("importing {:?} is not allowed", path)).into())
+        } else {
+            Err(RuntimeError::Import(format!("module {:?} not found", path)).into())
+        }
+    }
+
+    /// directories whose files can be imported: the directory of the main file,
+    /// the directories of the module path and the read roots of the capabilities
+    fn module_roots(&self) -> Vec<PathBuf> {
+        let main_dir = self.modules.loading.first().and_then(|file| file.parent());
+        main_dir
+            .into_iter()
+            .chain(self.modules.search_path.iter().map(PathBuf::as_path))
+            .chain(self.capabilities.read_roots.iter().map(PathBuf::as_path))
+            .filter_map(|root| fs::canonicalize(root).ok())
+            .collect()
+    }
+}
@@ -0,0 +1,33 @@
+use std::sync::OnceLock;
+
+use crate::ast::Program;
+use crate::evaluator::error::EvalError;
+use crate::evaluator::Evaluator;
+use crate::parser::Parser;
+
+/// functions written in the language itself, loaded into the globals of every new evaluator:
+/// `range(start, end)`, `reverse(array)`, `zip(left, right)`, `enumerate(array)`,
+/// `all(array, f)`, `count(array, f)` and `flat_map(array, f)`
+/// arrays are copied when they are passed around, so the arrays are built by a single `map`
+/// instead of an accumulator, which would make them quadratic
+const PRELUDE: &str = include_str!("prelude.lang");
+
+/// the parsed prelude, shared by all evaluators
+fn prelude() -> &'static Program {
+    static PROGRAM: OnceLock<Program> = OnceLock::new();
+    PROGRAM.get_or_init(|| Parser::parse_source(PRELUDE).expect("the prelude parses"))
+}
+
+impl Evaluator {
+    /// define the prelude functions in the globals and in the modules imported later, they are
+    /// evaluated in an env of their own, so redefining a global, e.g. `let range = ...`,
+    /// does not change the other prelude functions
+    pub(crate) fn load_prelude(&mut self) -> Result<(), EvalError> {
+        let members = self.eval_module(prelude().clone())?;
+        for (name, value) in &members {
+            self.globals.borrow_mut().set(name, value.clone());
+        }
+        self.prelude = members;
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+/// pseudo-random number generator of an evaluator (SplitMix64),
+/// the same seed gives the same sequence on every platform
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::from_entropy()
+    }
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    /// generator with a random seed, generators created one after another differ
+    pub fn from_entropy() -> Self {
+        Rng::new(RandomState::new().hash_one(0u64))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// uniform float in `[0, 1)`
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// uniform integer in `[0, n)`, `n` must not be 0
+    pub fn below(&mut self, n: u64) -> u64 {
+        // reject the values of the incomplete last range so that every result is equally likely
+        let zone = u64::MAX - u64::MAX % n;
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return x % n;
+            }
+        }
+    }
+}
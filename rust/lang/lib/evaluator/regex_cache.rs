@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use regex::Regex;
+
+/// most patterns kept compiled, the cache is emptied once it is full
+const CAPACITY: usize = 64;
+
+/// compiled patterns of the regex builtins
+#[derive(Clone, Debug, Default)]
+pub struct RegexCache {
+    compiled: HashMap<String, Rc<Regex>>,
+}
+
+impl RegexCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// the compiled pattern, compiling it on the first use
+    pub fn get(&mut self, pattern: &str) -> Result<Rc<Regex>, regex::Error> {
+        if let Some(regex) = self.compiled.get(pattern) {
+            return Ok(regex.clone());
+        }
+        let regex = Rc::new(Regex::new(pattern)?);
+        if self.compiled.len() >= CAPACITY {
+            self.compiled.clear();
+        }
+        self.compiled.insert(pattern.to_string(), regex.clone());
+        Ok(regex)
+    }
+
+    pub fn len(&self) -> usize {
+        self.compiled.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.compiled.is_empty()
+    }
+}
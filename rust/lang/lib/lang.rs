@@ -1,5 +1,8 @@
 #![cfg_attr(debug_assertions, allow(dead_code, unused_imports, unused_doc_comments))]
 
+// the derive macros refer to `::lang_lib`, which also has to resolve inside this crate
+extern crate self as lang_lib;
+
 pub mod ast;
 pub mod lexer;
 pub mod parser;
@@ -1,11 +1,11 @@
-use crate::token::Token;
+use crate::token::{Float, Span, Token};
 use nom::{
     branch::alt,
     bytes::complete::{tag, take},
     character::complete::{alpha1, alphanumeric1, digit1, multispace0},
-    combinator::{map, map_res, recognize},
+    combinator::{map, map_res, not, peek, recognize},
     multi::many0,
-    sequence::{delimited, pair},
+    sequence::{delimited, pair, terminated, tuple},
     IResult,
 };
 use std::str;
@@ -39,6 +39,7 @@ mod token_lex {
     /// operators
     map_lex! {equal_operator, "==", Token::Equal}
     map_lex! {not_equal_operator, "!=", Token::NotEqual}
+    map_lex! {fat_arrow_operator, "=>", Token::FatArrow}
     map_lex! {greater_equal_operator, ">=", Token::GreaterThanEqual}
     map_lex! {lesser_equal_operator, "<=", Token::LessThanEqual}
     map_lex! {assign_operator, "=", Token::Assign}
@@ -55,6 +56,7 @@ mod token_lex {
         (
             equal_operator,
             not_equal_operator,
+            fat_arrow_operator,
             greater_equal_operator,
             lesser_equal_operator,
             assign_operator,
@@ -71,6 +73,7 @@ mod token_lex {
     /// punctuations
     map_lex! {comma_punctuation, ",", Token::Comma}
     map_lex! {colon_punctuation, ":", Token::Colon}
+    map_lex! {dot_punctuation, ".", Token::Dot}
     map_lex! {semicolon_punctuation, ";", Token::SemiColon}
     map_lex! {lparen_punctuation, "(", Token::LParen}
     map_lex! {rparen_punctuation, ")", Token::RParen}
@@ -84,6 +87,7 @@ mod token_lex {
         (   comma_punctuation,
             semicolon_punctuation,
             colon_punctuation,
+            dot_punctuation,
             lparen_punctuation,
             rparen_punctuation,
             lbrace_punctuation,
@@ -93,14 +97,33 @@ mod token_lex {
         )
     }
 
+    /// 匹配关键字，关键字后不能紧跟标识符字符，避免将 `letter` 解析为 `let` 和 `ter`
+    fn keyword<'a>(word: &'static str) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], &'a [u8]> {
+        terminated(tag(word), not(peek(alt((alphanumeric1, tag("_"))))))
+    }
+
     /// keywords
-    map_lex! {let_keywords,"let",Token::Let}
-    map_lex! {function_keywords,"fn",Token::Function}
-    map_lex! {if_keywords,"if",Token::If}
-    map_lex! {else_keywords,"else",Token::Else}
-    map_lex! {return_keywords,"return",Token::Return}
-    map_lex! {true_keywords,"true",Token::BoolLiteral(true)}
-    map_lex! {false_keywords,"false",Token::BoolLiteral(false)}
+    map_lex! {let_keywords,keyword("let"),|_| Token::Let}
+    map_lex! {function_keywords,keyword("fn"),|_| Token::Function}
+    map_lex! {if_keywords,keyword("if"),|_| Token::If}
+    map_lex! {else_keywords,keyword("else"),|_| Token::Else}
+    map_lex! {return_keywords,keyword("return"),|_| Token::Return}
+    map_lex! {true_keywords,keyword("true"),|_| Token::BoolLiteral(true)}
+    map_lex! {false_keywords,keyword("false"),|_| Token::BoolLiteral(false)}
+    map_lex! {try_keywords,keyword("try"),|_| Token::Try}
+    map_lex! {catch_keywords,keyword("catch"),|_| Token::Catch}
+    map_lex! {finally_keywords,keyword("finally"),|_| Token::Finally}
+    map_lex! {throw_keywords,keyword("throw"),|_| Token::Throw}
+    map_lex! {struct_keywords,keyword("struct"),|_| Token::Struct}
+    map_lex! {match_keywords,keyword("match"),|_| Token::Match}
+    map_lex! {enum_keywords,keyword("enum"),|_| Token::Enum}
+    map_lex! {macro_keywords,keyword("macro"),|_| Token::Macro}
+    map_lex! {import_keywords,keyword("import"),|_| Token::Import}
+    map_lex! {export_keywords,keyword("export"),|_| Token::Export}
+    map_lex! {as_keywords,keyword("as"),|_| Token::As}
+    map_lex! {for_keywords,keyword("for"),|_| Token::For}
+    map_lex! {in_keywords,keyword("in"),|_| Token::In}
+    map_lex! {yield_keywords,keyword("yield"),|_| Token::Yield}
 
     /// 创建一个用于解析关键字的多匹配解析子
     parsers! {lex_keywords,
@@ -112,6 +135,20 @@ mod token_lex {
             return_keywords,
             true_keywords,
             false_keywords,
+            try_keywords,
+            catch_keywords,
+            finally_keywords,
+            throw_keywords,
+            struct_keywords,
+            match_keywords,
+            enum_keywords,
+            macro_keywords,
+            import_keywords,
+            export_keywords,
+            as_keywords,
+            for_keywords,
+            in_keywords,
+            yield_keywords,
         )
     }
     /// 用于解析string的辅助解析子集合
@@ -155,12 +192,21 @@ mod token_lex {
             )),
             |ident| {
                 let byte_to_str = String::from_utf8(Vec::from(ident));
-                byte_to_str.map(|astr| Token::Ident(astr))
+                byte_to_str.map(Token::Ident)
             },
         )
         ,|token| token
     }
 
+    /// Floats parsing，形如 `1.5`，小数点两侧都需要数字，因此 `5.x` 仍是字段访问
+    map_lex! {lex_float,
+        map_res(
+            map_res(recognize(tuple((digit1, tag("."), digit1))), str::from_utf8),
+            str::FromStr::from_str,
+        ),
+        |f| Token::FloatLiteral(Float(f))
+    }
+
     /// Integers parsing
     map_lex! {lex_integer,map_res(map_res(digit1, str::from_utf8), str::FromStr::from_str),Token::IntLiteral}
 
@@ -174,6 +220,7 @@ mod token_lex {
         lex_string,
         lex_keywords,
         lex_ident,
+        lex_float,
         lex_integer,
         lex_illegal,
     )}
@@ -184,6 +231,40 @@ mod token_lex {
         // 每个解析器，主要用于匹配由多个空白包围的token
         many0(delimited(multispace0, lex_token, multispace0))(input)
     }
+
+    /// 匹配一个token，并且记录匹配前剩余的输入长度
+    fn lex_token_with_rest(input: &[u8]) -> IResult<&[u8], (usize, Token)> {
+        map(lex_token, |token| (input.len(), token))(input)
+    }
+
+    /// 匹配多个token，并且记录每个token的偏移量
+    pub fn lex_tokens_with_offsets(input: &[u8]) -> IResult<&[u8], Vec<(usize, Token)>> {
+        map(
+            many0(delimited(multispace0, lex_token_with_rest, multispace0)),
+            |tokens| tokens.into_iter().map(|(rest, token)| (input.len() - rest, token)).collect(),
+        )(input)
+    }
+}
+
+/// 将有序的字节偏移量转换为行列位置
+fn offsets_to_spans(input: &[u8], offsets: &[usize]) -> Vec<Span> {
+    let mut spans = Vec::with_capacity(offsets.len());
+    let mut span = Span { line: 1, column: 1 };
+    let mut pos = 0;
+    for &offset in offsets {
+        while pos < offset {
+            if input[pos] == b'\n' {
+                span.line += 1;
+                span.column = 1;
+            } else if input[pos] & 0xC0 != 0x80 {
+                // 只对utf8的首字节计数
+                span.column += 1;
+            }
+            pos += 1;
+        }
+        spans.push(span);
+    }
+    spans
 }
 
 /// Lexer 词法解析器
@@ -195,6 +276,16 @@ impl Lexer {
         token_lex::lex_tokens(bytes)
             .map(|(slice, result)| (slice, [&result[..], &vec![Token::EOF][..]].concat()))
     }
+
+    /// 带位置信息的词法分析，返回的 spans 与 tokens 一一对应，最后一个为 `Token::EOF` 的位置
+    pub fn lexing_with_spans(bytes: &[u8]) -> IResult<&[u8], (Vec<Token>, Vec<Span>)> {
+        token_lex::lex_tokens_with_offsets(bytes).map(|(slice, result)| {
+            let (mut offsets, mut tokens): (Vec<usize>, Vec<Token>) = result.into_iter().unzip();
+            offsets.push(bytes.len() - slice.len());
+            tokens.push(Token::EOF);
+            (slice, (tokens, offsets_to_spans(bytes, &offsets)))
+        })
+    }
 }
 
 #[cfg(test)]
@@ -438,4 +529,133 @@ mod tests {
         ];
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn token_spans() {
+        let input = "let a = 5;\nlet s = \"💖\"; a(s);".as_bytes();
+        let (_, (tokens, spans)) = Lexer::lexing_with_spans(input).unwrap();
+        let (_, expected) = Lexer::lexing(input).unwrap();
+        assert_eq!(tokens, expected);
+        let expected_spans: Vec<(usize, usize)> = vec![
+            (1, 1), (1, 5), (1, 7), (1, 9), (1, 10),
+            (2, 1), (2, 5), (2, 7), (2, 9), (2, 12),
+            (2, 14), (2, 15), (2, 16), (2, 17), (2, 18),
+            (2, 19),
+        ];
+        assert_eq!(
+            spans.iter().map(|s| (s.line, s.column)).collect::<Vec<_>>(),
+            expected_spans
+        );
+    }
+
+    #[test]
+    fn keywords() {
+        let (_, result) = Lexer::lexing(&b"try { throw e } catch (e) {} finally {} letter iffy trying"[..]).unwrap();
+        let expected = vec![
+            Token::Try,
+            Token::LBrace,
+            Token::Throw,
+            Token::Ident("e".to_owned()),
+            Token::RBrace,
+            Token::Catch,
+            Token::LParen,
+            Token::Ident("e".to_owned()),
+            Token::RParen,
+            Token::LBrace,
+            Token::RBrace,
+            Token::Finally,
+            Token::LBrace,
+            Token::RBrace,
+            Token::Ident("letter".to_owned()),
+            Token::Ident("iffy".to_owned()),
+            Token::Ident("trying".to_owned()),
+            Token::EOF,
+        ];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn struct_tokens() {
+        let (_, result) = Lexer::lexing(&b"enum struct P { x } match (p.x) { _ => 1 } macro macros"[..]).unwrap();
+        let expected = vec![
+            Token::Enum,
+            Token::Struct,
+            Token::Ident("P".to_owned()),
+            Token::LBrace,
+            Token::Ident("x".to_owned()),
+            Token::RBrace,
+            Token::Match,
+            Token::LParen,
+            Token::Ident("p".to_owned()),
+            Token::Dot,
+            Token::Ident("x".to_owned()),
+            Token::RParen,
+            Token::LBrace,
+            Token::Ident("_".to_owned()),
+            Token::FatArrow,
+            Token::IntLiteral(1),
+            Token::RBrace,
+            Token::Macro,
+            Token::Ident("macros".to_owned()),
+            Token::EOF,
+        ];
+        assert_eq!(result, expected);
+    }
+    #[test]
+    fn module_tokens() {
+        let (_, result) = Lexer::lexing(&b"import \"util.lang\" as util; export let asks"[..]).unwrap();
+        let expected = vec![
+            Token::Import,
+            Token::StringLiteral("util.lang".to_owned()),
+            Token::As,
+            Token::Ident("util".to_owned()),
+            Token::SemiColon,
+            Token::Export,
+            Token::Let,
+            Token::Ident("asks".to_owned()),
+            Token::EOF,
+        ];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn generator_tokens() {
+        let (_, result) = Lexer::lexing(&b"fn*() { yield x; } for (i in items) inner"[..]).unwrap();
+        let expected = vec![
+            Token::Function,
+            Token::Multiply,
+            Token::LParen,
+            Token::RParen,
+            Token::LBrace,
+            Token::Yield,
+            Token::Ident("x".to_owned()),
+            Token::SemiColon,
+            Token::RBrace,
+            Token::For,
+            Token::LParen,
+            Token::Ident("i".to_owned()),
+            Token::In,
+            Token::Ident("items".to_owned()),
+            Token::RParen,
+            Token::Ident("inner".to_owned()),
+            Token::EOF,
+        ];
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn float_tokens() {
+        let (_, result) = Lexer::lexing(&b"1.5 10.25 5.x 2."[..]).unwrap();
+        let expected = vec![
+            Token::FloatLiteral(Float(1.5)),
+            Token::FloatLiteral(Float(10.25)),
+            Token::IntLiteral(5),
+            Token::Dot,
+            Token::Ident("x".to_owned()),
+            Token::IntLiteral(2),
+            Token::Dot,
+            Token::EOF,
+        ];
+        assert_eq!(result, expected);
+    }
 }
@@ -1,6 +1,10 @@
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use crate::ast::Identifier;
-use crate::object::objects::{BuiltinFunction, Object};
+use crate::evaluator::error::{EvalError, RuntimeError};
+use crate::evaluator::{EvalResult, Evaluator};
+use crate::object::objects::{BuiltinFunction, NativeFunction, Object};
+use crate::object::{hashes, io, iterators, json, math, process, random, regexes, strings, syntax, time};
 
 
 pub struct BuiltinsFunctions;
@@ -23,17 +27,114 @@ impl BuiltinsFunctions {
             add_builtin("head", 1, bhead_fn),
             add_builtin("tail", 1, btail_fn),
             add_builtin("cons", 2, bcons_fn),
-        ]
+            add_builtin("map", 2, bmap_fn),
+            add_builtin("filter", 2, bfilter_fn),
+            add_builtin("reduce", 3, breduce_fn),
+            add_builtin("sort_by", 2, bsort_by_fn),
+            add_builtin("find", 2, bfind_fn),
+            add_builtin("any", 2, bany_fn),
+            (Identifier("math".to_string()), math::get_module()),
+        ].into_iter()
+            .chain(strings::get_builtins())
+            .chain(hashes::get_builtins())
+            .chain(random::get_builtins())
+            .chain(json::get_builtins())
+            .chain(io::get_builtins())
+            .chain(process::get_builtins())
+            .chain(time::get_builtins())
+            .chain(regexes::get_builtins())
+            .chain(syntax::get_builtins())
+            .chain(iterators::get_builtins())
+            .collect()
     }
+
+    /// builtin methods by the `Object::type_name` of their receiver,
+    /// the receiver is the first argument and counts towards the number of parameters
+    pub fn get_methods(&self) -> Vec<(&'static str, Identifier, Object)> {
+        vec![
+            add_method("string", "len", 1, blen_fn),
+            add_method("array", "len", 1, blen_fn),
+            add_method("array", "head", 1, bhead_fn),
+            add_method("array", "tail", 1, btail_fn),
+            add_method("array", "push", 2, bpush_fn),
+            add_method("array", "map", 2, bmap_fn),
+            add_method("array", "filter", 2, bfilter_fn),
+            add_method("array", "reduce", 3, breduce_fn),
+            add_method("array", "sort_by", 2, bsort_by_fn),
+            add_method("array", "find", 2, bfind_fn),
+            add_method("array", "any", 2, bany_fn),
+            add_method("hash", "len", 1, blen_fn),
+        ].into_iter()
+            .chain(strings::get_methods())
+            .chain(hashes::get_methods())
+            .chain(iterators::get_methods())
+            .collect()
+    }
+}
+
+/// methods of the object types, filled with the builtin methods and extensible by the host
+#[derive(Clone, Debug, PartialEq)]
+pub struct MethodTable {
+    // type name -> method name -> method
+    methods: HashMap<String, HashMap<String, Object>>,
 }
 
-fn add_builtin(name: &str, param_num: usize, func: BuiltinFunction) -> (Identifier, Object) {
+impl Default for MethodTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MethodTable {
+    pub fn new() -> Self {
+        let mut table = MethodTable {
+            methods: HashMap::new(),
+        };
+        for (type_name, Identifier(name), method) in BuiltinsFunctions::new().get_methods() {
+            table.insert(type_name, &name, method);
+        }
+        table
+    }
+
+    /// add or replace a method of a type, `num_params` includes the receiver
+    pub fn add(&mut self, type_name: &str, name: &str, num_params: usize, func: BuiltinFunction) {
+        let (_, Identifier(name), method) = add_method(type_name, name, num_params, func);
+        self.insert(type_name, &name, method);
+    }
+
+    /// add or replace a method of a type implemented by a host closure, `num_params` includes the receiver
+    pub fn add_closure(&mut self, type_name: &str, name: &str, num_params: usize, func: NativeFunction) {
+        self.insert(type_name, name, Object::Closure(name.to_string(), num_params, func));
+    }
+
+    pub fn get(&self, type_name: &str, name: &str) -> Option<Object> {
+        self.methods.get(type_name)?.get(name).cloned()
+    }
+
+    fn insert(&mut self, type_name: &str, name: &str, method: Object) {
+        self.methods
+            .entry(type_name.to_string())
+            .or_default()
+            .insert(name.to_string(), method);
+    }
+}
+
+pub(crate) fn add_builtin(name: &str, param_num: usize, func: BuiltinFunction) -> (Identifier, Object) {
     let name = name.to_owned();
     (Identifier(name.clone()), Object::Builtin(name, param_num, func))
 }
 
-fn bprint_fn(args: Vec<Object>) -> Result<Object, String> {
-    match args.get(0) {
+pub(crate) fn add_method<'a>(type_name: &'a str, name: &str, param_num: usize, func: BuiltinFunction) -> (&'a str, Identifier, Object) {
+    let (ident, method) = add_builtin(name, param_num, func);
+    (type_name, ident, method)
+}
+
+pub(crate) fn builtin_error(message: &str) -> EvalError {
+    RuntimeError::Builtin(message.to_string()).into()
+}
+
+fn bprint_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    match args.first() {
         Some(Object::String(t)) => {
             println!("{}", t);
             Ok(Object::NULL)
@@ -42,48 +143,154 @@ fn bprint_fn(args: Vec<Object>) -> Result<Object, String> {
             println!("{}", o);
             Ok(Object::NULL)
         }
-        _ => Err(String::from("invalid arguments for print")),
+        _ => Err(builtin_error("invalid arguments for print")),
     }
 }
 
-fn blen_fn(args: Vec<Object>) -> Result<Object, String> {
-    match args.get(0) {
-        Some(Object::String(s)) => Ok(Object::Integer(s.len() as i64)),
+fn blen_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    match args.first() {
+        Some(Object::String(s)) => Ok(Object::Integer(s.chars().count() as i64)),
         Some(Object::Array(arr)) => Ok(Object::Integer(arr.len() as i64)),
-        _ => Err(String::from("invalid arguments for len")),
+        Some(Object::Hash(hash)) => Ok(Object::Integer(hash.len() as i64)),
+        _ => Err(builtin_error("invalid arguments for len")),
     }
 }
 
-fn bhead_fn(args: Vec<Object>) -> Result<Object, String> {
+fn bhead_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
     match args.into_iter().next() {
         Some(Object::Array(arr)) => match arr.into_iter().next() {
-            None => Err(String::from("empty array")),
+            None => Err(builtin_error("empty array")),
             Some(x) => Ok(x),
         },
-        _ => Err(String::from("invalid arguments for head")),
+        _ => Err(builtin_error("invalid arguments for head")),
     }
 }
 
-fn btail_fn(args: Vec<Object>) -> Result<Object, String> {
+fn btail_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
     match args.into_iter().next() {
         Some(Object::Array(mut arr)) => match arr.len() {
-            0 => Err(String::from("empty array")),
+            0 => Err(builtin_error("empty array")),
             _ => {
                 arr.remove(0);
                 Ok(Object::Array(arr))
             }
         },
-        _ => Err(String::from("invalid arguments for tail")),
+        _ => Err(builtin_error("invalid arguments for tail")),
     }
 }
 
-fn bcons_fn(args: Vec<Object>) -> Result<Object, String> {
+fn bcons_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
     let mut args = args.into_iter();
     match (args.next(), args.next()) {
         (Some(o), Some(Object::Array(mut os))) => {
             os.insert(0, o);
             Ok(Object::Array(os))
         }
-        _ => Err(String::from("invalid arguments for cons")),
+        _ => Err(builtin_error("invalid arguments for cons")),
+    }
+}
+
+fn bpush_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let mut args = args.into_iter();
+    match (args.next(), args.next()) {
+        (Some(Object::Array(mut os)), Some(o)) => {
+            os.push(o);
+            Ok(Object::Array(os))
+        }
+        _ => Err(builtin_error("invalid arguments for push")),
+    }
+}
+
+/// array and callback arguments of the higher-order builtins
+fn array_and_fn(args: Vec<Object>, name: &str) -> Result<(Vec<Object>, Object), EvalError> {
+    let mut args = args.into_iter();
+    match (args.next(), args.next()) {
+        (Some(Object::Array(arr)), Some(f)) => Ok((arr, f)),
+        _ => Err(builtin_error(&format!("invalid arguments for {}", name))),
+    }
+}
+
+/// result of a predicate callback
+fn predicate(evaluator: &mut Evaluator, f: &Object, item: Object) -> Result<bool, EvalError> {
+    let result = evaluator.call(f.clone(), vec![item])?;
+    Ok(evaluator.otb(result)?)
+}
+
+fn bmap_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let (arr, f) = array_and_fn(args, "map")?;
+    let mapped = arr.into_iter()
+        .map(|item| evaluator.call(f.clone(), vec![item]))
+        .collect::<Result<_, _>>()?;
+    Ok(Object::Array(mapped))
+}
+
+fn bfilter_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let (arr, f) = array_and_fn(args, "filter")?;
+    let mut filtered = vec![];
+    for item in arr {
+        if predicate(evaluator, &f, item.clone())? {
+            filtered.push(item);
+        }
+    }
+    Ok(Object::Array(filtered))
+}
+
+fn breduce_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let mut args = args.into_iter();
+    match (args.next(), args.next(), args.next()) {
+        (Some(Object::Array(arr)), Some(init), Some(f)) => {
+            arr.into_iter().try_fold(init, |acc, item| evaluator.call(f.clone(), vec![acc, item]))
+        }
+        _ => Err(builtin_error("invalid arguments for reduce")),
+    }
+}
+
+/// stable sort by a comparator returning a negative, zero or positive integer
+fn bsort_by_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let (arr, f) = array_and_fn(args, "sort_by")?;
+    Ok(Object::Array(merge_sort(evaluator, &f, arr)?))
+}
+
+/// merge sort which stops at the first failing comparison,
+/// unlike `slice::sort_by` it does not require the comparator to be a total order
+fn merge_sort(evaluator: &mut Evaluator, f: &Object, mut items: Vec<Object>) -> Result<Vec<Object>, EvalError> {
+    if items.len() <= 1 {
+        return Ok(items);
+    }
+    let right = items.split_off(items.len() / 2);
+    let mut left = merge_sort(evaluator, f, items)?.into_iter().peekable();
+    let mut right = merge_sort(evaluator, f, right)?.into_iter().peekable();
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+        let order = match evaluator.call(f.clone(), vec![l.clone(), r.clone()])? {
+            Object::Integer(i) => i.cmp(&0),
+            o => return Err(builtin_error(&format!("{} is not an integer", o))),
+        };
+        // equal elements keep their order
+        let next = if order == Ordering::Greater { right.next() } else { left.next() };
+        merged.extend(next);
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+fn bfind_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let (arr, f) = array_and_fn(args, "find")?;
+    for item in arr {
+        if predicate(evaluator, &f, item.clone())? {
+            return Ok(item);
+        }
+    }
+    Ok(Object::NULL)
+}
+
+fn bany_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let (arr, f) = array_and_fn(args, "any")?;
+    for item in arr {
+        if predicate(evaluator, &f, item)? {
+            return Ok(Object::Boolean(true));
+        }
     }
+    Ok(Object::Boolean(false))
 }
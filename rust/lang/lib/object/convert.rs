@@ -0,0 +1,397 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::rc::Rc;
+
+use crate::evaluator::error::RuntimeError;
+use crate::object::objects::{EnumDef, EnumValue, NativeObject, Object};
+
+/// `#[derive(IntoObject)]` implements `ToObject`, `#[derive(FromObject)]` implements `FromObject`
+pub use lang_derive::{FromObject, IntoObject};
+
+/// conversion of a host value into an object
+pub trait ToObject {
+    fn to_object(&self) -> Object;
+}
+
+/// conversion of an object into a host value
+pub trait FromObject: Sized {
+    fn from_object(object: Object) -> Result<Self, RuntimeError>;
+}
+
+/// host enum exposed as an enum type of the language, implemented by `#[derive(IntoObject)]`
+pub trait EnumType {
+    /// the enum type, the same definition is returned on every call of a thread
+    fn enum_def() -> Rc<EnumDef>;
+}
+
+fn mismatch(object: &Object, expected: &str) -> RuntimeError {
+    RuntimeError::TypeMismatch(format!("{} is not {}", object, expected))
+}
+
+// helpers of the derive macros
+
+#[doc(hidden)]
+pub fn enum_value(def: Rc<EnumDef>, tag: usize, values: Vec<Object>) -> Object {
+    Object::Variant(Rc::new(EnumValue { def, tag, values }))
+}
+
+/// field of a hash or a struct instance, missing fields are `null`
+#[doc(hidden)]
+pub fn struct_field(object: &Object, type_name: &str, field: &str) -> Result<Object, RuntimeError> {
+    match *object {
+        Object::Hash(ref hash) => Ok(hash.get(&Object::String(field.to_string())).cloned().unwrap_or(Object::NULL)),
+        Object::Instance(ref instance) => Ok(instance.borrow().get(field).unwrap_or(Object::NULL)),
+        ref o => Err(mismatch(o, &format!("a {}", type_name))),
+    }
+}
+
+#[doc(hidden)]
+pub fn field_error(path: &str, err: RuntimeError) -> RuntimeError {
+    RuntimeError::TypeMismatch(format!("{}: {}", path, err))
+}
+
+/// variant name and payload of a value of the enum named `type_name`
+#[doc(hidden)]
+pub fn variant_parts(object: Object, type_name: &str) -> Result<(String, Vec<Object>), RuntimeError> {
+    match object {
+        Object::Variant(ref value) if value.def.name == type_name => {
+            Ok((value.variant().to_string(), value.values.clone()))
+        }
+        o => Err(mismatch(&o, &format!("a {}", type_name))),
+    }
+}
+
+/// payload of a variant with its expected length
+#[doc(hidden)]
+pub fn payload(type_name: &str, variant: &str, values: Vec<Object>, expected: usize) -> Result<Vec<Object>, RuntimeError> {
+    if values.len() == expected {
+        Ok(values)
+    } else {
+        Err(RuntimeError::TypeMismatch(format!(
+            "{}.{} expects {} values but {} given",
+            type_name, variant, expected, values.len()
+        )))
+    }
+}
+
+#[doc(hidden)]
+pub fn unknown_variant(type_name: &str, variant: &str) -> RuntimeError {
+    RuntimeError::TypeMismatch(format!("{} has no variant {}", type_name, variant))
+}
+
+impl ToObject for Object {
+    fn to_object(&self) -> Object {
+        self.clone()
+    }
+}
+
+impl FromObject for Object {
+    fn from_object(object: Object) -> Result<Self, RuntimeError> {
+        Ok(object)
+    }
+}
+
+/// integers are converted through `i64`, values above `i64::MAX` saturate,
+/// out of range values of scripts are type mismatches
+macro_rules! integer_conversions {
+    ($($t:ty),*) => {
+        $(
+            impl ToObject for $t {
+                fn to_object(&self) -> Object {
+                    Object::Integer(i64::try_from(*self).unwrap_or(i64::MAX))
+                }
+            }
+
+            impl FromObject for $t {
+                fn from_object(object: Object) -> Result<Self, RuntimeError> {
+                    match object {
+                        Object::Integer(i) => <$t>::try_from(i).map_err(|_| {
+                            RuntimeError::TypeMismatch(format!("{} is out of range for {}", i, stringify!($t)))
+                        }),
+                        o => Err(mismatch(&o, "an integer")),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+integer_conversions!(i64, i32, i16, i8, u32, u16, u8, usize);
+
+/// floats are converted through `f64`, integers are accepted as floats
+macro_rules! float_conversions {
+    ($($t:ty),*) => {
+        $(
+            impl ToObject for $t {
+                fn to_object(&self) -> Object {
+                    Object::Float(*self as f64)
+                }
+            }
+
+            impl FromObject for $t {
+                fn from_object(object: Object) -> Result<Self, RuntimeError> {
+                    match object {
+                        Object::Float(x) => Ok(x as $t),
+                        Object::Integer(i) => Ok(i as $t),
+                        o => Err(mismatch(&o, "a number")),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+float_conversions!(f64, f32);
+
+impl ToObject for bool {
+    fn to_object(&self) -> Object {
+        Object::Boolean(*self)
+    }
+}
+
+impl FromObject for bool {
+    fn from_object(object: Object) -> Result<Self, RuntimeError> {
+        match object {
+            Object::Boolean(b) => Ok(b),
+            o => Err(mismatch(&o, "a bool")),
+        }
+    }
+}
+
+impl ToObject for String {
+    fn to_object(&self) -> Object {
+        Object::String(self.clone())
+    }
+}
+
+impl ToObject for str {
+    fn to_object(&self) -> Object {
+        Object::String(self.to_string())
+    }
+}
+
+impl FromObject for String {
+    fn from_object(object: Object) -> Result<Self, RuntimeError> {
+        match object {
+            Object::String(s) => Ok(s),
+            o => Err(mismatch(&o, "a string")),
+        }
+    }
+}
+
+impl ToObject for () {
+    fn to_object(&self) -> Object {
+        Object::NULL
+    }
+}
+
+impl<T: ToObject + ?Sized> ToObject for &T {
+    fn to_object(&self) -> Object {
+        (**self).to_object()
+    }
+}
+
+/// `None` is `null`
+impl<T: ToObject> ToObject for Option<T> {
+    fn to_object(&self) -> Object {
+        match *self {
+            Some(ref v) => v.to_object(),
+            None => Object::NULL,
+        }
+    }
+}
+
+impl<T: FromObject> FromObject for Option<T> {
+    fn from_object(object: Object) -> Result<Self, RuntimeError> {
+        match object {
+            Object::NULL => Ok(None),
+            o => T::from_object(o).map(Some),
+        }
+    }
+}
+
+impl<T: ToObject> ToObject for Vec<T> {
+    fn to_object(&self) -> Object {
+        Object::Array(self.iter().map(ToObject::to_object).collect())
+    }
+}
+
+impl<T: ToObject> ToObject for [T] {
+    fn to_object(&self) -> Object {
+        Object::Array(self.iter().map(ToObject::to_object).collect())
+    }
+}
+
+impl<T: FromObject> FromObject for Vec<T> {
+    fn from_object(object: Object) -> Result<Self, RuntimeError> {
+        match object {
+            Object::Array(items) => items.into_iter().map(T::from_object).collect(),
+            o => Err(mismatch(&o, "an array")),
+        }
+    }
+}
+
+impl<K: ToObject, V: ToObject> ToObject for HashMap<K, V> {
+    fn to_object(&self) -> Object {
+        Object::Hash(self.iter().map(|(k, v)| (k.to_object(), v.to_object())).collect())
+    }
+}
+
+impl<K: FromObject + Eq + Hash, V: FromObject> FromObject for HashMap<K, V> {
+    fn from_object(object: Object) -> Result<Self, RuntimeError> {
+        match object {
+            Object::Hash(hash) => hash.into_iter()
+                .map(|(k, v)| Ok((K::from_object(k)?, V::from_object(v)?)))
+                .collect(),
+            o => Err(mismatch(&o, "a hash")),
+        }
+    }
+}
+
+impl ToObject for NativeObject {
+    fn to_object(&self) -> Object {
+        Object::Native(self.clone())
+    }
+}
+
+impl FromObject for NativeObject {
+    fn from_object(object: Object) -> Result<Self, RuntimeError> {
+        match object {
+            Object::Native(native) => Ok(native),
+            o => Err(mismatch(&o, "a native value")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::evaluator::Evaluator;
+    use crate::evaluator::error::RuntimeError;
+    use crate::lexer::Lexer;
+    use crate::object::convert::{FromObject, IntoObject, ToObject};
+    use crate::parser::Parser;
+    use crate::token::Tokens;
+    use crate::object::objects::{HashPairs, NativeObject, Object};
+
+    #[test]
+    fn round_trip() {
+        assert_eq!(5u8.to_object(), Object::Integer(5));
+        assert_eq!(u8::from_object(Object::Integer(5)), Ok(5));
+        assert_eq!(usize::MAX.to_object(), Object::Integer(i64::MAX));
+        assert_eq!((-3i8).to_object(), Object::Integer(-3));
+        assert_eq!(
+            u8::from_object(Object::Integer(300)),
+            Err(RuntimeError::TypeMismatch("300 is out of range for u8".to_string()))
+        );
+        assert_eq!(1.5f64.to_object(), Object::Float(1.5));
+        assert_eq!(f64::from_object(Object::Integer(2)), Ok(2.0));
+        assert_eq!("a".to_object(), Object::String("a".to_string()));
+        assert_eq!(String::from_object(Object::Boolean(true)), Err(RuntimeError::TypeMismatch("true is not a string".to_string())));
+
+        let v = vec![Some(1i64), None];
+        let object = v.to_object();
+        assert_eq!(object, Object::Array(vec![Object::Integer(1), Object::NULL]));
+        assert_eq!(Vec::<Option<i64>>::from_object(object), Ok(v));
+
+        let mut hash = HashMap::new();
+        hash.insert("k".to_string(), vec![true]);
+        assert_eq!(HashMap::<String, Vec<bool>>::from_object(hash.to_object()), Ok(hash));
+
+        let native = NativeObject::new("Counter", 1i64);
+        let object = native.to_object();
+        assert_eq!(object.type_name(), "Counter");
+        let native = NativeObject::from_object(object).unwrap();
+        *native.borrow_mut::<i64>().unwrap() += 1;
+        assert_eq!(*native.borrow::<i64>().unwrap(), 2);
+        assert!(native.borrow::<String>().is_none());
+        // conflicting borrows do not panic
+        let value = native.borrow_mut::<i64>().unwrap();
+        assert!(native.borrow::<i64>().is_none());
+        assert!(native.borrow_mut::<i64>().is_none());
+        drop(value);
+        let value = native.borrow::<i64>().unwrap();
+        assert!(native.borrow_mut::<i64>().is_none());
+        assert_eq!(*native.borrow::<i64>().unwrap(), *value);
+    }
+
+    #[derive(Debug, PartialEq, IntoObject, FromObject)]
+    struct Config {
+        name: String,
+        #[object(rename = "max-size")]
+        max_size: Option<u32>,
+        tags: Vec<String>,
+        #[object(skip)]
+        cache: Vec<i64>,
+    }
+
+    #[derive(Debug, PartialEq, IntoObject, FromObject)]
+    #[object(rename = "Shape")]
+    enum HostShape {
+        Circle(i64),
+        #[object(rename = "Rect")]
+        Rectangle { w: i64, h: i64 },
+        Empty,
+    }
+
+    #[derive(Debug, PartialEq, IntoObject, FromObject)]
+    struct Wrapper<T> {
+        value: T,
+    }
+
+    #[test]
+    fn derive() {
+        let config = Config {
+            name: "lang".to_string(),
+            max_size: Some(3),
+            tags: vec!["a".to_string()],
+            cache: vec![1],
+        };
+        let object = config.to_object();
+        let Object::Hash(ref hash) = object else { panic!() };
+        assert_eq!(hash.len(), 3);
+        assert_eq!(hash[&Object::String("max-size".to_string())], Object::Integer(3));
+        assert_eq!(Config::from_object(object), Ok(Config { cache: vec![], ..config }));
+
+        let missing = Object::Hash(vec![
+            (Object::String("name".to_string()), Object::String("x".to_string())),
+            (Object::String("tags".to_string()), Object::Array(vec![])),
+        ].into_iter().collect());
+        assert_eq!(Config::from_object(missing).map(|c| c.max_size), Ok(None));
+        assert_eq!(
+            Config::from_object(Object::Hash(HashPairs::new())),
+            Err(RuntimeError::TypeMismatch("Config.name: null is not a string".to_string()))
+        );
+        assert_eq!(
+            Config::from_object(Object::Integer(1)),
+            Err(RuntimeError::TypeMismatch("1 is not a Config".to_string()))
+        );
+
+        let shapes = vec![HostShape::Circle(1), HostShape::Rectangle { w: 2, h: 3 }, HostShape::Empty];
+        let objects: Vec<Object> = shapes.iter().map(ToObject::to_object).collect();
+        assert_eq!(objects.iter().map(ToString::to_string).collect::<Vec<_>>(), vec![
+            "Shape.Circle(1)",
+            "Shape.Rect(2, 3)",
+            "Shape.Empty",
+        ]);
+        assert_eq!(HostShape::Circle(1).to_object(), objects[0]);
+        assert_eq!(
+            objects.into_iter().map(HostShape::from_object).collect::<Result<Vec<_>, _>>(),
+            Ok(shapes)
+        );
+
+        assert_eq!(Wrapper::<bool>::from_object(Wrapper { value: true }.to_object()), Ok(Wrapper { value: true }));
+
+        // host enums and structs in scripts
+        let (_, r) = Lexer::lexing(&b"struct Config { name, tags };\
+            [match (shape) { Rect(w, h) => w * h, _ => 0 }, Circle(5), Config(\"s\", [])]"[..]).unwrap();
+        let (_, program) = Parser::parsing(Tokens::new(&r)).unwrap();
+        let mut evaluator = Evaluator::new();
+        evaluator.register_enum::<HostShape>().unwrap();
+        evaluator.set_global("shape", &HostShape::Rectangle { w: 2, h: 3 });
+        let Object::Array(result) = evaluator.evaluation(program) else { panic!() };
+        assert_eq!(result[0], Object::Integer(6));
+        assert_eq!(HostShape::from_object(result[1].clone()), Ok(HostShape::Circle(5)));
+        assert_eq!(Config::from_object(result[2].clone()).map(|c| c.name), Ok("s".to_string()));
+    }
+}
@@ -1,16 +1,36 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
 use std::rc::Rc;
 use crate::ast::Identifier;
 use crate::object::builtins::BuiltinsFunctions;
-use crate::object::objects::Object;
+use crate::evaluator::{EvalResult, Evaluator};
+use crate::object::objects::{NativeFunction, Object};
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, PartialEq)]
 pub struct Environment {
     store: HashMap<String, Object>,
     outer: Option<Rc<RefCell<Environment>>>,
 }
 
+// only the names are printed, the values may be functions whose env is this env
+impl Debug for Environment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut names = self.store.keys().collect::<Vec<_>>();
+        names.sort();
+        f.debug_struct("Environment")
+            .field("names", &names)
+            .field("outer", &self.outer.is_some())
+            .finish()
+    }
+}
+
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Environment {
     /// create new env
@@ -27,6 +47,14 @@ impl Environment {
         self.store.insert(name.to_string(), val);
     }
 
+    /// store a host closure as a builtin function
+    pub fn register_fn<F>(&mut self, name: &str, num_params: usize, f: F)
+    where
+        F: Fn(&mut Evaluator, Vec<Object>) -> EvalResult + 'static,
+    {
+        self.set(name, Object::Closure(name.to_string(), num_params, NativeFunction::new(f)));
+    }
+
     /// get Object with identify
     pub fn get(&self, name: &str) -> Option<Object> {
         match self.store.get(name) {
@@ -38,11 +66,21 @@ impl Environment {
         }
     }
 
+    /// call `f` with the Object of identify without cloning it
+    pub fn with<T>(&self, name: &str, f: impl FnOnce(&Object) -> T) -> Option<T> {
+        match self.store.get(name) {
+            Some(o) => Some(f(o)),
+            None => match self.outer {
+                Some(ref parent_env) => parent_env.borrow().with(name, f),
+                None => None,
+            },
+        }
+    }
+
+    /// create an enclosed env, builtins are resolved through the outermost env
     pub fn new_with_outer(outer: Rc<RefCell<Environment>>) -> Self {
-        let mut hashmap = HashMap::new();
-        Self::fill_env_with_builtins(&mut hashmap);
         Environment {
-            store: hashmap,
+            store: HashMap::new(),
             outer: Some(outer),
         }
     }
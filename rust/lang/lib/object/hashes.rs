@@ -0,0 +1,97 @@
+use crate::ast::Identifier;
+use crate::evaluator::error::EvalError;
+use crate::evaluator::{EvalResult, Evaluator};
+use crate::object::builtins::{add_builtin, add_method, builtin_error};
+use crate::object::objects::{HashPairs, Object};
+
+/// hash builtins, hashes are values so the updating builtins return a new hash
+pub fn get_builtins() -> Vec<(Identifier, Object)> {
+    vec![
+        add_builtin("keys", 1, bkeys_fn),
+        add_builtin("values", 1, bvalues_fn),
+        add_builtin("entries", 1, bentries_fn),
+        add_builtin("has", 2, bhas_fn),
+        add_builtin("set", 3, bset_fn),
+        add_builtin("delete", 2, bdelete_fn),
+        add_builtin("merge", 2, bmerge_fn),
+    ]
+}
+
+/// the hash builtins as methods of hashes
+pub fn get_methods() -> Vec<(&'static str, Identifier, Object)> {
+    vec![
+        add_method("hash", "keys", 1, bkeys_fn),
+        add_method("hash", "values", 1, bvalues_fn),
+        add_method("hash", "entries", 1, bentries_fn),
+        add_method("hash", "has", 2, bhas_fn),
+        add_method("hash", "set", 3, bset_fn),
+        add_method("hash", "delete", 2, bdelete_fn),
+        add_method("hash", "merge", 2, bmerge_fn),
+    ]
+}
+
+/// the hash argument and the remaining arguments
+fn hash_args(args: Vec<Object>, name: &str) -> Result<(HashPairs, impl Iterator<Item = Object>), EvalError> {
+    let mut args = args.into_iter();
+    match args.next() {
+        Some(Object::Hash(hash)) => Ok((hash, args)),
+        _ => Err(builtin_error(&format!("invalid arguments for {}", name))),
+    }
+}
+
+/// a key argument, checked like the keys of hash literals
+fn key_arg(evaluator: &mut Evaluator, key: Option<Object>) -> Result<Object, EvalError> {
+    Ok(evaluator.othash(key.unwrap_or(Object::NULL))?)
+}
+
+/// keys in insertion order
+fn bkeys_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let (hash, _) = hash_args(args, "keys")?;
+    Ok(Object::Array(hash.into_keys().collect()))
+}
+
+/// values in insertion order of their keys
+fn bvalues_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let (hash, _) = hash_args(args, "values")?;
+    Ok(Object::Array(hash.into_values().collect()))
+}
+
+/// `[key, value]` pairs in insertion order
+fn bentries_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let (hash, _) = hash_args(args, "entries")?;
+    Ok(Object::Array(hash.into_iter().map(|(k, v)| Object::Array(vec![k, v])).collect()))
+}
+
+fn bhas_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let (hash, mut args) = hash_args(args, "has")?;
+    let key = key_arg(evaluator, args.next())?;
+    Ok(Object::Boolean(hash.contains_key(&key)))
+}
+
+/// `set(hash, key, value)`, a new key is appended, an existing key keeps its position
+fn bset_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let (mut hash, mut args) = hash_args(args, "set")?;
+    let key = key_arg(evaluator, args.next())?;
+    hash.insert(key, args.next().unwrap_or(Object::NULL));
+    Ok(Object::Hash(hash))
+}
+
+/// `delete(hash, key)`, the remaining keys keep their order, missing keys are ignored
+fn bdelete_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let (mut hash, mut args) = hash_args(args, "delete")?;
+    let key = key_arg(evaluator, args.next())?;
+    hash.shift_remove(&key);
+    Ok(Object::Hash(hash))
+}
+
+/// `merge(a, b)`, entries of `b` replace the ones of `a`, new keys of `b` are appended in order
+fn bmerge_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let (mut hash, mut args) = hash_args(args, "merge")?;
+    match args.next() {
+        Some(Object::Hash(other)) => {
+            hash.extend(other);
+            Ok(Object::Hash(hash))
+        }
+        _ => Err(builtin_error("invalid arguments for merge")),
+    }
+}
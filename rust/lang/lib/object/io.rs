@@ -0,0 +1,92 @@
+use std::fs;
+use std::io::{self, Read};
+
+use crate::ast::Identifier;
+use crate::evaluator::error::EvalError;
+use crate::evaluator::{EvalResult, Evaluator};
+use crate::object::builtins::{add_builtin, builtin_error};
+use crate::object::objects::Object;
+
+/// file and stdin builtins, they are checked against the `Capabilities` of the evaluator
+pub fn get_builtins() -> Vec<(Identifier, Object)> {
+    vec![
+        add_builtin("read_file", 1, bread_file_fn),
+        add_builtin("read_lines", 1, bread_lines_fn),
+        add_builtin("write_file", 2, bwrite_file_fn),
+        add_builtin("exists", 1, bexists_fn),
+        add_builtin("list_dir", 1, blist_dir_fn),
+        add_builtin("read_stdin", 0, bread_stdin_fn),
+    ]
+}
+
+fn path_arg(args: Vec<Object>, name: &str) -> Result<String, EvalError> {
+    match args.into_iter().next() {
+        Some(Object::String(path)) => Ok(path),
+        _ => Err(builtin_error(&format!("invalid arguments for {}", name))),
+    }
+}
+
+fn io_error(path: &str, err: io::Error) -> EvalError {
+    builtin_error(&format!("{}: {}", path, err))
+}
+
+/// content of a readable file, its size is checked against the allocation budgets before it is read
+fn read_to_string(evaluator: &mut Evaluator, path: &str) -> Result<String, EvalError> {
+    let resolved = evaluator.capabilities().check_read(path).map_err(|message| builtin_error(&message))?;
+    let size = fs::metadata(&resolved).map_err(|err| io_error(path, err))?.len();
+    evaluator.reserve(usize::try_from(size).unwrap_or(usize::MAX))?;
+    fs::read_to_string(&resolved).map_err(|err| io_error(path, err))
+}
+
+fn bread_file_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let path = path_arg(args, "read_file")?;
+    Ok(Object::String(read_to_string(evaluator, &path)?))
+}
+
+/// lines without their line endings
+fn bread_lines_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let path = path_arg(args, "read_lines")?;
+    let content = read_to_string(evaluator, &path)?;
+    Ok(Object::Array(content.lines().map(|line| Object::String(line.to_string())).collect()))
+}
+
+/// `write_file(path, content)` creates or replaces the file
+fn bwrite_file_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let mut args = args.into_iter();
+    let (path, content) = match (args.next(), args.next()) {
+        (Some(Object::String(path)), Some(Object::String(content))) => (path, content),
+        _ => return Err(builtin_error("invalid arguments for write_file")),
+    };
+    let resolved = evaluator.capabilities().check_write(&path).map_err(|message| builtin_error(&message))?;
+    fs::write(resolved, content).map_err(|err| io_error(&path, err))?;
+    Ok(Object::NULL)
+}
+
+/// whether a path inside the read roots exists
+fn bexists_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let path = path_arg(args, "exists")?;
+    evaluator.capabilities().check_exists(&path)
+        .map(Object::Boolean)
+        .map_err(|message| builtin_error(&message))
+}
+
+/// names of the entries of a directory, sorted
+fn blist_dir_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let path = path_arg(args, "list_dir")?;
+    let resolved = evaluator.capabilities().check_read(&path).map_err(|message| builtin_error(&message))?;
+    let mut names = fs::read_dir(resolved)
+        .and_then(|entries| entries.map(|entry| entry.map(|e| e.file_name().to_string_lossy().into_owned())).collect::<io::Result<Vec<_>>>())
+        .map_err(|err| io_error(&path, err))?;
+    names.sort();
+    Ok(Object::Array(names.into_iter().map(Object::String).collect()))
+}
+
+/// the rest of the standard input
+fn bread_stdin_fn(evaluator: &mut Evaluator, _: Vec<Object>) -> EvalResult {
+    if !evaluator.capabilities().stdin {
+        return Err(builtin_error("reading stdin is not allowed"));
+    }
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input).map_err(|err| io_error("stdin", err))?;
+    Ok(Object::String(input))
+}
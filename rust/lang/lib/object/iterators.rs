@@ -0,0 +1,140 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::vec;
+
+use crate::ast::Identifier;
+use crate::evaluator::error::EvalError;
+use crate::evaluator::generators::GeneratorState;
+use crate::evaluator::{EvalResult, Evaluator};
+use crate::object::builtins::{add_builtin, add_method, builtin_error};
+use crate::object::objects::Object;
+
+/// state of an `Object::Iterator`, the adapters pull the items of their source one at a time
+#[derive(Debug)]
+pub enum Iter {
+    // items of an array, the chars of a string or the keys of a hash
+    Items(vec::IntoIter<Object>),
+    // call of a generator function, suspended after a `yield`
+    Generator(GeneratorState),
+    // object with a `next()` method returning `{"value": v, "done": false}` until `{"done": true}`
+    Protocol(Object),
+    // results of a function applied to the items
+    Map(Rc<RefCell<Iter>>, Object),
+    // items for which a function returns true
+    Filter(Rc<RefCell<Iter>>, Object),
+    // at most the given number of items
+    Take(Rc<RefCell<Iter>>, usize),
+    // the items after the given number of items
+    Skip(Rc<RefCell<Iter>>, usize),
+    // `[left, right]` pairs of items, until one of the sources ends
+    Zip(Rc<RefCell<Iter>>, Rc<RefCell<Iter>>),
+    // `[index, item]` pairs, the index counts from the given number
+    Enumerate(Rc<RefCell<Iter>>, i64),
+    // placeholder while an item is computed, calling `next()` on a running iterator fails
+    Running,
+}
+
+/// a new iterator object
+pub fn iterator_object(iter: Iter) -> Object {
+    Object::Iterator(Rc::new(RefCell::new(iter)))
+}
+
+/// `iter(x)`, a lazy iterator over an array, string, hash, iterator or object with a `next()` method
+pub fn get_builtins() -> Vec<(Identifier, Object)> {
+    vec![
+        add_builtin("iter", 1, biter_fn),
+    ]
+}
+
+/// methods of iterators, the adapters return a new iterator which pulls from the receiver
+pub fn get_methods() -> Vec<(&'static str, Identifier, Object)> {
+    vec![
+        add_method("iterator", "next", 1, bnext_fn),
+        add_method("iterator", "collect", 1, bcollect_fn),
+        add_method("iterator", "map", 2, bmap_fn),
+        add_method("iterator", "filter", 2, bfilter_fn),
+        add_method("iterator", "take", 2, btake_fn),
+        add_method("iterator", "skip", 2, bskip_fn),
+        add_method("iterator", "zip", 2, bzip_fn),
+        add_method("iterator", "enumerate", 1, benumerate_fn),
+    ]
+}
+
+/// the receiver of an iterator method and its other arguments
+fn iterator_args(args: Vec<Object>, name: &str) -> Result<(Rc<RefCell<Iter>>, Vec<Object>), EvalError> {
+    let mut args = args.into_iter();
+    match args.next() {
+        Some(Object::Iterator(iter)) => Ok((iter, args.collect())),
+        _ => Err(builtin_error(&format!("invalid arguments for {}", name))),
+    }
+}
+
+/// the receiver and the count argument of `take` and `skip`
+fn count_args(evaluator: &mut Evaluator, args: Vec<Object>, name: &str) -> Result<(Rc<RefCell<Iter>>, usize), EvalError> {
+    let (iter, mut args) = iterator_args(args, name)?;
+    let count = evaluator.oti(args.remove(0))?;
+    let count = usize::try_from(count).map_err(|_| builtin_error(&format!("negative count {} for {}", count, name)))?;
+    Ok((iter, count))
+}
+
+fn biter_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    match args.into_iter().next() {
+        Some(object) => Ok(Object::Iterator(evaluator.iterator(object)?)),
+        None => Err(builtin_error("invalid arguments for iter")),
+    }
+}
+
+/// `it.next()`, `{"value": item, "done": false}` or `{"value": null, "done": true}` once the iterator is exhausted
+fn bnext_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let (iter, _) = iterator_args(args, "next")?;
+    let item = evaluator.next_item(&iter)?;
+    let done = item.is_none();
+    Ok(Object::Hash(vec![
+        (Object::String("value".to_string()), item.unwrap_or(Object::NULL)),
+        (Object::String("done".to_string()), Object::Boolean(done)),
+    ].into_iter().collect()))
+}
+
+/// `it.collect()`, the remaining items as an array
+fn bcollect_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let (iter, _) = iterator_args(args, "collect")?;
+    let mut items = vec![];
+    while let Some(item) = evaluator.next_item(&iter)? {
+        items.push(item);
+    }
+    Ok(Object::Array(items))
+}
+
+fn bmap_fn(evaluator: &mut Evaluator, mut args: Vec<Object>) -> EvalResult {
+    let f = evaluator.otfn(args.pop().unwrap_or(Object::NULL))?;
+    let (iter, _) = iterator_args(args, "map")?;
+    Ok(iterator_object(Iter::Map(iter, f)))
+}
+
+fn bfilter_fn(evaluator: &mut Evaluator, mut args: Vec<Object>) -> EvalResult {
+    let f = evaluator.otfn(args.pop().unwrap_or(Object::NULL))?;
+    let (iter, _) = iterator_args(args, "filter")?;
+    Ok(iterator_object(Iter::Filter(iter, f)))
+}
+
+fn btake_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let (iter, count) = count_args(evaluator, args, "take")?;
+    Ok(iterator_object(Iter::Take(iter, count)))
+}
+
+fn bskip_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let (iter, count) = count_args(evaluator, args, "skip")?;
+    Ok(iterator_object(Iter::Skip(iter, count)))
+}
+
+/// `it.zip(other)`, `other` is anything `for ... in` accepts
+fn bzip_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let (iter, mut args) = iterator_args(args, "zip")?;
+    let other = evaluator.iterator(args.remove(0))?;
+    Ok(iterator_object(Iter::Zip(iter, other)))
+}
+
+fn benumerate_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let (iter, _) = iterator_args(args, "enumerate")?;
+    Ok(iterator_object(Iter::Enumerate(iter, 0)))
+}
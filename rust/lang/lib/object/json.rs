@@ -0,0 +1,135 @@
+use serde::Serialize;
+use serde_json::ser::{PrettyFormatter, Serializer};
+use serde_json::{Map, Number, Value};
+
+use crate::ast::Identifier;
+use crate::evaluator::error::RuntimeError;
+use crate::evaluator::{EvalResult, Evaluator};
+use crate::object::builtins::{add_builtin, builtin_error};
+use crate::object::convert::{FromObject, ToObject};
+use crate::object::objects::Object;
+
+/// deepest nesting of arrays and objects converted to JSON, the same as the limit of the JSON parser
+const MAX_DEPTH: usize = 128;
+
+/// widest indent of `json_stringify`
+const MAX_INDENT: usize = 16;
+
+/// JSON builtins
+pub fn get_builtins() -> Vec<(Identifier, Object)> {
+    vec![
+        add_builtin("json_parse", 1, bjson_parse_fn),
+        add_builtin("json_stringify", 2, bjson_stringify_fn),
+    ]
+}
+
+/// JSON value as an object, objects become hashes in the order of their keys,
+/// numbers become integers if they fit and floats otherwise
+pub fn from_json(value: Value) -> Object {
+    match value {
+        Value::Null => Object::NULL,
+        Value::Bool(b) => Object::Boolean(b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Object::Integer(i),
+            None => Object::Float(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        Value::String(s) => Object::String(s),
+        Value::Array(items) => Object::Array(items.into_iter().map(from_json).collect()),
+        Value::Object(map) => Object::Hash(map.into_iter().map(|(k, v)| (Object::String(k), from_json(v))).collect()),
+    }
+}
+
+/// object as a JSON value, struct instances become objects of their fields,
+/// functions, hash keys which are not strings and non-finite floats are errors
+pub fn to_json(object: &Object) -> Result<Value, RuntimeError> {
+    json_value(object, 0)
+}
+
+fn json_value(object: &Object, depth: usize) -> Result<Value, RuntimeError> {
+    if depth > MAX_DEPTH {
+        // also stops at instances which contain themselves
+        return Err(json_error(format!("{} is nested too deeply", object.type_name())));
+    }
+    match *object {
+        Object::NULL => Ok(Value::Null),
+        Object::Boolean(b) => Ok(Value::Bool(b)),
+        Object::Integer(i) => Ok(Value::Number(i.into())),
+        Object::Float(x) => Number::from_f64(x)
+            .map(Value::Number)
+            .ok_or_else(|| json_error(format!("{:?} can not be converted to JSON", x))),
+        Object::String(ref s) => Ok(Value::String(s.clone())),
+        Object::Array(ref items) => items.iter()
+            .map(|item| json_value(item, depth + 1))
+            .collect::<Result<_, _>>()
+            .map(Value::Array),
+        Object::Hash(ref hash) => {
+            let mut map = Map::new();
+            for (k, v) in hash {
+                match *k {
+                    Object::String(ref key) => map.insert(key.clone(), json_value(v, depth + 1)?),
+                    ref key => return Err(json_error(format!("hash key {} is not a string", key))),
+                };
+            }
+            Ok(Value::Object(map))
+        }
+        Object::Instance(ref instance) => {
+            let instance = instance.borrow();
+            let mut map = Map::new();
+            for (field, value) in instance.def.fields.iter().zip(instance.values.iter()) {
+                map.insert(field.clone(), json_value(value, depth + 1)?);
+            }
+            Ok(Value::Object(map))
+        }
+        ref o => Err(json_error(format!("{} can not be converted to JSON", o))),
+    }
+}
+
+fn json_error(message: String) -> RuntimeError {
+    RuntimeError::TypeMismatch(message)
+}
+
+impl ToObject for Value {
+    fn to_object(&self) -> Object {
+        from_json(self.clone())
+    }
+}
+
+impl FromObject for Value {
+    fn from_object(object: Object) -> Result<Self, RuntimeError> {
+        to_json(&object)
+    }
+}
+
+/// `json_parse(s)`
+fn bjson_parse_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    match args.into_iter().next() {
+        Some(Object::String(s)) => serde_json::from_str(&s)
+            .map(from_json)
+            .map_err(|err| builtin_error(&format!("invalid JSON: {}", err))),
+        _ => Err(builtin_error("invalid arguments for json_parse")),
+    }
+}
+
+/// `json_stringify(object, indent)`, an indent of 0 gives compact output, the indent is at most 16 spaces
+fn bjson_stringify_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let mut args = args.into_iter();
+    let (object, indent) = match (args.next(), args.next()) {
+        (Some(object), Some(Object::Integer(indent))) if indent >= 0 => (object, indent as usize),
+        _ => return Err(builtin_error("invalid arguments for json_stringify")),
+    };
+    if indent > MAX_INDENT {
+        return Err(builtin_error(&format!("indent {} is larger than {}", indent, MAX_INDENT)));
+    }
+    let value = to_json(&object)?;
+    let json = if indent == 0 {
+        value.to_string()
+    } else {
+        let indent = " ".repeat(indent);
+        let mut out = Vec::new();
+        let mut serializer = Serializer::with_formatter(&mut out, PrettyFormatter::with_indent(indent.as_bytes()));
+        value.serialize(&mut serializer).map_err(|err| builtin_error(&err.to_string()))?;
+        // the serializer only writes valid utf-8
+        String::from_utf8(out).unwrap_or_default()
+    };
+    Ok(Object::String(json))
+}
@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::f64::consts;
+use std::rc::Rc;
+
+use crate::ast::Identifier;
+use crate::evaluator::error::{overflow, EvalError};
+use crate::evaluator::{EvalResult, Evaluator};
+use crate::object::builtins::{add_builtin, builtin_error};
+use crate::object::objects::{Module, Object};
+
+/// the `math` module, integer results are checked for overflow,
+/// functions which are only defined on reals return floats
+pub fn get_module() -> Object {
+    let functions = vec![
+        add_builtin("abs", 1, babs_fn),
+        add_builtin("min", 2, bmin_fn),
+        add_builtin("max", 2, bmax_fn),
+        add_builtin("clamp", 3, bclamp_fn),
+        add_builtin("pow", 2, bpow_fn),
+        add_builtin("sum", 1, bsum_fn),
+        add_builtin("gcd", 2, bgcd_fn),
+        add_builtin("floor", 1, bfloor_fn),
+        add_builtin("ceil", 1, bceil_fn),
+        add_builtin("round", 1, bround_fn),
+        add_builtin("trunc", 1, btrunc_fn),
+        add_builtin("float", 1, bfloat_fn),
+        add_builtin("sqrt", 1, bsqrt_fn),
+        add_builtin("exp", 1, bexp_fn),
+        add_builtin("ln", 1, bln_fn),
+        add_builtin("sin", 1, bsin_fn),
+        add_builtin("cos", 1, bcos_fn),
+        add_builtin("tan", 1, btan_fn),
+        add_builtin("asin", 1, basin_fn),
+        add_builtin("acos", 1, bacos_fn),
+        add_builtin("atan", 1, batan_fn),
+        add_builtin("atan2", 2, batan2_fn),
+    ];
+    let constants = vec![
+        ("PI", Object::Float(consts::PI)),
+        ("E", Object::Float(consts::E)),
+        ("INF", Object::Float(f64::INFINITY)),
+        ("NAN", Object::Float(f64::NAN)),
+        ("MAX_INT", Object::Integer(i64::MAX)),
+        ("MIN_INT", Object::Integer(i64::MIN)),
+    ];
+    let members: HashMap<String, Object> = functions.into_iter()
+        .map(|(Identifier(name), function)| (name, function))
+        .chain(constants.into_iter().map(|(name, value)| (name.to_string(), value)))
+        .collect();
+    Object::Module(Rc::new(Module {
+        name: "math".to_string(),
+        members,
+    }))
+}
+
+/// an integer or a float argument
+enum Number {
+    Int(i64),
+    Float(f64),
+}
+
+impl Number {
+    fn to_f64(&self) -> f64 {
+        match *self {
+            Number::Int(i) => i as f64,
+            Number::Float(x) => x,
+        }
+    }
+
+    /// integers are compared exactly, they are only converted when the other number is a float
+    fn less_than(&self, other: &Number) -> bool {
+        match (self, other) {
+            (Number::Int(a), Number::Int(b)) => a < b,
+            (a, b) => a.to_f64() < b.to_f64(),
+        }
+    }
+}
+
+fn numbers<const N: usize>(args: Vec<Object>, name: &str) -> Result<[Number; N], EvalError> {
+    let numbers = args.into_iter()
+        .map(|arg| match arg {
+            Object::Integer(i) => Ok(Number::Int(i)),
+            Object::Float(x) => Ok(Number::Float(x)),
+            o => Err(builtin_error(&format!("{} is not a number", o))),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    numbers.try_into().map_err(|_| builtin_error(&format!("invalid arguments for {}", name)))
+}
+
+fn integers<const N: usize>(args: Vec<Object>, name: &str) -> Result<[i64; N], EvalError> {
+    let integers = args.into_iter()
+        .map(|arg| match arg {
+            Object::Integer(i) => Ok(i),
+            o => Err(builtin_error(&format!("{} is not an integer", o))),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    integers.try_into().map_err(|_| builtin_error(&format!("invalid arguments for {}", name)))
+}
+
+fn checked(result: Option<i64>) -> EvalResult {
+    result.map(Object::Integer).ok_or_else(|| overflow().into())
+}
+
+/// a float rounded to an integer, out of range values and `NaN` are errors
+fn to_integer(x: f64) -> EvalResult {
+    // `i64::MAX as f64` rounds up to 2^63, which is already out of range
+    if x.is_nan() || x < i64::MIN as f64 || x >= i64::MAX as f64 {
+        Err(builtin_error(&format!("{:?} is out of range for an integer", x)))
+    } else {
+        Ok(Object::Integer(x as i64))
+    }
+}
+
+fn babs_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    match numbers(args, "abs")? {
+        [Number::Int(i)] => checked(i.checked_abs()),
+        [Number::Float(x)] => Ok(Object::Float(x.abs())),
+    }
+}
+
+/// the smaller number, integers stay integers
+fn bmin_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let [a, b] = numbers(args, "min")?;
+    Ok(number_object(if b.less_than(&a) { b } else { a }))
+}
+
+/// the larger number, integers stay integers
+fn bmax_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let [a, b] = numbers(args, "max")?;
+    Ok(number_object(if a.less_than(&b) { b } else { a }))
+}
+
+fn number_object(n: Number) -> Object {
+    match n {
+        Number::Int(i) => Object::Integer(i),
+        Number::Float(x) => Object::Float(x),
+    }
+}
+
+/// `clamp(x, lo, hi)`
+fn bclamp_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    match numbers(args, "clamp")? {
+        [Number::Int(x), Number::Int(lo), Number::Int(hi)] if lo <= hi => Ok(Object::Integer(x.clamp(lo, hi))),
+        [x, lo, hi] if lo.to_f64() <= hi.to_f64() => Ok(Object::Float(x.to_f64().clamp(lo.to_f64(), hi.to_f64()))),
+        _ => Err(builtin_error("clamp bounds are out of order")),
+    }
+}
+
+/// integer powers with a non negative exponent are integers, other powers are floats
+fn bpow_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    match numbers(args, "pow")? {
+        [Number::Int(base), Number::Int(exp)] if exp >= 0 => {
+            checked(u32::try_from(exp).ok().and_then(|exp| base.checked_pow(exp)))
+        }
+        [base, exp] => Ok(Object::Float(base.to_f64().powf(exp.to_f64()))),
+    }
+}
+
+/// sum of an array of numbers, a float makes the sum a float
+fn bsum_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let items = match args.into_iter().next() {
+        Some(Object::Array(items)) => items,
+        _ => return Err(builtin_error("invalid arguments for sum")),
+    };
+    let mut sum = Number::Int(0);
+    for item in items {
+        let [n] = numbers(vec![item], "sum")?;
+        sum = match (sum, n) {
+            (Number::Int(a), Number::Int(b)) => Number::Int(a.checked_add(b).ok_or_else(overflow)?),
+            (a, b) => Number::Float(a.to_f64() + b.to_f64()),
+        };
+    }
+    Ok(number_object(sum))
+}
+
+/// greatest common divisor, never negative
+fn bgcd_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let [a, b] = integers(args, "gcd")?;
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    checked(i64::try_from(a).ok())
+}
+
+/// rounding functions return integers, integers are returned as they are
+fn round_with(args: Vec<Object>, name: &str, round: fn(f64) -> f64) -> EvalResult {
+    match numbers(args, name)? {
+        [Number::Int(i)] => Ok(Object::Integer(i)),
+        [Number::Float(x)] => to_integer(round(x)),
+    }
+}
+
+fn bfloor_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    round_with(args, "floor", f64::floor)
+}
+
+fn bceil_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    round_with(args, "ceil", f64::ceil)
+}
+
+/// half way cases are rounded away from zero
+fn bround_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    round_with(args, "round", f64::round)
+}
+
+fn btrunc_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    round_with(args, "trunc", f64::trunc)
+}
+
+fn bfloat_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let [x] = numbers(args, "float")?;
+    Ok(Object::Float(x.to_f64()))
+}
+
+/// functions of reals, `NaN` outside of their domain
+fn real_fn(args: Vec<Object>, name: &str, f: fn(f64) -> f64) -> EvalResult {
+    let [x] = numbers(args, name)?;
+    Ok(Object::Float(f(x.to_f64())))
+}
+
+fn bsqrt_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    real_fn(args, "sqrt", f64::sqrt)
+}
+
+fn bexp_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    real_fn(args, "exp", f64::exp)
+}
+
+fn bln_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    real_fn(args, "ln", f64::ln)
+}
+
+fn bsin_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    real_fn(args, "sin", f64::sin)
+}
+
+fn bcos_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    real_fn(args, "cos", f64::cos)
+}
+
+fn btan_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    real_fn(args, "tan", f64::tan)
+}
+
+fn basin_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    real_fn(args, "asin", f64::asin)
+}
+
+fn bacos_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    real_fn(args, "acos", f64::acos)
+}
+
+fn batan_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    real_fn(args, "atan", f64::atan)
+}
+
+/// `atan2(y, x)`
+fn batan2_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let [y, x] = numbers(args, "atan2")?;
+    Ok(Object::Float(y.to_f64().atan2(x.to_f64())))
+}
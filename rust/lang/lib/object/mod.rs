@@ -1,3 +1,15 @@
 pub mod objects;
 pub mod builtins;
-pub mod environment;
\ No newline at end of file
+pub mod strings;
+pub mod hashes;
+pub mod math;
+pub mod random;
+pub mod json;
+pub mod io;
+pub mod process;
+pub mod time;
+pub mod regexes;
+pub mod syntax;
+pub mod iterators;
+pub mod environment;
+pub mod convert;
\ No newline at end of file
@@ -1,17 +1,97 @@
-use std::cell::RefCell;
+use std::any::Any;
+use std::cell::{Ref, RefCell, RefMut};
 use std::collections::HashMap;
-use std::fmt::{Display, Formatter};
-use std::hash::{Hash, Hasher};
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::rc::Rc;
+use std::thread::LocalKey;
 
-use crate::ast::{BlockStatement, Identifier};
+use indexmap::IndexMap;
+
+use crate::ast::{BlockStatement, Expr, Identifier};
+use crate::evaluator::{EvalResult, Evaluator};
 use crate::object::environment::Environment;
+use crate::object::iterators::Iter;
 
-pub type BuiltinFunction = fn(Vec<Object>) -> Result<Object, String>;
+/// builtin function, the evaluator is passed to call back functions given as arguments
+pub type BuiltinFunction = fn(&mut Evaluator, Vec<Object>) -> EvalResult;
 
-#[derive(Clone, Debug, PartialEq)]
+/// entries of a hash, iterated in insertion order
+pub type HashPairs = IndexMap<Object, Object>;
+
+/// signature of builtins implemented by host closures
+pub type NativeFn = dyn Fn(&mut Evaluator, Vec<Object>) -> EvalResult;
+
+/// host closure registered as a builtin, it may capture state
+#[derive(Clone)]
+pub struct NativeFunction(pub Rc<NativeFn>);
+
+impl NativeFunction {
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&mut Evaluator, Vec<Object>) -> EvalResult + 'static,
+    {
+        NativeFunction(Rc::new(f))
+    }
+}
+
+// closures are only equal to themselves
+impl PartialEq for NativeFunction {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Debug for NativeFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "NativeFunction")
+    }
+}
+
+/// opaque host value, scripts can only pass it around and call the methods registered for `type_name`
+#[derive(Clone)]
+pub struct NativeObject {
+    pub type_name: &'static str,
+    value: Rc<RefCell<dyn Any>>,
+}
+
+impl NativeObject {
+    pub fn new<T: Any>(type_name: &'static str, value: T) -> Self {
+        NativeObject {
+            type_name,
+            value: Rc::new(RefCell::new(value)),
+        }
+    }
+
+    /// borrow the host value, `None` if it is not a `T` or it is mutably borrowed,
+    /// e.g. by a host method calling back into the same object
+    pub fn borrow<T: Any>(&self) -> Option<Ref<'_, T>> {
+        Ref::filter_map(self.value.try_borrow().ok()?, |value| value.downcast_ref::<T>()).ok()
+    }
+
+    /// mutably borrow the host value, `None` if it is not a `T` or it is already borrowed
+    pub fn borrow_mut<T: Any>(&self) -> Option<RefMut<'_, T>> {
+        RefMut::filter_map(self.value.try_borrow_mut().ok()?, |value| value.downcast_mut::<T>()).ok()
+    }
+}
+
+// host values are only equal to themselves
+impl PartialEq for NativeObject {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.value, &other.value)
+    }
+}
+
+impl Debug for NativeObject {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "NativeObject({})", self.type_name)
+    }
+}
+
+#[derive(Clone, Debug)]
 pub enum Object {
     Integer(i64),
+    Float(f64),
     Boolean(bool),
     String(String),
     NULL,
@@ -20,24 +100,348 @@ pub enum Object {
     Function(
         // Parameters
         Vec<Identifier>,
-        // Body
-        BlockStatement,
+        // Body, shared by the copies of the function
+        Rc<BlockStatement>,
         // Function Env
         Rc<RefCell<Environment>>,
     ),
     Builtin(String, usize, BuiltinFunction),
-    Hash(HashMap<Object, Object>),
+    // host closure with its name and number of parameters
+    Closure(String, usize, NativeFunction),
+    // opaque host value
+    Native(NativeObject),
+    // builtin method bound to its receiver, which is passed as the first argument
+    Method(Box<Object>, Box<Object>),
+    // keys are kept in insertion order
+    Hash(HashPairs),
+    // struct type, called to construct an instance
+    Struct(Rc<StructDef>),
+    // struct instance, shared and mutable through field assignment
+    Instance(Rc<RefCell<Instance>>),
+    // enum type, its variants are accessed as fields
+    Enum(Rc<EnumDef>),
+    // constructor of the enum variant with the given tag
+    Constructor(Rc<EnumDef>, usize),
+    // enum variant value
+    Variant(Rc<EnumValue>),
+    // namespace whose members are accessed as fields, e.g. `math.sqrt`
+    Module(Rc<Module>),
+    // syntax tree created by `quote(expr)`
+    Quote(Expr),
+    // macro literal, called with quoted arguments while the macros of a program are expanded
+    Macro(
+        // Parameters
+        Vec<Identifier>,
+        // Body, returns a quote
+        Rc<BlockStatement>,
+        // Macro Env
+        Rc<RefCell<Environment>>,
+    ),
+    // generator function, a call returns an iterator which runs the body from one `yield` to the next
+    Generator(
+        // Parameters
+        Vec<Identifier>,
+        // Body, containing `yield` statements
+        Rc<BlockStatement>,
+        // Generator Env
+        Rc<RefCell<Environment>>,
+    ),
+    // lazy sequence of values, advanced by `next()` and `for ... in`
+    Iterator(Rc<RefCell<Iter>>),
     Return(Box<Object>),
     // error message
     Error(String),
 }
 
+/// immutable values are compared by their contents, functions, builtins and types by identity,
+/// a function is only equal to itself and its copies
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Object::Integer(a), Object::Integer(b)) => a == b,
+            // numbers are compared by value
+            (Object::Float(a), Object::Float(b)) => a == b,
+            (Object::Integer(a), Object::Float(b)) | (Object::Float(b), Object::Integer(a)) => *a as f64 == *b,
+            (Object::Boolean(a), Object::Boolean(b)) => a == b,
+            (Object::String(a), Object::String(b)) => a == b,
+            (Object::NULL, Object::NULL) => true,
+            (Object::Array(a), Object::Array(b)) => a == b,
+            // the order of the keys does not matter
+            (Object::Hash(a), Object::Hash(b)) => a == b,
+            (Object::Function(_, body1, env1), Object::Function(_, body2, env2)) => {
+                Rc::ptr_eq(body1, body2) && Rc::ptr_eq(env1, env2)
+            }
+            (Object::Builtin(name1, n1, f1), Object::Builtin(name2, n2, f2)) => {
+                name1 == name2 && n1 == n2 && std::ptr::fn_addr_eq(*f1, *f2)
+            }
+            (Object::Closure(name1, n1, f1), Object::Closure(name2, n2, f2)) => name1 == name2 && n1 == n2 && f1 == f2,
+            (Object::Native(a), Object::Native(b)) => a == b,
+            (Object::Method(receiver1, method1), Object::Method(receiver2, method2)) => {
+                receiver1 == receiver2 && method1 == method2
+            }
+            (Object::Struct(a), Object::Struct(b)) => a == b,
+            // instances compare their fields
+            (Object::Instance(a), Object::Instance(b)) => Rc::ptr_eq(a, b) || a == b,
+            (Object::Enum(a), Object::Enum(b)) => a == b,
+            (Object::Constructor(def1, tag1), Object::Constructor(def2, tag2)) => def1 == def2 && tag1 == tag2,
+            (Object::Variant(a), Object::Variant(b)) => a == b,
+            (Object::Module(a), Object::Module(b)) => Rc::ptr_eq(a, b),
+            (Object::Quote(a), Object::Quote(b)) => a == b,
+            (Object::Macro(_, body1, env1), Object::Macro(_, body2, env2))
+            | (Object::Generator(_, body1, env1), Object::Generator(_, body2, env2)) => {
+                Rc::ptr_eq(body1, body2) && Rc::ptr_eq(env1, env2)
+            }
+            (Object::Iterator(a), Object::Iterator(b)) => Rc::ptr_eq(a, b),
+            (Object::Return(a), Object::Return(b)) => a == b,
+            (Object::Error(a), Object::Error(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
 impl Eq for Object {}
 
+impl Object {
+    /// whether the object can be a hash key, mutable values such as instances and host values are not hashable
+    pub fn is_hashable(&self) -> bool {
+        match *self {
+            Object::Integer(_) | Object::Boolean(_) | Object::String(_) => true,
+            Object::Array(ref items) => items.iter().all(Object::is_hashable),
+            Object::Hash(ref hash) => hash.values().all(Object::is_hashable),
+            Object::Function(_, _, _) | Object::Builtin(_, _, _) | Object::Closure(_, _, _) | Object::Macro(_, _, _) => true,
+            Object::Generator(_, _, _) => true,
+            Object::Method(ref receiver, _) => receiver.is_hashable(),
+            Object::Struct(_) | Object::Enum(_) | Object::Constructor(_, _) | Object::Module(_) => true,
+            Object::Variant(ref value) => value.values.iter().all(Object::is_hashable),
+            // floats are not hashable, `NaN` is not equal to itself
+            Object::Float(_) | Object::NULL | Object::Native(_) | Object::Instance(_) | Object::Quote(_) => false,
+            Object::Iterator(_) => false,
+            Object::Return(_) | Object::Error(_) => false,
+        }
+    }
+
+    /// name of the type of the object, used to look up builtin methods
+    pub fn type_name(&self) -> &'static str {
+        match *self {
+            Object::Integer(_) => "integer",
+            Object::Float(_) => "float",
+            Object::Boolean(_) => "boolean",
+            Object::String(_) => "string",
+            Object::NULL => "null",
+            Object::Array(_) => "array",
+            Object::Function(_, _, _) => "function",
+            Object::Builtin(_, _, _) | Object::Closure(_, _, _) => "builtin",
+            Object::Native(ref native) => native.type_name,
+            Object::Method(_, _) => "method",
+            Object::Hash(_) => "hash",
+            Object::Struct(_) => "struct",
+            Object::Instance(_) => "instance",
+            Object::Enum(_) => "enum",
+            Object::Constructor(_, _) => "constructor",
+            Object::Variant(_) => "variant",
+            Object::Module(_) => "module",
+            Object::Quote(_) => "quote",
+            Object::Macro(_, _, _) => "macro",
+            Object::Generator(_, _, _) => "generator",
+            Object::Iterator(_) => "iterator",
+            Object::Return(_) => "return",
+            Object::Error(_) => "error",
+        }
+    }
+}
+
+/// named members of a namespace
+pub struct Module {
+    pub name: String,
+    pub members: HashMap<String, Object>,
+}
+
+// members are omitted, functions of a module may refer to the module
+impl Debug for Module {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut members = self.members.keys().collect::<Vec<_>>();
+        members.sort();
+        f.debug_struct("Module")
+            .field("name", &self.name)
+            .field("members", &members)
+            .finish()
+    }
+}
+
+/// definition of a struct type
+pub struct StructDef {
+    pub name: String,
+    pub fields: Vec<String>,
+    // methods by name, methods with a leading `self` parameter are bound to the instance
+    pub methods: HashMap<String, Object>,
+}
+
+// struct types are only equal to themselves
+impl PartialEq for StructDef {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+// methods are omitted, their env contains the struct itself
+impl Debug for StructDef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StructDef")
+            .field("name", &self.name)
+            .field("fields", &self.fields)
+            .field("methods", &self.methods.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// instance of a struct type, field values are in declaration order
+#[derive(Clone)]
+pub struct Instance {
+    pub def: Rc<StructDef>,
+    pub values: Vec<Object>,
+}
+
+thread_local! {
+    // pairs of instances being compared, a field may point back at its own instance
+    static COMPARING: RefCell<Vec<(*const Instance, *const Instance)>> = const { RefCell::new(Vec::new()) };
+    // instances being formatted
+    static FORMATTING: RefCell<Vec<*const Instance>> = const { RefCell::new(Vec::new()) };
+}
+
+/// run `f` with `key` pushed on `stack`, `None` if the key is already on it, i.e. for a cycle
+fn visit<K: PartialEq, T>(stack: &'static LocalKey<RefCell<Vec<K>>>, key: K, f: impl FnOnce() -> T) -> Option<T> {
+    if stack.with(|stack| stack.borrow().contains(&key)) {
+        return None;
+    }
+    stack.with(|stack| stack.borrow_mut().push(key));
+    let result = f();
+    stack.with(|stack| stack.borrow_mut().pop());
+    Some(result)
+}
+
+// a pair of instances reached again through their fields is equal, so cyclic instances compare
+// without recursing forever
+impl PartialEq for Instance {
+    fn eq(&self, other: &Self) -> bool {
+        visit(&COMPARING, (self as *const _, other as *const _), || {
+            self.def == other.def && self.values == other.values
+        }).unwrap_or(true)
+    }
+}
+
+// an instance reached again through its fields is printed as `...`
+impl Debug for Instance {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        visit(&FORMATTING, self as *const _, || {
+            f.debug_struct("Instance")
+                .field("def", &self.def)
+                .field("values", &self.values)
+                .finish()
+        }).unwrap_or_else(|| write!(f, "..."))
+    }
+}
+
+impl Instance {
+    pub fn get(&self, field: &str) -> Option<Object> {
+        let index = self.def.fields.iter().position(|f| f == field)?;
+        Some(self.values[index].clone())
+    }
+
+    /// set the value of a declared field, returns false for an unknown field
+    pub fn set(&mut self, field: &str, value: Object) -> bool {
+        match self.def.fields.iter().position(|f| f == field) {
+            Some(index) => {
+                self.values[index] = value;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// definition of an enum type, variants are in declaration order
+#[derive(Debug)]
+pub struct EnumDef {
+    pub name: String,
+    // variant names with their payload field names
+    pub variants: Vec<(String, Vec<String>)>,
+}
+
+// enum types are only equal to themselves
+impl PartialEq for EnumDef {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl EnumDef {
+    pub fn tag(&self, variant: &str) -> Option<usize> {
+        self.variants.iter().position(|(name, _)| name == variant)
+    }
+}
+
+/// value of an enum variant, `tag` is the index of the variant
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnumValue {
+    pub def: Rc<EnumDef>,
+    pub tag: usize,
+    pub values: Vec<Object>,
+}
+
+impl EnumValue {
+    pub fn variant(&self) -> &str {
+        &self.def.variants[self.tag].0
+    }
+
+    /// payload value by field name
+    pub fn get(&self, field: &str) -> Option<Object> {
+        let index = self.def.variants[self.tag].1.iter().position(|f| f == field)?;
+        Some(self.values[index].clone())
+    }
+}
+
+impl Display for EnumValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.def.name, self.variant())?;
+        if self.values.is_empty() {
+            return Ok(());
+        }
+        write!(f, "(")?;
+        for (i, value) in self.values.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", value)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl Display for Instance {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        visit(&FORMATTING, self as *const _, || {
+            write!(f, "{}", self.def.name)?;
+            if self.values.is_empty() {
+                return Ok(());
+            }
+            write!(f, " {{ ")?;
+            for (i, (field, value)) in self.def.fields.iter().zip(&self.values).enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}: {}", field, value)?;
+            }
+            write!(f, " }}")
+        }).unwrap_or_else(|| write!(f, "..."))
+    }
+}
+
 impl Display for Object {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match *self {
             Object::Integer(ref i) => write!(f, "{}", i),
+            // debug formatting keeps the fraction of integral floats, e.g. `1.0`
+            Object::Float(ref x) => write!(f, "{:?}", x),
             Object::Boolean(ref b) => {
                 if *b {
                     write!(f, "true")
@@ -60,7 +464,12 @@ impl Display for Object {
                 write!(f, "{}", fmt_string)
             }
             Object::Function(_, _, _) => write!(f, "[function]"),
-            Object::Builtin(ref name, _, _) => write!(f, "[built-in function: {}]", *name),
+            Object::Builtin(ref name, _, _) | Object::Closure(ref name, _, _) => write!(f, "[built-in function: {}]", *name),
+            Object::Native(ref native) => write!(f, "[native: {}]", native.type_name),
+            Object::Method(_, ref method) => match **method {
+                Object::Builtin(ref name, _, _) | Object::Closure(ref name, _, _) => write!(f, "[built-in method: {}]", *name),
+                ref o => write!(f, "{}", o),
+            },
 
             Object::Hash(ref hashmap) => {
                 let mut fmt_string = String::new();
@@ -74,19 +483,62 @@ impl Display for Object {
                 fmt_string.push('}');
                 write!(f, "{}", fmt_string)
             }
+            Object::Struct(ref def) => write!(f, "[struct: {}]", def.name),
+            Object::Instance(ref instance) => write!(f, "{}", instance.borrow()),
+            Object::Enum(ref def) => write!(f, "[enum: {}]", def.name),
+            Object::Constructor(ref def, tag) => write!(f, "[variant: {}.{}]", def.name, def.variants[tag].0),
+            Object::Variant(ref value) => write!(f, "{}", value),
+            Object::Module(ref module) => write!(f, "[module: {}]", module.name),
+            Object::Quote(ref expr) => write!(f, "QUOTE({})", expr),
+            Object::Macro(_, _, _) => write!(f, "[macro]"),
+            Object::Generator(_, _, _) => write!(f, "[generator]"),
+            Object::Iterator(_) => write!(f, "[iterator]"),
             Object::Return(ref o) => write!(f, "{}", *o),
             Object::Error(ref s) => write!(f, "Error: {}", s),
         }
     }
 }
 
+/// consistent with `PartialEq`, only hashable objects are used as hash keys
 impl Hash for Object {
     fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
         match *self {
             Object::Integer(ref i) => i.hash(state),
             Object::Boolean(ref b) => b.hash(state),
             Object::String(ref s) => s.hash(state),
-            _ => "".hash(state),
+            Object::Array(ref items) => items.hash(state),
+            Object::Hash(ref hash) => {
+                // independent of the order of the keys, like the equality
+                let sum = hash.iter().fold(0u64, |sum, pair| {
+                    let mut hasher = DefaultHasher::new();
+                    pair.hash(&mut hasher);
+                    sum.wrapping_add(hasher.finish())
+                });
+                sum.hash(state)
+            }
+            Object::Function(_, ref body, ref env)
+            | Object::Macro(_, ref body, ref env)
+            | Object::Generator(_, ref body, ref env) => {
+                Rc::as_ptr(body).hash(state);
+                Rc::as_ptr(env).hash(state);
+            }
+            Object::Builtin(ref name, _, _) | Object::Closure(ref name, _, _) => name.hash(state),
+            Object::Method(ref receiver, ref method) => {
+                receiver.hash(state);
+                method.hash(state);
+            }
+            Object::Struct(ref def) => Rc::as_ptr(def).hash(state),
+            Object::Module(ref module) => Rc::as_ptr(module).hash(state),
+            Object::Enum(ref def) | Object::Constructor(ref def, _) => Rc::as_ptr(def).hash(state),
+            Object::Variant(ref value) => {
+                Rc::as_ptr(&value.def).hash(state);
+                value.tag.hash(state);
+                value.values.hash(state);
+            }
+            // instances are not hashable, their fields are not hashed so a cycle through them ends here
+            Object::Instance(_) => {}
+            _ => {}
         }
     }
 }
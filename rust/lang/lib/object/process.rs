@@ -0,0 +1,47 @@
+use std::env;
+
+use crate::ast::Identifier;
+use crate::evaluator::error::RuntimeError;
+use crate::evaluator::{EvalResult, Evaluator};
+use crate::object::builtins::{add_builtin, builtin_error};
+use crate::object::objects::Object;
+
+/// builtins for scripts run from the command line
+pub fn get_builtins() -> Vec<(Identifier, Object)> {
+    vec![
+        add_builtin("args", 0, bargs_fn),
+        add_builtin("env", 1, benv_fn),
+        add_builtin("exit", 1, bexit_fn),
+    ]
+}
+
+/// the arguments given to the script, without the script itself
+fn bargs_fn(evaluator: &mut Evaluator, _: Vec<Object>) -> EvalResult {
+    Ok(Object::Array(evaluator.args().iter().map(|arg| Object::String(arg.clone())).collect()))
+}
+
+/// `env(name)`, `null` if the variable is not set or not unicode
+fn benv_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let name = match args.into_iter().next() {
+        Some(Object::String(name)) => name,
+        _ => return Err(builtin_error("invalid arguments for env")),
+    };
+    if !evaluator.capabilities().env {
+        return Err(builtin_error("reading environment variables is not allowed"));
+    }
+    match env::var(&name) {
+        Ok(value) => Ok(Object::String(value)),
+        Err(_) => Ok(Object::NULL),
+    }
+}
+
+/// `exit(code)` stops the evaluation, `try catch` does not catch it but `finally` blocks still run
+fn bexit_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    match args.into_iter().next() {
+        Some(Object::Integer(code)) => match i32::try_from(code) {
+            Ok(code) => Err(RuntimeError::Exit(code).into()),
+            Err(_) => Err(builtin_error(&format!("exit status {} is out of range", code))),
+        },
+        _ => Err(builtin_error("invalid arguments for exit")),
+    }
+}
@@ -0,0 +1,78 @@
+use crate::ast::Identifier;
+use crate::evaluator::{EvalResult, Evaluator};
+use crate::object::builtins::{add_builtin, builtin_error};
+use crate::object::objects::Object;
+
+/// random builtins, they draw from the generator of the evaluator
+pub fn get_builtins() -> Vec<(Identifier, Object)> {
+    vec![
+        add_builtin("random", 0, brandom_fn),
+        add_builtin("random_int", 2, brandom_int_fn),
+        add_builtin("shuffle", 1, bshuffle_fn),
+        add_builtin("choice", 1, bchoice_fn),
+        add_builtin("seed", 1, bseed_fn),
+    ]
+}
+
+/// uniform float in `[0, 1)`
+fn brandom_fn(evaluator: &mut Evaluator, _: Vec<Object>) -> EvalResult {
+    Ok(Object::Float(evaluator.rng().next_f64()))
+}
+
+/// `random_int(a, b)`, uniform integer in `[a, b]`
+fn brandom_int_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let mut args = args.into_iter();
+    match (args.next(), args.next()) {
+        (Some(Object::Integer(a)), Some(Object::Integer(b))) if a <= b => {
+            // the width of the range wraps to 0 when it covers every integer
+            let width = (b as u64).wrapping_sub(a as u64).wrapping_add(1);
+            let offset = match width {
+                0 => evaluator.rng().next_u64(),
+                width => evaluator.rng().below(width),
+            };
+            Ok(Object::Integer((a as u64).wrapping_add(offset) as i64))
+        }
+        (Some(Object::Integer(a)), Some(Object::Integer(b))) => {
+            Err(builtin_error(&format!("empty range for random_int: {} > {}", a, b)))
+        }
+        _ => Err(builtin_error("invalid arguments for random_int")),
+    }
+}
+
+/// a shuffled copy of an array
+fn bshuffle_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    match args.into_iter().next() {
+        Some(Object::Array(mut items)) => {
+            // Fisher-Yates
+            for i in (1..items.len()).rev() {
+                let j = evaluator.rng().below(i as u64 + 1) as usize;
+                items.swap(i, j);
+            }
+            Ok(Object::Array(items))
+        }
+        _ => Err(builtin_error("invalid arguments for shuffle")),
+    }
+}
+
+/// a random item of an array
+fn bchoice_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    match args.into_iter().next() {
+        Some(Object::Array(items)) if items.is_empty() => Err(builtin_error("choice from an empty array")),
+        Some(Object::Array(mut items)) => {
+            let i = evaluator.rng().below(items.len() as u64) as usize;
+            Ok(items.swap_remove(i))
+        }
+        _ => Err(builtin_error("invalid arguments for choice")),
+    }
+}
+
+/// `seed(n)` makes the following random numbers reproducible
+fn bseed_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    match args.into_iter().next() {
+        Some(Object::Integer(seed)) => {
+            evaluator.seed(seed as u64);
+            Ok(Object::NULL)
+        }
+        _ => Err(builtin_error("invalid arguments for seed")),
+    }
+}
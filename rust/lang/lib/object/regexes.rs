@@ -0,0 +1,84 @@
+use std::rc::Rc;
+
+use regex::{Captures, Regex};
+
+use crate::ast::Identifier;
+use crate::evaluator::error::EvalError;
+use crate::evaluator::{EvalResult, Evaluator};
+use crate::object::builtins::{add_builtin, builtin_error};
+use crate::object::objects::Object;
+
+/// regular expression builtins, the pattern is the second argument and uses the syntax of the `regex` crate,
+/// compiled patterns are cached by the evaluator, backslashes are doubled in string literals, e.g. `"\\d+"`
+pub fn get_builtins() -> Vec<(Identifier, Object)> {
+    vec![
+        add_builtin("regex_match", 2, bregex_match_fn),
+        add_builtin("regex_find_all", 2, bregex_find_all_fn),
+        add_builtin("regex_replace", 3, bregex_replace_fn),
+        add_builtin("regex_split", 2, bregex_split_fn),
+    ]
+}
+
+/// the string arguments and the compiled pattern
+fn regex_args<const N: usize>(evaluator: &mut Evaluator, args: Vec<Object>, name: &str) -> Result<([String; N], Rc<Regex>), EvalError> {
+    let invalid = || builtin_error(&format!("invalid arguments for {}", name));
+    let mut strings = args.into_iter()
+        .map(|arg| match arg {
+            Object::String(s) => Ok(s),
+            _ => Err(invalid()),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if strings.len() < 2 {
+        return Err(invalid());
+    }
+    let pattern = strings.remove(1);
+    let regex = evaluator.regexes().get(&pattern).map_err(|err| builtin_error(&format!("invalid regex: {}", err)))?;
+    Ok((strings.try_into().map_err(|_| invalid())?, regex))
+}
+
+fn group(m: Option<regex::Match>) -> Object {
+    m.map_or(Object::NULL, |m| Object::String(m.as_str().to_string()))
+}
+
+/// a hash of the named groups if the pattern has any,
+/// otherwise an array of the whole match and the groups, groups which did not take part are `null`
+fn captures_object(regex: &Regex, captures: &Captures) -> Object {
+    if regex.capture_names().flatten().next().is_some() {
+        Object::Hash(regex.capture_names()
+            .flatten()
+            .map(|name| (Object::String(name.to_string()), group(captures.name(name))))
+            .collect())
+    } else {
+        Object::Array(captures.iter().map(group).collect())
+    }
+}
+
+/// `regex_match(s, pattern)`, the captures of the first match or `null`
+fn bregex_match_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let ([s], regex) = regex_args(evaluator, args, "regex_match")?;
+    Ok(regex.captures(&s).map_or(Object::NULL, |captures| captures_object(&regex, &captures)))
+}
+
+/// `regex_find_all(s, pattern)`, the matched strings, or their captures if the pattern has groups
+fn bregex_find_all_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let ([s], regex) = regex_args(evaluator, args, "regex_find_all")?;
+    let matches = if regex.captures_len() > 1 {
+        regex.captures_iter(&s).map(|captures| captures_object(&regex, &captures)).collect()
+    } else {
+        regex.find_iter(&s).map(|m| Object::String(m.as_str().to_string())).collect()
+    };
+    Ok(Object::Array(matches))
+}
+
+/// `regex_replace(s, pattern, replacement)` replaces all matches,
+/// `$1` and `${name}` in the replacement are groups of the match and `$$` is a dollar sign
+fn bregex_replace_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let ([s, replacement], regex) = regex_args(evaluator, args, "regex_replace")?;
+    Ok(Object::String(regex.replace_all(&s, replacement.as_str()).into_owned()))
+}
+
+/// `regex_split(s, pattern)`, the parts between the matches
+fn bregex_split_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let ([s], regex) = regex_args(evaluator, args, "regex_split")?;
+    Ok(Object::Array(regex.split(&s).map(|part| Object::String(part.to_string())).collect()))
+}
@@ -0,0 +1,242 @@
+use crate::ast::Identifier;
+use crate::evaluator::error::EvalError;
+use crate::evaluator::{EvalResult, Evaluator};
+use crate::object::builtins::{add_builtin, add_method, builtin_error};
+use crate::object::objects::Object;
+
+/// string builtins, indices and lengths count chars
+pub fn get_builtins() -> Vec<(Identifier, Object)> {
+    vec![
+        add_builtin("split", 2, bsplit_fn),
+        add_builtin("join", 2, bjoin_fn),
+        add_builtin("trim", 1, btrim_fn),
+        add_builtin("upper", 1, bupper_fn),
+        add_builtin("lower", 1, blower_fn),
+        add_builtin("replace", 3, breplace_fn),
+        add_builtin("contains", 2, bcontains_fn),
+        add_builtin("starts_with", 2, bstarts_with_fn),
+        add_builtin("ends_with", 2, bends_with_fn),
+        add_builtin("index_of", 2, bindex_of_fn),
+        add_builtin("repeat", 2, brepeat_fn),
+        add_builtin("chars", 1, bchars_fn),
+        add_builtin("bytes", 1, bbytes_fn),
+        add_builtin("format", 2, bformat_fn),
+        add_builtin("sprintf", 2, bsprintf_fn),
+        add_builtin("parse_int", 1, bparse_int_fn),
+    ]
+}
+
+/// the string builtins as methods of strings, `join` is a method of arrays
+pub fn get_methods() -> Vec<(&'static str, Identifier, Object)> {
+    vec![
+        add_method("string", "split", 2, bsplit_fn),
+        add_method("array", "join", 2, bjoin_fn),
+        add_method("string", "trim", 1, btrim_fn),
+        add_method("string", "upper", 1, bupper_fn),
+        add_method("string", "lower", 1, blower_fn),
+        add_method("string", "replace", 3, breplace_fn),
+        add_method("string", "contains", 2, bcontains_fn),
+        add_method("string", "starts_with", 2, bstarts_with_fn),
+        add_method("string", "ends_with", 2, bends_with_fn),
+        add_method("string", "index_of", 2, bindex_of_fn),
+        add_method("string", "repeat", 2, brepeat_fn),
+        add_method("string", "chars", 1, bchars_fn),
+        add_method("string", "bytes", 1, bbytes_fn),
+        add_method("string", "format", 2, bformat_fn),
+        add_method("string", "sprintf", 2, bsprintf_fn),
+        add_method("string", "parse_int", 1, bparse_int_fn),
+    ]
+}
+
+fn invalid_arguments(name: &str) -> EvalError {
+    builtin_error(&format!("invalid arguments for {}", name))
+}
+
+/// arguments of builtins which only take strings
+fn strings<const N: usize>(args: Vec<Object>, name: &str) -> Result<[String; N], EvalError> {
+    let strings = args.into_iter()
+        .map(|arg| match arg {
+            Object::String(s) => Ok(s),
+            _ => Err(invalid_arguments(name)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    strings.try_into().map_err(|_| invalid_arguments(name))
+}
+
+fn string(s: String) -> EvalResult {
+    Ok(Object::String(s))
+}
+
+/// `split(s, sep)`, an empty separator splits into chars
+fn bsplit_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let [s, sep] = strings(args, "split")?;
+    let parts: Vec<Object> = if sep.is_empty() {
+        s.chars().map(|c| Object::String(c.to_string())).collect()
+    } else {
+        s.split(sep.as_str()).map(|part| Object::String(part.to_string())).collect()
+    };
+    Ok(Object::Array(parts))
+}
+
+/// `join(arr, sep)`, the items are displayed
+fn bjoin_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let mut args = args.into_iter();
+    match (args.next(), args.next()) {
+        (Some(Object::Array(arr)), Some(Object::String(sep))) => {
+            string(arr.iter().map(ToString::to_string).collect::<Vec<_>>().join(&sep))
+        }
+        _ => Err(invalid_arguments("join")),
+    }
+}
+
+fn btrim_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let [s] = strings(args, "trim")?;
+    string(s.trim().to_string())
+}
+
+fn bupper_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let [s] = strings(args, "upper")?;
+    string(s.to_uppercase())
+}
+
+fn blower_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let [s] = strings(args, "lower")?;
+    string(s.to_lowercase())
+}
+
+/// `replace(s, from, to)` replaces all occurrences
+fn breplace_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let [s, from, to] = strings(args, "replace")?;
+    if from.is_empty() {
+        return Err(builtin_error("replace of an empty string"));
+    }
+    string(s.replace(&from, &to))
+}
+
+fn bcontains_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let [s, sub] = strings(args, "contains")?;
+    Ok(Object::Boolean(s.contains(&sub)))
+}
+
+fn bstarts_with_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let [s, prefix] = strings(args, "starts_with")?;
+    Ok(Object::Boolean(s.starts_with(&prefix)))
+}
+
+fn bends_with_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let [s, suffix] = strings(args, "ends_with")?;
+    Ok(Object::Boolean(s.ends_with(&suffix)))
+}
+
+/// char index of the first occurrence, `-1` if there is none
+fn bindex_of_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let [s, sub] = strings(args, "index_of")?;
+    let index = match s.find(&sub) {
+        Some(byte) => s[..byte].chars().count() as i64,
+        None => -1,
+    };
+    Ok(Object::Integer(index))
+}
+
+fn brepeat_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let mut args = args.into_iter();
+    match (args.next(), args.next()) {
+        (Some(Object::String(s)), Some(Object::Integer(n))) if n >= 0 => {
+            let len = s.len().checked_mul(n as usize).ok_or_else(|| builtin_error("repeat result is too large"))?;
+            evaluator.reserve(len)?;
+            string(s.repeat(n as usize))
+        }
+        _ => Err(invalid_arguments("repeat")),
+    }
+}
+
+fn bchars_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let [s] = strings(args, "chars")?;
+    Ok(Object::Array(s.chars().map(|c| Object::String(c.to_string())).collect()))
+}
+
+/// utf-8 bytes as integers
+fn bbytes_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let [s] = strings(args, "bytes")?;
+    Ok(Object::Array(s.bytes().map(|b| Object::Integer(b as i64)).collect()))
+}
+
+/// format string and its array of arguments
+fn format_args(args: Vec<Object>, name: &str) -> Result<(String, Vec<Object>), EvalError> {
+    let mut args = args.into_iter();
+    match (args.next(), args.next()) {
+        (Some(Object::String(fmt)), Some(Object::Array(values))) => Ok((fmt, values)),
+        _ => Err(invalid_arguments(name)),
+    }
+}
+
+/// the next argument of a format string
+fn next_value(values: &mut impl Iterator<Item = Object>, name: &str) -> Result<Object, EvalError> {
+    values.next().ok_or_else(|| builtin_error(&format!("too few arguments for {}", name)))
+}
+
+/// the remaining arguments of a format string
+fn no_values_left(mut values: impl Iterator<Item = Object>, name: &str) -> Result<(), EvalError> {
+    match values.next() {
+        None => Ok(()),
+        Some(_) => Err(builtin_error(&format!("too many arguments for {}", name))),
+    }
+}
+
+/// `format("{} + {}", [1, 2])`, `{}` displays the next argument, `{{` and `}}` are braces
+fn bformat_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let (fmt, values) = format_args(args, "format")?;
+    let mut values = values.into_iter();
+    let mut out = String::new();
+    let mut chars = fmt.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                out.push(c);
+            }
+            ('{', Some('}')) => {
+                chars.next();
+                out.push_str(&next_value(&mut values, "format")?.to_string());
+            }
+            ('{', _) | ('}', _) => return Err(builtin_error(&format!("unmatched {} in format string", c))),
+            _ => out.push(c),
+        }
+    }
+    no_values_left(values, "format")?;
+    string(out)
+}
+
+/// `sprintf("%s is %d", ["x", 1])`, `%s` displays the next argument, `%d` requires an integer, `%%` is a percent sign
+fn bsprintf_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let (fmt, values) = format_args(args, "sprintf")?;
+    let mut values = values.into_iter();
+    let mut out = String::new();
+    let mut chars = fmt.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => out.push('%'),
+            Some('s') => out.push_str(&next_value(&mut values, "sprintf")?.to_string()),
+            Some('d') => match next_value(&mut values, "sprintf")? {
+                Object::Integer(i) => out.push_str(&i.to_string()),
+                o => return Err(builtin_error(&format!("{} is not an integer", o))),
+            },
+            Some(c) => return Err(builtin_error(&format!("unknown format %{}", c))),
+            None => return Err(builtin_error("unterminated format %")),
+        }
+    }
+    no_values_left(values, "sprintf")?;
+    string(out)
+}
+
+/// decimal integer with an optional sign, surrounding whitespace is ignored
+fn bparse_int_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let [s] = strings(args, "parse_int")?;
+    s.trim().parse::<i64>()
+        .map(Object::Integer)
+        .map_err(|_| builtin_error(&format!("{:?} is not an integer", s)))
+}
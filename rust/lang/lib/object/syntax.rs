@@ -0,0 +1,220 @@
+use crate::ast::{BlockStatement, Expr, Identifier, Infix, Literal, Pattern, Prefix, Stmt};
+use crate::evaluator::error::RuntimeError;
+use crate::evaluator::{EvalResult, Evaluator};
+use crate::object::builtins::add_builtin;
+use crate::object::convert::ToObject;
+use crate::object::objects::Object;
+use crate::parser::Parser;
+
+/// builtins which work on source code
+pub fn get_builtins() -> Vec<(Identifier, Object)> {
+    vec![
+        add_builtin("eval", 1, beval_fn),
+        add_builtin("parse", 1, bparse_fn),
+    ]
+}
+
+fn source_arg(args: Vec<Object>, name: &str) -> Result<String, RuntimeError> {
+    match args.into_iter().next() {
+        Some(Object::String(source)) => Ok(source),
+        _ => Err(RuntimeError::Builtin(format!("invalid arguments for {}", name))),
+    }
+}
+
+/// `eval(source)` evaluates the source in the env of the caller, `let` bindings stay in that env
+fn beval_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let source = source_arg(args, "eval")?;
+    evaluator.eval_source(&source)
+}
+
+/// `parse(source)`, the statements of the source as hashes with a `type` field
+fn bparse_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let source = source_arg(args, "parse")?;
+    match Parser::parse_source(&source) {
+        Ok(program) => Ok(program.to_object()),
+        Err(span) => Err(RuntimeError::Syntax(format!("syntax error at {}", span)).into()),
+    }
+}
+
+/// a node of the syntax tree as a hash
+fn node(node_type: &str, fields: Vec<(&str, Object)>) -> Object {
+    Object::Hash(
+        Some(("type", Object::String(node_type.to_string())))
+            .into_iter()
+            .chain(fields)
+            .map(|(name, value)| (Object::String(name.to_string()), value))
+            .collect(),
+    )
+}
+
+fn name(Identifier(ref name): &Identifier) -> Object {
+    Object::String(name.clone())
+}
+
+fn names(identifiers: &[Identifier]) -> Object {
+    Object::Array(identifiers.iter().map(name).collect())
+}
+
+fn block(statements: &BlockStatement) -> Object {
+    Object::Array(statements.iter().map(ToObject::to_object).collect())
+}
+
+fn optional<T>(value: &Option<T>, f: impl Fn(&T) -> Object) -> Object {
+    value.as_ref().map_or(Object::NULL, f)
+}
+
+impl ToObject for Stmt {
+    fn to_object(&self) -> Object {
+        match *self {
+            Stmt::LetStmt(ref ident, ref value) => node("let", vec![("name", name(ident)), ("value", value.to_object())]),
+            Stmt::ReturnStmt(ref value) => node("return", vec![("value", value.to_object())]),
+            Stmt::ExprStmt(ref expr) => node("expr", vec![("expr", expr.to_object())]),
+            Stmt::ThrowStmt(ref value) => node("throw", vec![("value", value.to_object())]),
+            Stmt::StructStmt { name: ref struct_name, ref fields, ref methods } => node("struct", vec![
+                ("name", name(struct_name)),
+                ("fields", names(fields)),
+                ("methods", Object::Array(methods.iter()
+                    .map(|(method, function)| node("method", vec![("name", name(method)), ("function", function.to_object())]))
+                    .collect())),
+            ]),
+            Stmt::EnumStmt { name: ref enum_name, ref variants } => node("enum", vec![
+                ("name", name(enum_name)),
+                ("variants", Object::Array(variants.iter()
+                    .map(|(variant, fields)| node("variant", vec![("name", name(variant)), ("fields", names(fields))]))
+                    .collect())),
+            ]),
+            Stmt::AssignStmt(ref target, ref value) => node("assign", vec![("target", target.to_object()), ("value", value.to_object())]),
+            Stmt::ImportStmt(ref path, ref alias) => node("import", vec![("path", Object::String(path.clone())), ("alias", name(alias))]),
+            Stmt::ExportStmt(ref stmt) => node("export", vec![("declaration", stmt.to_object())]),
+            Stmt::ForStmt { ref ident, ref iterable, ref body } => node("for", vec![
+                ("name", name(ident)),
+                ("iterable", iterable.to_object()),
+                ("body", block(body)),
+            ]),
+            Stmt::YieldStmt(ref value) => node("yield", vec![("value", value.to_object())]),
+        }
+    }
+}
+
+impl ToObject for Expr {
+    fn to_object(&self) -> Object {
+        match *self {
+            Expr::IdentExpr(ref ident) => node("ident", vec![("name", name(ident))]),
+            Expr::LiteralExpr(ref literal) => node("literal", vec![("value", literal.to_object())]),
+            Expr::PrefixExpr(ref prefix, ref right) => node("prefix", vec![
+                ("operator", Object::String(prefix_operator(prefix).to_string())),
+                ("right", right.to_object()),
+            ]),
+            Expr::InfixExpr(ref infix, ref left, ref right) => node("infix", vec![
+                ("operator", Object::String(infix_operator(infix).to_string())),
+                ("left", left.to_object()),
+                ("right", right.to_object()),
+            ]),
+            Expr::IfExpr { ref cond, ref consequence, ref alternative } => node("if", vec![
+                ("condition", cond.to_object()),
+                ("consequence", block(consequence)),
+                ("alternative", optional(alternative, block)),
+            ]),
+            Expr::TryExpr { ref body, ref catch, ref finally } => node("try", vec![
+                ("body", block(body)),
+                ("catch", optional(catch, |(ident, body)| node("catch", vec![("name", name(ident)), ("body", block(body))]))),
+                ("finally", optional(finally, block)),
+            ]),
+            Expr::MatchExpr { ref subject, ref arms } => node("match", vec![
+                ("subject", subject.to_object()),
+                ("arms", Object::Array(arms.iter()
+                    .map(|(pattern, body)| node("arm", vec![("pattern", pattern.to_object()), ("body", block(body))]))
+                    .collect())),
+            ]),
+            Expr::FnExpr { ref parameters, ref body } => node("function", vec![
+                ("parameters", names(parameters)),
+                ("body", block(body)),
+            ]),
+            Expr::GeneratorExpr { ref parameters, ref body } => node("generator", vec![
+                ("parameters", names(parameters)),
+                ("body", block(body)),
+            ]),
+            Expr::MacroExpr { ref parameters, ref body } => node("macro", vec![
+                ("parameters", names(parameters)),
+                ("body", block(body)),
+            ]),
+            Expr::ArrayExpr(ref items) => node("array", vec![
+                ("items", Object::Array(items.iter().map(ToObject::to_object).collect())),
+            ]),
+            Expr::HashExpr(ref pairs) => node("hash", vec![
+                ("pairs", Object::Array(pairs.iter()
+                    .map(|(k, v)| Object::Array(vec![k.to_object(), v.to_object()]))
+                    .collect())),
+            ]),
+            Expr::CallExpr { ref function, ref arguments, span } => node("call", vec![
+                ("function", function.to_object()),
+                ("arguments", Object::Array(arguments.iter().map(ToObject::to_object).collect())),
+                ("line", Object::Integer(span.line as i64)),
+                ("column", Object::Integer(span.column as i64)),
+            ]),
+            Expr::IndexExpr { ref left, ref index } => node("index", vec![
+                ("left", left.to_object()),
+                ("index", index.to_object()),
+            ]),
+            Expr::FieldExpr { ref object, ref field } => node("field", vec![
+                ("object", object.to_object()),
+                ("field", name(field)),
+            ]),
+        }
+    }
+}
+
+impl ToObject for Pattern {
+    fn to_object(&self) -> Object {
+        match *self {
+            Pattern::Wildcard => node("wildcard", vec![]),
+            Pattern::Binding(ref ident) => node("binding", vec![("name", name(ident))]),
+            Pattern::Literal(ref literal) => node("literal", vec![("value", literal.to_object())]),
+            Pattern::Struct(ref struct_name, ref fields) => node("struct", vec![
+                ("name", name(struct_name)),
+                ("fields", Object::Array(fields.iter()
+                    .map(|(field, pattern)| node("field", vec![("name", name(field)), ("pattern", pattern.to_object())]))
+                    .collect())),
+            ]),
+            Pattern::Variant(ref enum_name, ref variant, ref patterns) => node("variant", vec![
+                ("enum", optional(enum_name, name)),
+                ("name", name(variant)),
+                ("patterns", Object::Array(patterns.iter().map(ToObject::to_object).collect())),
+            ]),
+        }
+    }
+}
+
+impl ToObject for Literal {
+    fn to_object(&self) -> Object {
+        match *self {
+            Literal::IntLiteral(i) => Object::Integer(i),
+            Literal::FloatLiteral(x) => Object::Float(x.0),
+            Literal::BoolLiteral(b) => Object::Boolean(b),
+            Literal::StringLiteral(ref s) => Object::String(s.clone()),
+        }
+    }
+}
+
+fn prefix_operator(prefix: &Prefix) -> &'static str {
+    match *prefix {
+        Prefix::Plus => "+",
+        Prefix::Minus => "-",
+        Prefix::Not => "!",
+    }
+}
+
+fn infix_operator(infix: &Infix) -> &'static str {
+    match *infix {
+        Infix::Plus => "+",
+        Infix::Minus => "-",
+        Infix::Divide => "/",
+        Infix::Multiply => "*",
+        Infix::Equal => "==",
+        Infix::NotEqual => "!=",
+        Infix::GreaterThanEqual => ">=",
+        Infix::LessThanEqual => "<=",
+        Infix::GreaterThan => ">",
+        Infix::LessThan => "<",
+    }
+}
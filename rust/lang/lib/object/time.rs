@@ -0,0 +1,151 @@
+use crate::ast::Identifier;
+use crate::evaluator::{EvalResult, Evaluator};
+use crate::object::builtins::{add_builtin, builtin_error};
+use crate::object::objects::Object;
+
+const MS_PER_DAY: i64 = 86_400_000;
+
+/// time builtins, timestamps are milliseconds since the unix epoch in UTC,
+/// they read the clock of the evaluator
+pub fn get_builtins() -> Vec<(Identifier, Object)> {
+    vec![
+        add_builtin("now", 0, bnow_fn),
+        add_builtin("clock", 0, bclock_fn),
+        add_builtin("sleep", 1, bsleep_fn),
+        add_builtin("format_time", 2, bformat_time_fn),
+        add_builtin("parse_time", 2, bparse_time_fn),
+    ]
+}
+
+fn bnow_fn(evaluator: &mut Evaluator, _: Vec<Object>) -> EvalResult {
+    Ok(Object::Integer(evaluator.clock().now()))
+}
+
+/// monotonic milliseconds as a float, for measuring durations
+fn bclock_fn(evaluator: &mut Evaluator, _: Vec<Object>) -> EvalResult {
+    Ok(Object::Float(evaluator.clock().monotonic()))
+}
+
+/// `sleep(ms)`, a sleep is cut short by the deadline of the evaluation
+fn bsleep_fn(evaluator: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    match args.into_iter().next() {
+        Some(Object::Integer(ms)) if ms >= 0 => {
+            evaluator.sleep(ms as u64)?;
+            Ok(Object::NULL)
+        }
+        _ => Err(builtin_error("invalid arguments for sleep")),
+    }
+}
+
+/// days since the epoch of a date of the proleptic gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// date of the proleptic gregorian calendar of days since the epoch
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    (if month <= 2 { year_of_era + era * 400 + 1 } else { year_of_era + era * 400 }, month, day)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// `format_time(ts, "%Y-%m-%d %H:%M:%S.%L")`, `%L` are milliseconds and `%%` is a percent sign
+fn bformat_time_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let mut args = args.into_iter();
+    let (ts, fmt) = match (args.next(), args.next()) {
+        (Some(Object::Integer(ts)), Some(Object::String(fmt))) => (ts, fmt),
+        _ => return Err(builtin_error("invalid arguments for format_time")),
+    };
+    let (year, month, day) = civil_from_days(ts.div_euclid(MS_PER_DAY));
+    let ms = ts.rem_euclid(MS_PER_DAY);
+    let mut out = String::new();
+    let mut chars = fmt.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => out.push_str(&format!("{:04}", year)),
+            Some('m') => out.push_str(&format!("{:02}", month)),
+            Some('d') => out.push_str(&format!("{:02}", day)),
+            Some('H') => out.push_str(&format!("{:02}", ms / 3_600_000)),
+            Some('M') => out.push_str(&format!("{:02}", ms / 60_000 % 60)),
+            Some('S') => out.push_str(&format!("{:02}", ms / 1000 % 60)),
+            Some('L') => out.push_str(&format!("{:03}", ms % 1000)),
+            Some('%') => out.push('%'),
+            Some(c) => return Err(builtin_error(&format!("unknown format %{}", c))),
+            None => return Err(builtin_error("unterminated format %")),
+        }
+    }
+    Ok(Object::String(out))
+}
+
+/// `parse_time(s, fmt)` with the formats of `format_time`, fields which are not in the format
+/// are the ones of the epoch
+fn bparse_time_fn(_: &mut Evaluator, args: Vec<Object>) -> EvalResult {
+    let mut args = args.into_iter();
+    let (s, fmt) = match (args.next(), args.next()) {
+        (Some(Object::String(s)), Some(Object::String(fmt))) => (s, fmt),
+        _ => return Err(builtin_error("invalid arguments for parse_time")),
+    };
+    let mismatch = || builtin_error(&format!("{:?} does not match the format {:?}", s, fmt));
+    let mut fields = [1970, 1, 1, 0, 0, 0, 0];
+    let mut input = s.as_str();
+    let mut chars = fmt.chars();
+    while let Some(c) = chars.next() {
+        let (field, width) = match c {
+            '%' => match chars.next() {
+                Some('Y') => (0, 4),
+                Some('m') => (1, 2),
+                Some('d') => (2, 2),
+                Some('H') => (3, 2),
+                Some('M') => (4, 2),
+                Some('S') => (5, 2),
+                Some('L') => (6, 3),
+                Some('%') => {
+                    input = input.strip_prefix('%').ok_or_else(mismatch)?;
+                    continue;
+                }
+                Some(c) => return Err(builtin_error(&format!("unknown format %{}", c))),
+                None => return Err(builtin_error("unterminated format %")),
+            },
+            c => {
+                input = input.strip_prefix(c).ok_or_else(mismatch)?;
+                continue;
+            }
+        };
+        let digits = input.get(..width).filter(|digits| digits.bytes().all(|b| b.is_ascii_digit()));
+        fields[field] = digits.ok_or_else(mismatch)?.parse().map_err(|_| mismatch())?;
+        input = &input[width..];
+    }
+    if !input.is_empty() {
+        return Err(mismatch());
+    }
+    let [year, month, day, hour, minute, second, ms] = fields;
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) || hour > 23 || minute > 59 || second > 59 {
+        return Err(builtin_error(&format!("{:?} is not a valid time", s)));
+    }
+    let days = days_from_civil(year, month, day);
+    Ok(Object::Integer(days * MS_PER_DAY + ((hour * 60 + minute) * 60 + second) * 1000 + ms))
+}
@@ -84,6 +84,22 @@ mod program_parse {
     verify_token!(verify_function, Token::Function);
     verify_token!(verify_let, Token::Let);
     verify_token!(verify_return, Token::Return);
+    verify_token!(verify_try, Token::Try);
+    verify_token!(verify_catch, Token::Catch);
+    verify_token!(verify_finally, Token::Finally);
+    verify_token!(verify_throw, Token::Throw);
+    verify_token!(verify_struct, Token::Struct);
+    verify_token!(verify_match, Token::Match);
+    verify_token!(verify_enum, Token::Enum);
+    verify_token!(verify_macro, Token::Macro);
+    verify_token!(verify_import, Token::Import);
+    verify_token!(verify_export, Token::Export);
+    verify_token!(verify_as, Token::As);
+    verify_token!(verify_for, Token::For);
+    verify_token!(verify_in, Token::In);
+    verify_token!(verify_yield, Token::Yield);
+    verify_token!(verify_fat_arrow, Token::FatArrow);
+    verify_token!(verify_dot, Token::Dot);
     verify_token!(verify_comma, Token::Comma);
     verify_token!(verify_colon, Token::Colon);
     verify_token!(verify_semicolon, Token::SemiColon);
@@ -106,10 +122,18 @@ mod program_parse {
         literal_parse->Literal,
         (
             (Token::IntLiteral => Literal::IntLiteral),
+            (Token::FloatLiteral => Literal::FloatLiteral),
             (Token::StringLiteral => Literal::StringLiteral),
             (Token::BoolLiteral => Literal::BoolLiteral)
         )
     }
+    /// 将字符串 Token 转换为 String
+    map_token! {
+        string_parse->String,
+        (
+            (Token::StringLiteral => String::from)
+        )
+    }
 
     /// 表达式解析
     mod expr_parse {
@@ -118,13 +142,13 @@ mod program_parse {
         use std::cell::Cell;
 
         /// 解析 标识符列表
-        mod ident_list_parse {
+        pub mod ident_list_parse {
             use super::*;
 
             /// 空标识符列表解析
             map_parser! {
                 empty_idents->Vec<Identifier>,
-                ()=>||vec![]
+                ()=>Vec::new
             }
 
             /// 标识符列表解析
@@ -153,7 +177,7 @@ mod program_parse {
             /// 空表达式列表解析
             map_parser! {
                 empty_exprs->Vec<Expr>,
-                ()=>||vec![]
+                ()=>Vec::new
             }
 
             /// 表达式列表解析
@@ -181,23 +205,23 @@ mod program_parse {
 
             /// hash pair 解析
             map_parser! {
-                parse_hash_pair->(Literal,Expr),
+                parse_hash_pair->(Expr,Expr),
                 (
-                    literal_parse,
+                    parse_expr,
                     verify_colon,
                     parse_expr
-                )=>|(l,_,e)|(l,e)
+                )=>|(k,_,e)|(k,e)
             }
 
             /// 空 hash pair 列表解析
             map_parser! {
-                empty_pairs->Vec<(Literal, Expr)>,
-                ()=>||vec![]
+                empty_pairs->Vec<(Expr, Expr)>,
+                ()=>Vec::new
             }
 
             /// hash pair 列表解析
             map_parser! {
-                parse_pairs->Vec<(Literal, Expr)>,
+                parse_pairs->Vec<(Expr, Expr)>,
                 (
                     parse_hash_pair,
                     many0(preceded(verify_comma, parse_hash_pair))
@@ -206,7 +230,7 @@ mod program_parse {
 
             /// hashPair 列表解析
             parsers! {
-                pub parse_pair_list->Vec<(Literal, Expr)>,
+                pub parse_pair_list->Vec<(Expr, Expr)>,
                 (
                     parse_pairs,
                     empty_pairs
@@ -217,7 +241,7 @@ mod program_parse {
         /// 解析 语句块
         /// 形如`{ stmt,stmt,... }`
         map_parser! {
-            parse_block_stmt->BlockStatement,
+            pub parse_block_stmt->BlockStatement,
             (
                 verify_lbrace,
                 many0(stmt_parse::parse_stmt),
@@ -233,7 +257,7 @@ mod program_parse {
                 parse_prefix_plus->Expr,
                 (
                     verify_plus,
-                    parse_prefix,
+                    parse_prefix_operand,
                 )=>|(_,e)|Expr::PrefixExpr(Prefix::Plus,Box::new(e))
             }
             /// prefix minus 解析
@@ -241,7 +265,7 @@ mod program_parse {
                 parse_prefix_minus->Expr,
                 (
                     verify_minus,
-                    parse_prefix,
+                    parse_prefix_operand,
                 )=>|(_,e)|Expr::PrefixExpr(Prefix::Minus,Box::new(e))
             }
             /// prefix plus 解析
@@ -249,10 +273,16 @@ mod program_parse {
                 parse_prefix_not->Expr,
                 (
                     verify_not,
-                    parse_prefix,
+                    parse_prefix_operand,
                 )=>|(_,e)|Expr::PrefixExpr(Prefix::Not,Box::new(e))
             }
 
+            /// 前缀操作符的操作数解析
+            /// 调用、索引、字段访问的优先级高于前缀操作符，形如 `!p.done` 解析为 `!(p.done)`
+            fn parse_prefix_operand(input: Tokens) -> IResult<Tokens, Expr> {
+                precedence_parse_expr(input, Precedence::PPrefix)
+            }
+
             /// 前缀表达式解析
             /// 形如`[+/-/!]expr`
             parsers! {
@@ -305,6 +335,33 @@ mod program_parse {
                 )=>|(_, _, p, _, b)| Expr::FnExpr {parameters: p,body: b}
             }
 
+            /// 解析 生成器函数表达式
+            /// 形如 `func * ( [ident,ident,..] ) { stmt,stmt,... }`
+            map_parser! {
+                parse_generator_expr->Expr,
+                (
+                    verify_function,
+                    verify_multipy,
+                    verify_lparen,
+                    ident_list_parse::parse_ident_list,
+                    verify_rparen,
+                    parse_block_stmt,
+                )=>|(_, _, _, p, _, b)| Expr::GeneratorExpr {parameters: p,body: b}
+            }
+
+            /// 解析 宏表达式
+            /// 形如 `macro ( [ident,ident,..] ) { stmt,stmt,... }`
+            map_parser! {
+                parse_macro_expr->Expr,
+                (
+                    verify_macro,
+                    verify_lparen,
+                    ident_list_parse::parse_ident_list,
+                    verify_rparen,
+                    parse_block_stmt,
+                )=>|(_, _, p, _, b)| Expr::MacroExpr {parameters: p,body: b}
+            }
+
             /// 解析 数组表达式
             /// 形如 `[ [expr,expr,..] ]`
             map_parser! {
@@ -345,6 +402,191 @@ mod program_parse {
                 }
             }
 
+            /// 解析 catch 子句
+            /// 形如 `catch ( ident ) { stmt,stmt,... }`
+            map_parser! {
+                parse_catch_clause->(Identifier, BlockStatement),
+                (
+                    verify_catch,
+                    verify_lparen,
+                    ident_parse,
+                    verify_rparen,
+                    parse_block_stmt,
+                )=>|(_, _, ident, _, block)| (ident, block)
+            }
+
+            /// 解析 try 表达式的各个部分
+            map_parser! {
+                parse_try_parts->Expr,
+                (
+                    verify_try,
+                    parse_block_stmt,
+                    opt(parse_catch_clause),
+                    opt(preceded(verify_finally, parse_block_stmt)),
+                )=>|(_, body, catch, finally)| Expr::TryExpr {
+                    body,
+                    catch,
+                    finally,
+                }
+            }
+
+            /// 解析 try表达式
+            /// 形如 `try { stmt,stmt,... } [catch ( ident ) { stmt,stmt,... }] [finally { stmt,stmt,... }]`
+            /// catch 与 finally 至少需要一个
+            fn parse_try_expr(input: Tokens) -> IResult<Tokens, Expr> {
+                verify(parse_try_parts, |expr| {
+                    !matches!(expr, Expr::TryExpr { catch: None, finally: None, .. })
+                })(input)
+            }
+
+            /// 解析 match 分支模式
+            mod pattern_parse {
+                use super::*;
+
+                /// 结构体模式的字段解析
+                /// 形如 `ident [: pattern]`，省略模式时绑定到同名标识符
+                map_parser! {
+                    parse_field_pattern->(Identifier, Pattern),
+                    (
+                        ident_parse,
+                        opt(preceded(verify_colon, parse_pattern)),
+                    )=>|(ident, pattern)| {
+                        let pattern = pattern.unwrap_or_else(|| Pattern::Binding(ident.clone()));
+                        (ident, pattern)
+                    }
+                }
+
+                /// 结构体模式解析
+                /// 形如 `ident { [ident [: pattern],...] }`
+                map_parser! {
+                    parse_struct_pattern->Pattern,
+                    (
+                        ident_parse,
+                        verify_lbrace,
+                        opt(tuple((
+                            parse_field_pattern,
+                            many0(preceded(verify_comma, parse_field_pattern)),
+                        ))),
+                        opt(verify_comma),
+                        verify_rbrace,
+                    )=>|(name, _, fields, _, _)| {
+                        let fields = fields
+                            .map(|(field, fields)| [&vec![field][..], &fields[..]].concat())
+                            .unwrap_or_default();
+                        Pattern::Struct(name, fields)
+                    }
+                }
+
+                /// 模式列表解析
+                /// 形如 `( [pattern,pattern,..] )`
+                map_parser! {
+                    parse_pattern_list->Vec<Pattern>,
+                    (
+                        verify_lparen,
+                        opt(tuple((
+                            parse_pattern,
+                            many0(preceded(verify_comma, parse_pattern)),
+                        ))),
+                        verify_rparen,
+                    )=>|(_, patterns, _)| patterns
+                        .map(|(pattern, patterns)| [&vec![pattern][..], &patterns[..]].concat())
+                        .unwrap_or_default()
+                }
+
+                /// 限定枚举名的变体模式解析
+                /// 形如 `ident . ident [( [pattern,...] )]`
+                map_parser! {
+                    parse_qualified_variant_pattern->Pattern,
+                    (
+                        ident_parse,
+                        verify_dot,
+                        ident_parse,
+                        opt(parse_pattern_list),
+                    )=>|(name, _, variant, patterns)| Pattern::Variant(Some(name), variant, patterns.unwrap_or_default())
+                }
+
+                /// 变体模式解析
+                /// 形如 `ident ( [pattern,...] )`
+                map_parser! {
+                    parse_variant_pattern->Pattern,
+                    (
+                        ident_parse,
+                        parse_pattern_list,
+                    )=>|(variant, patterns)| Pattern::Variant(None, variant, patterns)
+                }
+
+                /// 标识符模式解析，`_` 为通配模式
+                map_parser! {
+                    parse_ident_pattern->Pattern,
+                    (
+                        ident_parse,
+                    )=>|(Identifier(name), )| if name == "_" {
+                        Pattern::Wildcard
+                    } else {
+                        Pattern::Binding(Identifier(name))
+                    }
+                }
+
+                /// 字面量模式解析
+                map_parser! {
+                    parse_literal_pattern->Pattern,
+                    (
+                        literal_parse,
+                    )=>|(literal, )| Pattern::Literal(literal)
+                }
+
+                /// 模式解析
+                parsers! {
+                    pub parse_pattern->Pattern,
+                    (
+                        parse_struct_pattern,
+                        parse_qualified_variant_pattern,
+                        parse_variant_pattern,
+                        parse_ident_pattern,
+                        parse_literal_pattern,
+                    )
+                }
+            }
+
+            /// 解析 match 分支体
+            /// 形如 `{ stmt,stmt,... }` 或者 `expr`
+            fn parse_arm_body(input: Tokens) -> IResult<Tokens, BlockStatement> {
+                alt((
+                    parse_block_stmt,
+                    map(parse_expr, |expr| vec![Stmt::ExprStmt(expr)]),
+                ))(input)
+            }
+
+            /// 解析 match 分支
+            /// 形如 `pattern => body [,]`
+            map_parser! {
+                parse_match_arm->(Pattern, BlockStatement),
+                (
+                    pattern_parse::parse_pattern,
+                    verify_fat_arrow,
+                    parse_arm_body,
+                    opt(verify_comma),
+                )=>|(pattern, _, body, _)| (pattern, body)
+            }
+
+            /// 解析 match表达式
+            /// 形如 `match ( expr ) { pattern => body, ... }`
+            map_parser! {
+                parse_match_expr->Expr,
+                (
+                    verify_match,
+                    verify_lparen,
+                    parse_expr,
+                    verify_rparen,
+                    verify_lbrace,
+                    many0(parse_match_arm),
+                    verify_rbrace,
+                )=>|(_, _, subject, _, _, arms, _)| Expr::MatchExpr {
+                    subject: Box::new(subject),
+                    arms,
+                }
+            }
+
             /// 解析 具有prefix语义的表达式
             parsers! {
                 pub parse_prefix->Expr,
@@ -353,7 +595,11 @@ mod program_parse {
                     parse_literal_expr,
                     parse_prefix_expr,
                     parse_if_expr,
+                    parse_try_expr,
+                    parse_match_expr,
                     parse_func_expr,
+                    parse_generator_expr,
+                    parse_macro_expr,
                     parse_array_expr,
                     parse_hash_expr,
                     parse_paren_expr,
@@ -367,20 +613,22 @@ mod program_parse {
             /// 解析 调用表达式
             /// 形如 `left ( [expr,expr,...] )`
             /// 解析时，只解析 left 后面部分，left传递给返回的闭包
-            map_parser! {
-                pub parse_call_expr->impl FnOnce(Expr) -> Expr,
-                (
+            /// 调用位置记录为 `(` 的位置
+            pub fn parse_call_expr(input: Tokens) -> IResult<Tokens, impl FnOnce(Expr) -> Expr> {
+                let span = input.span();
+                map(tuple((
                     verify_lparen,
                     expr_list_parse::parse_expr_list,
                     verify_rparen
-                )=>|(_, arguments, _)| {
-                    |function:Expr| {
+                )), move |(_, arguments, _)| {
+                    move |function: Expr| {
                         Expr::CallExpr {
                             function: Box::new(function),
-                            arguments: arguments
+                            arguments,
+                            span,
                         }
                     }
-                }
+                })(input)
             }
 
             /// 解析 索引表达式
@@ -402,6 +650,24 @@ mod program_parse {
                 }
             }
 
+            /// 解析 字段访问表达式
+            /// 形如 `left . ident`
+            /// 解析时，只解析 left 后面部分，left传递给返回的闭包
+            map_parser! {
+                pub parse_field_expr->impl FnOnce(Expr) -> Expr,
+                (
+                    verify_dot,
+                    ident_parse,
+                )=>|(_, field)| {
+                    |object:Expr| {
+                        Expr::FieldExpr {
+                            object: Box::new(object),
+                            field,
+                        }
+                    }
+                }
+            }
+
             /// 解析 中缀表达式
             /// 匹配中缀操作符，将其映射为优先级以及Infix::Option
             /// 然后再及解析剩余部分得到 right
@@ -418,11 +684,11 @@ mod program_parse {
                     verify_minus,
                     verify_multipy,
                     verify_divide,
-                )), |next| precedences(&next.tokens[0]))(input).unwrap();
+                )), |next| precedences(&next.tokens[0]))(input)?;
                 if tokens.tokens.is_empty() {
                     Err(Err::Error(Error::new(input, ErrorKind::Tag)))
                 } else {
-                    let (tokens, right) = precedence_parse_expr(tokens, token_precedence).unwrap();
+                    let (tokens, right) = precedence_parse_expr(tokens, token_precedence)?;
                     Ok((tokens, |left: Expr| Expr::InfixExpr(token_opt.unwrap(), Box::new(left), Box::new(right))))
                 }
             }
@@ -442,6 +708,10 @@ mod program_parse {
                             let (tokens, expression) = parse_index_expr(input)?;
                             parse_infix(tokens, precedence, expression(left))
                         }
+                        Precedence::PField if precedence < Precedence::PField => {
+                            let (tokens, expression) = parse_field_expr(input)?;
+                            parse_infix(tokens, precedence, expression(left))
+                        }
                         ref next_precedence if precedence < *next_precedence => {
                             let (tokens, expression) = parse_infix_expr(input)?;
                             parse_infix(tokens, precedence, expression(left))
@@ -494,23 +764,165 @@ mod program_parse {
             )=>|(_, expr, _)| Stmt::ReturnStmt(expr)
         }
 
-        /// 多种语句表达式的匹配
-        /// 形如 `expr [;]`
+        /// throw stmt parse
+        /// 形如 `throw expr [;]`
         map_parser! {
-            parse_expr_stmt->Stmt,
+            parse_throw_stmt->Stmt,
             (
+                verify_throw,
                 parse_expr,
                 opt(verify_semicolon)
-            )=>|(expr, _)| Stmt::ExprStmt(expr)
+            )=>|(_, expr, _)| Stmt::ThrowStmt(expr)
+        }
+
+        /// 结构体方法解析
+        /// 形如 `fn ident ( [ident,ident,..] ) { stmt,stmt,... } [,|;]`
+        map_parser! {
+            parse_method->(Identifier, Expr),
+            (
+                verify_function,
+                ident_parse,
+                verify_lparen,
+                ident_list_parse::parse_ident_list,
+                verify_rparen,
+                parse_block_stmt,
+                opt(alt((verify_comma, verify_semicolon))),
+            )=>|(_, name, _, parameters, _, body, _)| (name, Expr::FnExpr { parameters, body })
+        }
+
+        /// struct stmt parse
+        /// 形如 `struct ident { [ident,ident,..] [,|;] [method ...] } [;]`
+        map_parser! {
+            parse_struct_stmt->Stmt,
+            (
+                verify_struct,
+                ident_parse,
+                verify_lbrace,
+                ident_list_parse::parse_ident_list,
+                opt(alt((verify_comma, verify_semicolon))),
+                many0(parse_method),
+                verify_rbrace,
+                opt(verify_semicolon),
+            )=>|(_, name, _, fields, _, methods, _, _)| Stmt::StructStmt { name, fields, methods }
+        }
+
+        /// 枚举变体解析
+        /// 形如 `ident [( [ident,ident,..] )]`
+        map_parser! {
+            parse_variant->(Identifier, Vec<Identifier>),
+            (
+                ident_parse,
+                opt(tuple((
+                    verify_lparen,
+                    ident_list_parse::parse_ident_list,
+                    verify_rparen,
+                ))),
+            )=>|(name, fields)| (name, fields.map(|(_, fields, _)| fields).unwrap_or_default())
+        }
+
+        /// enum stmt parse
+        /// 形如 `enum ident { [variant,variant,..] } [;]`
+        map_parser! {
+            parse_enum_stmt->Stmt,
+            (
+                verify_enum,
+                ident_parse,
+                verify_lbrace,
+                opt(tuple((
+                    parse_variant,
+                    many0(preceded(verify_comma, parse_variant)),
+                ))),
+                opt(verify_comma),
+                verify_rbrace,
+                opt(verify_semicolon),
+            )=>|(_, name, _, variants, _, _, _)| Stmt::EnumStmt {
+                name,
+                variants: variants
+                    .map(|(variant, variants)| [&vec![variant][..], &variants[..]].concat())
+                    .unwrap_or_default(),
+            }
+        }
+
+        /// yield stmt parse
+        /// 形如 `yield expr [;]`
+        map_parser! {
+            parse_yield_stmt->Stmt,
+            (
+                verify_yield,
+                parse_expr,
+                opt(verify_semicolon)
+            )=>|(_, expr, _)| Stmt::YieldStmt(expr)
+        }
+
+        /// for stmt parse
+        /// 形如 `for ( ident in expr ) { stmt,stmt,... } [;]`
+        map_parser! {
+            parse_for_stmt->Stmt,
+            (
+                verify_for,
+                verify_lparen,
+                ident_parse,
+                verify_in,
+                parse_expr,
+                verify_rparen,
+                parse_block_stmt,
+                opt(verify_semicolon),
+            )=>|(_, _, ident, _, iterable, _, body, _)| Stmt::ForStmt { ident, iterable, body }
+        }
+
+        /// import stmt parse
+        /// 形如 `import string as ident [;]`
+        map_parser! {
+            parse_import_stmt->Stmt,
+            (
+                verify_import,
+                string_parse,
+                verify_as,
+                ident_parse,
+                opt(verify_semicolon),
+            )=>|(_, path, _, alias, _)| Stmt::ImportStmt(path, alias)
+        }
+
+        /// export stmt parse
+        /// 形如 `export let_stmt|struct_stmt|enum_stmt`
+        map_parser! {
+            parse_export_stmt->Stmt,
+            (
+                verify_export,
+                alt((parse_let_stmt, parse_struct_stmt, parse_enum_stmt)),
+            )=>|(_, stmt)| Stmt::ExportStmt(Box::new(stmt))
+        }
+
+        /// 表达式语句以及字段赋值语句的匹配
+        /// 形如 `expr [;]` 或者 `expr.ident = expr [;]`
+        fn parse_expr_stmt(input: Tokens) -> IResult<Tokens, Stmt> {
+            let (rest, (expr, value, _)) = tuple((
+                parse_expr,
+                opt(preceded(verify_assign, parse_expr)),
+                opt(verify_semicolon),
+            ))(input)?;
+            match value {
+                None => Ok((rest, Stmt::ExprStmt(expr))),
+                Some(value) if matches!(expr, Expr::FieldExpr { .. }) => Ok((rest, Stmt::AssignStmt(expr, value))),
+                // 只有字段可以被赋值
+                Some(_) => Err(Err::Error(Error::new(input, ErrorKind::Verify))),
+            }
         }
 
         /// 匹配单个stmt
-        /// 依次匹配三种 stmt即 LetStmt，ReturnStmt,ExprStmt
+        /// 依次匹配 LetStmt，ReturnStmt，ThrowStmt，YieldStmt，ForStmt，StructStmt，EnumStmt，ImportStmt，ExportStmt，ExprStmt
         parsers! {
             pub parse_stmt->Stmt,
             (
                 parse_let_stmt,
                 parse_return_stmt,
+                parse_throw_stmt,
+                parse_yield_stmt,
+                parse_for_stmt,
+                parse_struct_stmt,
+                parse_enum_stmt,
+                parse_import_stmt,
+                parse_export_stmt,
                 parse_expr_stmt,
             )
         }
@@ -532,6 +944,19 @@ impl Parser {
     pub fn parsing(tokens: Tokens) -> IResult<Tokens, Program> {
         program_parse::parse_program(tokens)
     }
+
+    /// 对源码进行词法分析与语法分析，失败时返回无法解析的 token 的位置
+    pub fn parse_source(source: &str) -> Result<Program, Span> {
+        let (tokens, spans) = match crate::lexer::Lexer::lexing_with_spans(source.as_bytes()) {
+            Ok((_, lexed)) => lexed,
+            Err(_) => return Err(Span::default()),
+        };
+        match Parser::parsing(Tokens::with_spans(&tokens, &spans)) {
+            Ok((_, program)) => Ok(program),
+            Err(Err::Error(e)) | Err(Err::Failure(e)) => Err(e.input.span()),
+            Err(Err::Incomplete(_)) => Err(spans.last().copied().unwrap_or_default()),
+        }
+    }
 }
 
 
@@ -934,6 +1359,7 @@ mod tests {
                     Expr::LiteralExpr(Literal::IntLiteral(2)),
                     Expr::LiteralExpr(Literal::IntLiteral(3)),
                 ],
+                span: Span::default(),
             }),
             Stmt::ExprStmt(Expr::CallExpr {
                 function: Box::new(Expr::IdentExpr(Identifier("add".to_owned()))),
@@ -953,6 +1379,7 @@ mod tests {
                             Box::new(Expr::LiteralExpr(Literal::IntLiteral(4))),
                             Box::new(Expr::LiteralExpr(Literal::IntLiteral(5))),
                         )],
+                        span: Span::default(),
                     },
                     Expr::CallExpr {
                         function: Box::new(Expr::IdentExpr(Identifier("add".to_owned()))),
@@ -964,8 +1391,10 @@ mod tests {
                                 Box::new(Expr::LiteralExpr(Literal::IntLiteral(8))),
                             ),
                         ],
+                        span: Span::default(),
                     },
                 ],
+                span: Span::default(),
             }),
             Stmt::ExprStmt(Expr::CallExpr {
                 function: Box::new(Expr::FnExpr {
@@ -980,6 +1409,7 @@ mod tests {
                     Expr::LiteralExpr(Literal::IntLiteral(1)),
                     Expr::LiteralExpr(Literal::IntLiteral(2)),
                 ],
+                span: Span::default(),
             }),
         ];
 
@@ -1090,15 +1520,15 @@ mod tests {
 
         let program: Program = vec![Stmt::ExprStmt(Expr::HashExpr(vec![
             (
-                Literal::StringLiteral("one".to_owned()),
+                Expr::LiteralExpr(Literal::StringLiteral("one".to_owned())),
                 Expr::LiteralExpr(Literal::IntLiteral(1)),
             ),
             (
-                Literal::StringLiteral("two".to_owned()),
+                Expr::LiteralExpr(Literal::StringLiteral("two".to_owned())),
                 Expr::LiteralExpr(Literal::IntLiteral(2)),
             ),
             (
-                Literal::StringLiteral("three".to_owned()),
+                Expr::LiteralExpr(Literal::StringLiteral("three".to_owned())),
                 Expr::LiteralExpr(Literal::IntLiteral(3)),
             ),
         ]))];
@@ -1109,15 +1539,15 @@ mod tests {
 
         let program: Program = vec![Stmt::ExprStmt(Expr::HashExpr(vec![
             (
-                Literal::IntLiteral(4),
+                Expr::LiteralExpr(Literal::IntLiteral(4)),
                 Expr::LiteralExpr(Literal::IntLiteral(1)),
             ),
             (
-                Literal::IntLiteral(5),
+                Expr::LiteralExpr(Literal::IntLiteral(5)),
                 Expr::LiteralExpr(Literal::IntLiteral(2)),
             ),
             (
-                Literal::IntLiteral(6),
+                Expr::LiteralExpr(Literal::IntLiteral(6)),
                 Expr::LiteralExpr(Literal::IntLiteral(3)),
             ),
         ]))];
@@ -1128,11 +1558,11 @@ mod tests {
 
         let program: Program = vec![Stmt::ExprStmt(Expr::HashExpr(vec![
             (
-                Literal::BoolLiteral(true),
+                Expr::LiteralExpr(Literal::BoolLiteral(true)),
                 Expr::LiteralExpr(Literal::IntLiteral(1)),
             ),
             (
-                Literal::BoolLiteral(false),
+                Expr::LiteralExpr(Literal::BoolLiteral(false)),
                 Expr::LiteralExpr(Literal::IntLiteral(2)),
             ),
         ]))];
@@ -1143,7 +1573,7 @@ mod tests {
 
         let program: Program = vec![Stmt::ExprStmt(Expr::HashExpr(vec![
             (
-                Literal::StringLiteral("one".to_owned()),
+                Expr::LiteralExpr(Literal::StringLiteral("one".to_owned())),
                 Expr::InfixExpr(
                     Infix::Plus,
                     Box::new(Expr::LiteralExpr(Literal::IntLiteral(0))),
@@ -1151,7 +1581,7 @@ mod tests {
                 ),
             ),
             (
-                Literal::StringLiteral("two".to_owned()),
+                Expr::LiteralExpr(Literal::StringLiteral("two".to_owned())),
                 Expr::InfixExpr(
                     Infix::Minus,
                     Box::new(Expr::LiteralExpr(Literal::IntLiteral(10))),
@@ -1159,7 +1589,7 @@ mod tests {
                 ),
             ),
             (
-                Literal::StringLiteral("three".to_owned()),
+                Expr::LiteralExpr(Literal::StringLiteral("three".to_owned())),
                 Expr::InfixExpr(
                     Infix::Divide,
                     Box::new(Expr::LiteralExpr(Literal::IntLiteral(15))),
@@ -1170,4 +1600,248 @@ mod tests {
 
         assert_input_with_program(input, program);
     }
+
+    #[test]
+    fn try_expr() {
+        let input = "try { throw \"boom\"; } catch (e) { e } finally { 1 }\
+             try { 2 } finally { 3 }\
+            "
+            .as_bytes();
+
+        let program: Program = vec![
+            Stmt::ExprStmt(Expr::TryExpr {
+                body: vec![Stmt::ThrowStmt(Expr::LiteralExpr(Literal::StringLiteral("boom".to_owned())))],
+                catch: Some((
+                    Identifier("e".to_owned()),
+                    vec![Stmt::ExprStmt(Expr::IdentExpr(Identifier("e".to_owned())))],
+                )),
+                finally: Some(vec![Stmt::ExprStmt(Expr::LiteralExpr(Literal::IntLiteral(1)))]),
+            }),
+            Stmt::ExprStmt(Expr::TryExpr {
+                body: vec![Stmt::ExprStmt(Expr::LiteralExpr(Literal::IntLiteral(2)))],
+                catch: None,
+                finally: Some(vec![Stmt::ExprStmt(Expr::LiteralExpr(Literal::IntLiteral(3)))]),
+            }),
+        ];
+
+        assert_input_with_program(input, program);
+
+        let (_, r) = Lexer::lexing(&b"try { 1 }"[..]).unwrap();
+        assert!(Parser::parsing(Tokens::new(&r)).is_err());
+    }
+
+    #[test]
+    fn struct_and_match() {
+        let input = "struct Point { x, y; fn sum(self) { self.x + self.y } }\
+             p.x = 1;\
+             match (p) { Point { x: 0, y } => y, _ => { 1 } }\
+            "
+            .as_bytes();
+
+        let ident = |name: &str| Identifier(name.to_owned());
+        let field = |object: &str, name: &str| Expr::FieldExpr {
+            object: Box::new(Expr::IdentExpr(ident(object))),
+            field: ident(name),
+        };
+        let program: Program = vec![
+            Stmt::StructStmt {
+                name: ident("Point"),
+                fields: vec![ident("x"), ident("y")],
+                methods: vec![(
+                    ident("sum"),
+                    Expr::FnExpr {
+                        parameters: vec![ident("self")],
+                        body: vec![Stmt::ExprStmt(Expr::InfixExpr(
+                            Infix::Plus,
+                            Box::new(field("self", "x")),
+                            Box::new(field("self", "y")),
+                        ))],
+                    },
+                )],
+            },
+            Stmt::AssignStmt(field("p", "x"), Expr::LiteralExpr(Literal::IntLiteral(1))),
+            Stmt::ExprStmt(Expr::MatchExpr {
+                subject: Box::new(Expr::IdentExpr(ident("p"))),
+                arms: vec![
+                    (
+                        Pattern::Struct(ident("Point"), vec![
+                            (ident("x"), Pattern::Literal(Literal::IntLiteral(0))),
+                            (ident("y"), Pattern::Binding(ident("y"))),
+                        ]),
+                        vec![Stmt::ExprStmt(Expr::IdentExpr(ident("y")))],
+                    ),
+                    (Pattern::Wildcard, vec![Stmt::ExprStmt(Expr::LiteralExpr(Literal::IntLiteral(1)))]),
+                ],
+            }),
+        ];
+
+        assert_input_with_program(input, program);
+
+        compare_inputs("a.b.c(1)[2]".as_bytes(), "((a.b).c)(1)[2]".as_bytes());
+        compare_inputs("!p.done".as_bytes(), "!(p.done)".as_bytes());
+        compare_inputs("-f(1) * 2".as_bytes(), "(-(f(1))) * 2".as_bytes());
+
+        // only fields can be assigned
+        let (_, r) = Lexer::lexing(&b"x = 1"[..]).unwrap();
+        assert!(Parser::parsing(Tokens::new(&r)).is_err());
+    }
+
+    #[test]
+    fn enum_and_variant_patterns() {
+        let input = "enum Shape { Circle(r), Rect(w, h), Empty };\
+             match (s) { Circle(r) => r, Shape.Rect(w, _) => w, Shape.Empty => 0 }\
+            "
+            .as_bytes();
+
+        let ident = |name: &str| Identifier(name.to_owned());
+        let program: Program = vec![
+            Stmt::EnumStmt {
+                name: ident("Shape"),
+                variants: vec![
+                    (ident("Circle"), vec![ident("r")]),
+                    (ident("Rect"), vec![ident("w"), ident("h")]),
+                    (ident("Empty"), vec![]),
+                ],
+            },
+            Stmt::ExprStmt(Expr::MatchExpr {
+                subject: Box::new(Expr::IdentExpr(ident("s"))),
+                arms: vec![
+                    (
+                        Pattern::Variant(None, ident("Circle"), vec![Pattern::Binding(ident("r"))]),
+                        vec![Stmt::ExprStmt(Expr::IdentExpr(ident("r")))],
+                    ),
+                    (
+                        Pattern::Variant(Some(ident("Shape")), ident("Rect"), vec![Pattern::Binding(ident("w")), Pattern::Wildcard]),
+                        vec![Stmt::ExprStmt(Expr::IdentExpr(ident("w")))],
+                    ),
+                    (
+                        Pattern::Variant(Some(ident("Shape")), ident("Empty"), vec![]),
+                        vec![Stmt::ExprStmt(Expr::LiteralExpr(Literal::IntLiteral(0)))],
+                    ),
+                ],
+            }),
+        ];
+
+        assert_input_with_program(input, program);
+    }
+
+    #[test]
+    fn macro_expr() {
+        let input = "macro(x, y) { quote(unquote(x) + y) }".as_bytes();
+
+        let program: Program = vec![Stmt::ExprStmt(Expr::MacroExpr {
+            parameters: vec![Identifier("x".to_owned()), Identifier("y".to_owned())],
+            body: vec![Stmt::ExprStmt(Expr::CallExpr {
+                function: Box::new(Expr::IdentExpr(Identifier("quote".to_owned()))),
+                arguments: vec![Expr::InfixExpr(
+                    Infix::Plus,
+                    Box::new(Expr::CallExpr {
+                        function: Box::new(Expr::IdentExpr(Identifier("unquote".to_owned()))),
+                        arguments: vec![Expr::IdentExpr(Identifier("x".to_owned()))],
+                        span: Span::default(),
+                    }),
+                    Box::new(Expr::IdentExpr(Identifier("y".to_owned()))),
+                )],
+                span: Span::default(),
+            })],
+        })];
+
+        assert_input_with_program(input, program);
+    }
+
+    #[test]
+    fn import_and_export() {
+        let input = "import \"lib/util.lang\" as util; export let x = util.y; export enum E { A }".as_bytes();
+
+        let program: Program = vec![
+            Stmt::ImportStmt("lib/util.lang".to_owned(), Identifier("util".to_owned())),
+            Stmt::ExportStmt(Box::new(Stmt::LetStmt(
+                Identifier("x".to_owned()),
+                Expr::FieldExpr {
+                    object: Box::new(Expr::IdentExpr(Identifier("util".to_owned()))),
+                    field: Identifier("y".to_owned()),
+                },
+            ))),
+            Stmt::ExportStmt(Box::new(Stmt::EnumStmt {
+                name: Identifier("E".to_owned()),
+                variants: vec![(Identifier("A".to_owned()), vec![])],
+            })),
+        ];
+
+        assert_input_with_program(input, program);
+
+        // 只有声明可以被导出
+        let (_, tokens) = Lexer::lexing("export 1".as_bytes()).unwrap();
+        assert!(Parser::parsing(Tokens::new(&tokens)).is_err());
+    }
+
+    #[test]
+    fn generators_and_for() {
+        let input = "let g = fn*(n) { yield n; }; for (x in g(1)) { x; }".as_bytes();
+
+        let program: Program = vec![
+            Stmt::LetStmt(
+                Identifier("g".to_owned()),
+                Expr::GeneratorExpr {
+                    parameters: vec![Identifier("n".to_owned())],
+                    body: vec![Stmt::YieldStmt(Expr::IdentExpr(Identifier("n".to_owned())))],
+                },
+            ),
+            Stmt::ForStmt {
+                ident: Identifier("x".to_owned()),
+                iterable: Expr::CallExpr {
+                    function: Box::new(Expr::IdentExpr(Identifier("g".to_owned()))),
+                    arguments: vec![Expr::LiteralExpr(Literal::IntLiteral(1))],
+                    span: Span::default(),
+                },
+                body: vec![Stmt::ExprStmt(Expr::IdentExpr(Identifier("x".to_owned())))],
+            },
+        ];
+
+        assert_input_with_program(input, program);
+
+        // `fn` 后面的 `*` 不是乘法, `for` 需要 `in`
+        for input in ["fn * 2", "for (x of y) { x }", "for x in y { x }"] {
+            let (_, tokens) = Lexer::lexing(input.as_bytes()).unwrap();
+            assert!(Parser::parsing(Tokens::new(&tokens)).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn display_round_trip() {
+        // 语法树输出的源码可以重新解析为同样的语法树
+        let input = "let a = -1 + 2 * 3; \
+            let s = \"q\\\"uote\"; \
+            let f = fn(x) { if (x > 1.5) { return x; } else { [x, {\"k\": x}][0] } }; \
+            let m = macro(e) { quote(unquote(e) + 1) }; \
+            struct P { x, y; fn len(self) { self.x } } \
+            enum E { A(v), B } \
+            p.x = try { throw f(1); } catch (e) { e } finally { 0 }; \
+            match (p) { P { x: 1 } => { 1 } E.A(_) => { 2 } v => { v } } \
+            import \"util.lang\" as util; \
+            export struct Q { x } \
+            let g = fn*(n) { for (x in n) { if (x) { yield x; } } }; \
+            for (c in \"ab\") { c; }";
+        let parse = |input: &str| {
+            let (_, tokens) = Lexer::lexing(input.as_bytes()).unwrap();
+            let (_, program) = Parser::parsing(Tokens::new(&tokens)).unwrap();
+            program
+        };
+        let program = parse(input);
+        assert_eq!(program.len(), 12);
+        let source = program.iter().map(ToString::to_string).collect::<Vec<_>>().join(" ");
+        assert_eq!(parse(&source), program);
+    }
+
+    #[test]
+    fn parse_source() {
+        assert_eq!(
+            Parser::parse_source("let a = 1;"),
+            Ok(vec![Stmt::LetStmt(Identifier("a".to_owned()), Expr::LiteralExpr(Literal::IntLiteral(1)))])
+        );
+        // 无法解析时返回出错语句的位置，悬空的中缀操作符不会 panic
+        assert_eq!(Parser::parse_source("1 +"), Err(Span { line: 1, column: 1 }));
+        assert_eq!(Parser::parse_source("let a = 1;\n  a *"), Err(Span { line: 2, column: 3 }));
+        assert_eq!(Parser::parse_source("let a = 1; )"), Err(Span { line: 1, column: 12 }));
+    }
 }
@@ -1,3 +1,4 @@
+use std::fmt::{Display, Formatter};
 use std::iter::Enumerate;
 use std::ops::{Range, RangeFrom};
 use std::ptr::eq;
@@ -19,6 +20,8 @@ pub enum Token {
     StringLiteral(String),
     // integer literals
     IntLiteral(i64),
+    // float literals, e.g. 1.5
+    FloatLiteral(Float),
     // boolean literals，True/False
     BoolLiteral(bool),
 
@@ -47,6 +50,8 @@ pub enum Token {
     LessThan,
     // !
     Not,
+    // =>
+    FatArrow,
 
     // reserved words
     // func
@@ -63,6 +68,34 @@ pub enum Token {
     TRUE,
     // false
     FALSE,
+    // try
+    Try,
+    // catch
+    Catch,
+    // finally
+    Finally,
+    // throw
+    Throw,
+    // struct
+    Struct,
+    // match
+    Match,
+    // enum
+    Enum,
+    // macro
+    Macro,
+    // import
+    Import,
+    // export
+    Export,
+    // as
+    As,
+    // for
+    For,
+    // in
+    In,
+    // yield
+    Yield,
 
     // punctuations
     // delimiters
@@ -70,6 +103,8 @@ pub enum Token {
     Comma,
     // :
     Colon,
+    // .
+    Dot,
     // ;
     SemiColon,
     // (
@@ -86,16 +121,44 @@ pub enum Token {
     RBracket,
 }
 
+/// 浮点数字面量的值，按位比较，因此 token 和语法树可以实现 `Eq`
+#[derive(Debug, Clone, Copy)]
+pub struct Float(pub f64);
+
+impl PartialEq for Float {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+impl Eq for Float {}
+
+/// token 在源码中的位置，行列均从1开始
+/// 没有位置信息时为默认值 `0:0`
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
 /// 由于在使用nom时，需要实现 InputLength,InputTake,InputIter 这三个trait
 /// 因此对 Vec[token] 进行包装
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct Tokens<'a> {
     pub tokens: &'a [Token], // 不需要所有权
+    // 与 tokens 一一对应的位置信息，可以为空
+    pub spans: &'a [Span],
 }
 
 impl<'a> Slice<RangeFrom<usize>> for Tokens<'a> {
     fn slice(&self, range: RangeFrom<usize>) -> Self {
-        self.slice(range.start..self.tokens.len() - 0usize)
+        self.slice(range.start..self.tokens.len())
     }
 }
 
@@ -103,13 +166,32 @@ impl<'a> Slice<Range<usize>> for Tokens<'a> {
     fn slice(&self, range: Range<usize>) -> Self {
         Tokens {
             tokens: self.tokens.slice(range.clone()),
+            spans: self.spans_slice(range),
         }
     }
 }
 
 impl<'a> Tokens<'a> {
     pub fn new(tokens: &'a [Token]) -> Self {
-        Tokens { tokens }
+        Tokens { tokens, spans: &[] }
+    }
+
+    /// 带位置信息的 tokens，spans 需要与 tokens 等长
+    pub fn with_spans(tokens: &'a [Token], spans: &'a [Span]) -> Self {
+        Tokens { tokens, spans }
+    }
+
+    /// 第一个 token 的位置
+    pub fn span(&self) -> Span {
+        self.spans.first().copied().unwrap_or_default()
+    }
+
+    fn spans_slice(&self, range: Range<usize>) -> &'a [Span] {
+        if self.spans.is_empty() {
+            self.spans
+        } else {
+            &self.spans[range]
+        }
     }
 }
 
@@ -123,11 +205,17 @@ impl<'a> InputTake for Tokens<'a> {
     fn take(&self, count: usize) -> Self {
         Tokens {
             tokens: &self.tokens[0..count],
+            spans: self.spans_slice(0..count),
         }
     }
     fn take_split(&self, count: usize) -> (Self, Self) {
         let (prefix, suffix) = self.tokens.split_at(count);
-        (Tokens { tokens: suffix }, Tokens { tokens: prefix })
+        let (prefix_spans, suffix_spans) = if self.spans.is_empty() {
+            (self.spans, self.spans)
+        } else {
+            self.spans.split_at(count)
+        };
+        (Tokens { tokens: suffix, spans: suffix_spans }, Tokens { tokens: prefix, spans: prefix_spans })
     }
 }
 
@@ -3,6 +3,8 @@ extern crate nom;
 extern crate rustyline;
 extern crate rustyline_derive;
 
+use lang_lib::evaluator::capabilities::Capabilities;
+use lang_lib::evaluator::error::{exit_status, RuntimeError};
 use lang_lib::evaluator::*;
 use lang_lib::lexer::*;
 use lang_lib::parser::*;
@@ -17,6 +19,8 @@ use rustyline::validate::{self, MatchingBracketValidator, Validator};
 use rustyline::{CompletionType, Config, Context, EditMode, Editor};
 use rustyline_derive::Helper;
 use std::borrow::Cow::{self, Borrowed, Owned};
+use std::path::{Path, PathBuf};
+use std::{env, fs, process};
 
 #[derive(Helper)]
 struct MyHelper {
@@ -86,7 +90,66 @@ impl Validator for MyHelper {
     }
 }
 
+/// directories of the `LANG_PATH` environment variable, where imported modules are searched
+fn module_path() -> Vec<PathBuf> {
+    env::var_os("LANG_PATH")
+        .map(|paths| env::split_paths(&paths).collect())
+        .unwrap_or_default()
+}
+
+/// a new evaluator, the prelude is left out with `--no-prelude`
+fn new_evaluator(prelude: bool) -> Evaluator {
+    if prelude {
+        Evaluator::new()
+    } else {
+        Evaluator::without_prelude()
+    }
+}
+
+/// run a script file with its arguments, the result is the exit status of the process
+fn run_script(path: &str, args: Vec<String>, prelude: bool) -> i32 {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            return 1;
+        }
+    };
+    let program = match Parser::parse_source(&source) {
+        Ok(program) => program,
+        Err(span) => {
+            eprintln!("{}: syntax error at {}", path, span);
+            return 1;
+        }
+    };
+    // scripts run with the access of the user in the current directory
+    let cwd = env::current_dir().unwrap_or_default();
+    let mut evaluator = new_evaluator(prelude);
+    evaluator.set_capabilities(Capabilities {
+        read_roots: vec![cwd.clone()],
+        write_roots: vec![cwd],
+        stdin: true,
+        env: true,
+    });
+    evaluator.set_args(args);
+    evaluator.set_main_file(Path::new(path));
+    evaluator.set_module_path(module_path());
+    let result = evaluator.eval_program(program);
+    match result {
+        Err(ref err) if !matches!(err.error, RuntimeError::Exit(_)) => eprintln!("{}", err),
+        _ => {}
+    }
+    exit_status(&result)
+}
+
 fn main() -> rustyline::Result<()> {
+    // `lang_repl_exe [--no-prelude] [script.lang args...]` runs a script instead of the repl
+    let mut args = env::args().skip(1).peekable();
+    let prelude = args.next_if(|arg| arg == "--no-prelude").is_none();
+    if let Some(path) = args.next() {
+        process::exit(run_script(&path, args.collect(), prelude));
+    }
+
     let config = Config::builder()
         .history_ignore_space(true) // 保存以空格开始的行
         .completion_type(CompletionType::List) // 显示匹配列表
@@ -110,7 +173,9 @@ fn main() -> rustyline::Result<()> {
     println!("Press Ctrl-D or enter \"quit\" to exit.");
     println!();
 
-    let mut evaluator = Evaluator::new();
+    // the repl imports modules relative to the current directory
+    let mut evaluator = new_evaluator(prelude);
+    evaluator.set_module_path([vec![env::current_dir().unwrap_or_default()], module_path()].concat());
     let mut count = 1;
 
     loop {
@@ -121,16 +186,16 @@ fn main() -> rustyline::Result<()> {
         match readline {
             Ok(line) => {
                 rl.add_history_entry(line.as_str());
-                let lex_tokens = Lexer::lexing(line.as_bytes());
+                let lex_tokens = Lexer::lexing_with_spans(line.as_bytes());
                 match lex_tokens {
-                    Ok((_, r)) => {
+                    Ok((_, (r, spans))) => {
                         println!("{:?}", r);
-                        let parsed = Parser::parsing(Tokens::new(&r));
+                        let parsed = Parser::parsing(Tokens::with_spans(&r, &spans));
                         match parsed {
-                            Ok((_, program)) => {
-                                let eval = evaluator.evaluation(program);
-                                println!("{:?}", eval.clone());
-                            }
+                            Ok((_, program)) => match evaluator.eval_program(program) {
+                                Ok(eval) => println!("{:?}", eval),
+                                Err(err) => println!("{}", err),
+                            },
                             Err(Err::Error(_)) => println!("Parser error"),
                             Err(Err::Failure(_)) => println!("Parser failure"),
                             Err(Err::Incomplete(_)) => println!("Incomplete parsing"),